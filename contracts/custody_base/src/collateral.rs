@@ -12,6 +12,7 @@ use cosmwasm_std::{
 use cw20::Cw20ExecuteMsg;
 use moneymarket::custody::{BorrowerResponse, BorrowersResponse};
 use moneymarket::liquidation::Cw20HookMsg as LiquidationCw20HookMsg;
+use moneymarket::pause::{assert_not_paused, PauseAction};
 use terra_cosmwasm::TerraMsgWrapper;
 
 /// Deposit new collateral
@@ -21,6 +22,8 @@ pub fn deposit_collateral(
     borrower: Addr,
     amount: Uint256,
 ) -> Result<Response<TerraMsgWrapper>, ContractError> {
+    assert_not_paused(deps.storage, PauseAction::Deposit)?;
+
     let borrower_raw = deps.api.addr_canonicalize(borrower.as_str())?;
     let mut borrower_info: BorrowerInfo = read_borrower_info(deps.storage, &borrower_raw);
 
@@ -44,6 +47,8 @@ pub fn withdraw_collateral(
     info: MessageInfo,
     amount: Option<Uint256>,
 ) -> Result<Response<TerraMsgWrapper>, ContractError> {
+    assert_not_paused(deps.storage, PauseAction::Unlock)?;
+
     let config: Config = read_config(deps.storage)?;
 
     let borrower = info.sender;
@@ -158,6 +163,8 @@ pub fn liquidate_collateral(
     borrower: Addr,
     amount: Uint256,
 ) -> Result<Response<TerraMsgWrapper>, ContractError> {
+    assert_not_paused(deps.storage, PauseAction::Liquidate)?;
+
     let config: Config = read_config(deps.storage)?;
     if deps.api.addr_canonicalize(info.sender.as_str())? != config.overseer_contract {
         return Err(ContractError::Unauthorized {});
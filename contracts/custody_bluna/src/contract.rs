@@ -1,8 +1,8 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    attr, from_binary, to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response,
-    StdResult,
+    attr, from_binary, to_binary, Addr, Binary, CanonicalAddr, Deps, DepsMut, Env, MessageInfo,
+    Reply, Response, StdResult,
 };
 
 use crate::collateral::{
@@ -13,13 +13,22 @@ use crate::distribution::{distribute_hook, distribute_rewards, swap_to_stable_de
 use crate::error::ContractError;
 use crate::state::{read_config, store_config, Config};
 
+use cw2::set_contract_version;
 use cw20::Cw20ReceiveMsg;
 use moneymarket::common::optional_addr_validate;
 use moneymarket::custody::{
     ConfigResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg,
 };
+use moneymarket::migration::{assert_legacy, assert_upgrade};
+use moneymarket::ownership::{
+    claim_ownership, drop_ownership_proposal, propose_new_owner, query_ownership_proposal,
+};
+use moneymarket::pause::{pause, query_pause_info, unpause, update_guardian};
 use terra_cosmwasm::TerraMsgWrapper;
 
+pub const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
+pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
 pub const CLAIM_REWARDS_OPERATION: u64 = 1u64;
 pub const SWAP_TO_STABLE_OPERATION: u64 = 2u64;
 
@@ -30,6 +39,8 @@ pub fn instantiate(
     _info: MessageInfo,
     msg: InstantiateMsg,
 ) -> StdResult<Response> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
     let config = Config {
         owner: deps.api.addr_canonicalize(&msg.owner)?,
         overseer_contract: deps.api.addr_canonicalize(&msg.overseer_contract)?,
@@ -56,17 +67,43 @@ pub fn execute(
     match msg {
         ExecuteMsg::Receive(msg) => receive_cw20(deps, info, msg),
         ExecuteMsg::UpdateConfig {
-            owner,
             liquidation_contract,
         } => {
             let api = deps.api;
             update_config(
                 deps,
                 info,
-                optional_addr_validate(api, owner)?,
                 optional_addr_validate(api, liquidation_contract)?,
             )
         }
+        ExecuteMsg::ProposeNewOwner { owner, expires_in } => {
+            let config: Config = read_config(deps.storage)?;
+            Ok(propose_new_owner(
+                deps,
+                env,
+                info,
+                config.owner,
+                owner,
+                expires_in,
+            )?)
+        }
+        ExecuteMsg::DropOwnershipProposal {} => {
+            let config: Config = read_config(deps.storage)?;
+            Ok(drop_ownership_proposal(deps, info, config.owner)?)
+        }
+        ExecuteMsg::ClaimOwnership {} => Ok(claim_ownership(deps, env, info, update_owner)?),
+        ExecuteMsg::UpdateGuardian { guardian } => {
+            let config: Config = read_config(deps.storage)?;
+            Ok(update_guardian(deps, info, config.owner, guardian)?)
+        }
+        ExecuteMsg::Pause { actions } => {
+            let config: Config = read_config(deps.storage)?;
+            Ok(pause(deps, info, config.owner, actions)?)
+        }
+        ExecuteMsg::Unpause { actions } => {
+            let config: Config = read_config(deps.storage)?;
+            Ok(unpause(deps, info, config.owner, actions)?)
+        }
         ExecuteMsg::LockCollateral { borrower, amount } => {
             let borrower_addr = deps.api.addr_validate(&borrower)?;
             lock_collateral(deps, info, borrower_addr, amount)
@@ -129,7 +166,6 @@ pub fn receive_cw20(
 pub fn update_config(
     deps: DepsMut,
     info: MessageInfo,
-    owner: Option<Addr>,
     liquidation_contract: Option<Addr>,
 ) -> Result<Response<TerraMsgWrapper>, ContractError> {
     let mut config: Config = read_config(deps.storage)?;
@@ -138,10 +174,6 @@ pub fn update_config(
         return Err(ContractError::Unauthorized {});
     }
 
-    if let Some(owner) = owner {
-        config.owner = deps.api.addr_canonicalize(owner.as_str())?;
-    }
-
     if let Some(liquidation_contract) = liquidation_contract {
         config.liquidation_contract = deps.api.addr_canonicalize(liquidation_contract.as_str())?;
     }
@@ -150,10 +182,18 @@ pub fn update_config(
     Ok(Response::new().add_attributes(vec![attr("action", "update_config")]))
 }
 
+pub fn update_owner(deps: DepsMut, owner: CanonicalAddr) -> StdResult<()> {
+    let mut config: Config = read_config(deps.storage)?;
+    config.owner = owner;
+    store_config(deps.storage, &config)
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::OwnershipProposal {} => to_binary(&query_ownership_proposal(deps)?),
+        QueryMsg::PauseInfo {} => to_binary(&query_pause_info(deps)?),
         QueryMsg::Borrower { address } => {
             let addr = deps.api.addr_validate(&address)?;
             to_binary(&query_borrower(deps, addr)?)
@@ -190,6 +230,13 @@ pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
+pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> StdResult<Response> {
+    match msg {
+        MigrateMsg::FromLegacy {} => assert_legacy(deps.storage)?,
+        MigrateMsg::Upgrade {} => assert_upgrade(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?,
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
     Ok(Response::default())
 }
@@ -80,7 +80,6 @@ fn update_config() {
     let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
 
     let msg = ExecuteMsg::UpdateConfig {
-        owner: Some("owner2".to_string()),
         liquidation_contract: Some("liquidation2".to_string()),
     };
     let info = mock_info("owner", &[]);
@@ -88,7 +87,7 @@ fn update_config() {
 
     let query_res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
     let config_res: ConfigResponse = from_binary(&query_res).unwrap();
-    assert_eq!("owner2".to_string(), config_res.owner);
+    assert_eq!("owner".to_string(), config_res.owner);
     assert_eq!("bluna".to_string(), config_res.collateral_token);
     assert_eq!("overseer".to_string(), config_res.overseer_contract);
     assert_eq!("market".to_string(), config_res.market_contract);
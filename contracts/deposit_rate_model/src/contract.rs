@@ -0,0 +1,210 @@
+use crate::error::ContractError;
+use crate::state::{read_config, store_config, Config};
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::{
+    to_binary, Binary, CanonicalAddr, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
+};
+use cw2::set_contract_version;
+use moneymarket::common::SECONDS_PER_YEAR;
+use moneymarket::deposit_rate_model::{
+    ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg, TargetDepositRateResponse,
+};
+use moneymarket::ownership::{
+    claim_ownership, drop_ownership_proposal, propose_new_owner, query_ownership_proposal,
+};
+use std::cmp::min;
+
+pub const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
+pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> StdResult<Response> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    store_config(
+        deps.storage,
+        &Config {
+            owner: deps.api.addr_canonicalize(&msg.owner)?,
+            max_change: msg.max_change,
+            yr_increase_expectation: msg.yr_increase_expectation,
+        },
+    )?;
+
+    Ok(Response::default())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::UpdateConfig {
+            max_change,
+            yr_increase_expectation,
+        } => update_config(deps, info, max_change, yr_increase_expectation),
+        ExecuteMsg::ProposeNewOwner { owner, expires_in } => {
+            let config: Config = read_config(deps.storage)?;
+            Ok(propose_new_owner(
+                deps,
+                env,
+                info,
+                config.owner,
+                owner,
+                expires_in,
+            )?)
+        }
+        ExecuteMsg::DropOwnershipProposal {} => {
+            let config: Config = read_config(deps.storage)?;
+            Ok(drop_ownership_proposal(deps, info, config.owner)?)
+        }
+        ExecuteMsg::ClaimOwnership {} => Ok(claim_ownership(deps, env, info, update_owner)?),
+    }
+}
+
+pub fn update_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    max_change: Option<Decimal256>,
+    yr_increase_expectation: Option<Decimal256>,
+) -> Result<Response, ContractError> {
+    let mut config: Config = read_config(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if let Some(max_change) = max_change {
+        config.max_change = max_change;
+    }
+
+    if let Some(yr_increase_expectation) = yr_increase_expectation {
+        config.yr_increase_expectation = yr_increase_expectation;
+    }
+
+    store_config(deps.storage, &config)?;
+    Ok(Response::default())
+}
+
+pub fn update_owner(deps: DepsMut, owner: CanonicalAddr) -> StdResult<()> {
+    let mut config: Config = read_config(deps.storage)?;
+    config.owner = owner;
+    store_config(deps.storage, &config)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::OwnershipProposal {} => to_binary(&query_ownership_proposal(deps)?),
+        QueryMsg::TargetDepositRate {
+            yield_reserve,
+            prev_yield_reserve,
+            current_rate,
+            seconds: _,
+            model_state: _,
+        } => to_binary(&query_target_deposit_rate(
+            deps,
+            yield_reserve,
+            prev_yield_reserve,
+            current_rate,
+        )?),
+    }
+}
+
+fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let resp = ConfigResponse {
+        owner: deps.api.addr_humanize(&config.owner)?.to_string(),
+        max_change: config.max_change,
+        yr_increase_expectation: config.yr_increase_expectation,
+    };
+
+    Ok(resp)
+}
+
+fn query_target_deposit_rate(
+    deps: Deps,
+    yield_reserve: Decimal256,
+    prev_yield_reserve: Decimal256,
+    current_rate: Decimal256,
+) -> StdResult<TargetDepositRateResponse> {
+    let config: Config = read_config(deps.storage)?;
+
+    Ok(TargetDepositRateResponse {
+        rate: compute_target_deposit_rate(
+            config.max_change,
+            config.yr_increase_expectation,
+            yield_reserve,
+            prev_yield_reserve,
+            current_rate,
+        ),
+        model_state: None,
+    })
+}
+
+/// Moves the yearly deposit rate by the yield reserve change
+/// in excess of the expected increase, capped at max_change
+pub fn compute_target_deposit_rate(
+    max_change: Decimal256,
+    yr_increase_expectation: Decimal256,
+    yield_reserve: Decimal256,
+    prev_yield_reserve: Decimal256,
+    current_rate: Decimal256,
+) -> Decimal256 {
+    // convert per-second rate into yearly rate
+    let seconds_per_year = Decimal256::from_uint256(SECONDS_PER_YEAR);
+    let current_rate = current_rate * seconds_per_year;
+
+    let mut yr_went_up = yield_reserve > prev_yield_reserve;
+
+    // amount yield reserve changed in notional terms
+    let yield_reserve_delta = if yr_went_up {
+        yield_reserve - prev_yield_reserve
+    } else {
+        prev_yield_reserve - yield_reserve
+    };
+
+    // amount yield reserve changed in percentage terms
+    // if the prev yield reserve was zero; assume either a 100% decrease
+    // or a 100% increase, but this should be very rare
+    let mut yield_reserve_change = if prev_yield_reserve.is_zero() {
+        Decimal256::one()
+    } else {
+        yield_reserve_delta / prev_yield_reserve
+    };
+
+    // decreases the yield reserve change by yr_increase_expectation
+    // (assume (yr_went_up, yield_reserve_change) is one signed integer, this just subtracts
+    // that integer by yr_increase_expectation)
+    yield_reserve_change = if !yr_went_up {
+        yield_reserve_change + yr_increase_expectation
+    } else if yield_reserve_change > yr_increase_expectation {
+        yield_reserve_change - yr_increase_expectation
+    } else {
+        yr_went_up = !yr_went_up;
+        yr_increase_expectation - yield_reserve_change
+    };
+
+    yield_reserve_change = min(yield_reserve_change, max_change);
+
+    let new_rate = if yr_went_up {
+        current_rate + yield_reserve_change
+    } else if current_rate > yield_reserve_change {
+        current_rate - yield_reserve_change
+    } else {
+        Decimal256::zero()
+    };
+
+    // convert from yearly rate to per-second rate
+    new_rate / seconds_per_year
+}
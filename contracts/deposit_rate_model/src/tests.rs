@@ -0,0 +1,158 @@
+use crate::contract::{execute, instantiate, query};
+use crate::error::ContractError;
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::from_binary;
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+use moneymarket::deposit_rate_model::{
+    ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg, TargetDepositRateResponse,
+};
+use std::str::FromStr;
+
+#[test]
+fn proper_initialization() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner: "owner0000".to_string(),
+        max_change: Decimal256::from_str("0.03").unwrap(),
+        yr_increase_expectation: Decimal256::from_str("0.01").unwrap(),
+    };
+
+    let info = mock_info("addr0000", &[]);
+
+    // we can just call .unwrap() to assert this was a success
+    let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(0, res.messages.len());
+
+    // it worked, let's query the state
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let value: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!("owner0000", value.owner.as_str());
+    assert_eq!("0.03", &value.max_change.to_string());
+    assert_eq!("0.01", &value.yr_increase_expectation.to_string());
+}
+
+#[test]
+fn update_config() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner: "owner0000".to_string(),
+        max_change: Decimal256::from_str("0.03").unwrap(),
+        yr_increase_expectation: Decimal256::from_str("0.01").unwrap(),
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // transfer the ownership
+    let info = mock_info("owner0000", &[]);
+    let msg = ExecuteMsg::ProposeNewOwner {
+        owner: "owner0001".to_string(),
+        expires_in: 86400u64,
+    };
+
+    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let info = mock_info("owner0001", &[]);
+    let msg = ExecuteMsg::ClaimOwnership {};
+
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(0, res.messages.len());
+
+    // it worked, let's query the state
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let value: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!("owner0001", value.owner.as_str());
+
+    // Unauthorized err
+    let info = mock_info("owner0000", &[]);
+    let msg = ExecuteMsg::UpdateConfig {
+        max_change: Some(Decimal256::percent(1)),
+        yr_increase_expectation: None,
+    };
+
+    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    // update left items
+    let info = mock_info("owner0001", &[]);
+    let msg = ExecuteMsg::UpdateConfig {
+        max_change: Some(Decimal256::percent(1)),
+        yr_increase_expectation: Some(Decimal256::permille(5)),
+    };
+
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(0, res.messages.len());
+
+    // it worked, let's query the state
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let value: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!("0.01", &value.max_change.to_string());
+    assert_eq!("0.005", &value.yr_increase_expectation.to_string());
+}
+
+#[test]
+fn target_deposit_rate() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner: "owner0000".to_string(),
+        max_change: Decimal256::from_str("0.03").unwrap(),
+        yr_increase_expectation: Decimal256::from_str("0.01").unwrap(),
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let query_rate = |yield_reserve: u64, prev_yield_reserve: u64, current_rate: Decimal256| {
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::TargetDepositRate {
+                yield_reserve: Decimal256::from_uint256(yield_reserve),
+                prev_yield_reserve: Decimal256::from_uint256(prev_yield_reserve),
+                current_rate,
+                seconds: 86400,
+                model_state: None,
+            },
+        )
+        .unwrap();
+        let value: TargetDepositRateResponse = from_binary(&res).unwrap();
+        value.rate
+    };
+    let current_rate = Decimal256::from_ratio(1u64, 1000000u64);
+
+    // empty previous reserve counts as a 100% increase; capped at max_change
+    assert_eq!(
+        query_rate(10000000000, 0, current_rate),
+        Decimal256::from_ratio(1000951293759u64, 1000000000000000000u64)
+    );
+
+    // unchanged reserve falls short of the expected increase
+    assert_eq!(
+        query_rate(10000000000, 10000000000, current_rate),
+        Decimal256::from_ratio(999682902080u64, 1000000000000000000u64)
+    );
+
+    // 0.5% increase is still 0.5% below the expectation
+    assert_eq!(
+        query_rate(10050000000, 10000000000, current_rate),
+        Decimal256::from_ratio(999841451040u64, 1000000000000000000u64)
+    );
+
+    // 50% decrease; capped at max_change
+    assert_eq!(
+        query_rate(5000000000, 10000000000, current_rate),
+        Decimal256::from_ratio(999048706240u64, 1000000000000000000u64)
+    );
+
+    // rate does not go below zero
+    assert_eq!(
+        query_rate(5000000000, 10000000000, Decimal256::zero()),
+        Decimal256::zero()
+    );
+}
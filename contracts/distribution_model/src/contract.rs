@@ -1,15 +1,23 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cosmwasm_std::{to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
+use cosmwasm_std::{
+    to_binary, Binary, CanonicalAddr, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
+};
 
 use crate::error::ContractError;
 use crate::state::{read_config, store_config, Config};
 
 use cosmwasm_bignumber::Decimal256;
-use moneymarket::common::optional_addr_validate;
+use cw2::set_contract_version;
 use moneymarket::distribution_model::{
     AncEmissionRateResponse, ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg,
 };
+use moneymarket::ownership::{
+    claim_ownership, drop_ownership_proposal, propose_new_owner, query_ownership_proposal,
+};
+
+pub const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
+pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -18,6 +26,8 @@ pub fn instantiate(
     _info: MessageInfo,
     msg: InstantiateMsg,
 ) -> StdResult<Response> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
     store_config(
         deps.storage,
         &Config {
@@ -35,36 +45,46 @@ pub fn instantiate(
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
         ExecuteMsg::UpdateConfig {
-            owner,
             emission_cap,
             emission_floor,
             increment_multiplier,
             decrement_multiplier,
-        } => {
-            let api = deps.api;
-            update_config(
+        } => update_config(
+            deps,
+            info,
+            emission_cap,
+            emission_floor,
+            increment_multiplier,
+            decrement_multiplier,
+        ),
+        ExecuteMsg::ProposeNewOwner { owner, expires_in } => {
+            let config: Config = read_config(deps.storage)?;
+            Ok(propose_new_owner(
                 deps,
+                env,
                 info,
-                optional_addr_validate(api, owner)?,
-                emission_cap,
-                emission_floor,
-                increment_multiplier,
-                decrement_multiplier,
-            )
+                config.owner,
+                owner,
+                expires_in,
+            )?)
         }
+        ExecuteMsg::DropOwnershipProposal {} => {
+            let config: Config = read_config(deps.storage)?;
+            Ok(drop_ownership_proposal(deps, info, config.owner)?)
+        }
+        ExecuteMsg::ClaimOwnership {} => Ok(claim_ownership(deps, env, info, update_owner)?),
     }
 }
 
 pub fn update_config(
     deps: DepsMut,
     info: MessageInfo,
-    owner: Option<Addr>,
     emission_cap: Option<Decimal256>,
     emission_floor: Option<Decimal256>,
     increment_multiplier: Option<Decimal256>,
@@ -75,10 +95,6 @@ pub fn update_config(
         return Err(ContractError::Unauthorized {});
     }
 
-    if let Some(owner) = owner {
-        config.owner = deps.api.addr_canonicalize(owner.as_str())?;
-    }
-
     if let Some(emission_cap) = emission_cap {
         config.emission_cap = emission_cap;
     }
@@ -99,10 +115,17 @@ pub fn update_config(
     Ok(Response::default())
 }
 
+pub fn update_owner(deps: DepsMut, owner: CanonicalAddr) -> StdResult<()> {
+    let mut config: Config = read_config(deps.storage)?;
+    config.owner = owner;
+    store_config(deps.storage, &config)
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::OwnershipProposal {} => to_binary(&query_ownership_proposal(deps)?),
         QueryMsg::AncEmissionRate {
             deposit_rate,
             target_deposit_rate,
@@ -50,16 +50,18 @@ fn update_config() {
     let info = mock_info("addr0000", &[]);
     let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
 
-    // update owner
+    // transfer the ownership
     let info = mock_info("owner0000", &[]);
-    let msg = ExecuteMsg::UpdateConfig {
-        owner: Some("owner0001".to_string()),
-        emission_cap: None,
-        emission_floor: None,
-        increment_multiplier: None,
-        decrement_multiplier: None,
+    let msg = ExecuteMsg::ProposeNewOwner {
+        owner: "owner0001".to_string(),
+        expires_in: 86400u64,
     };
 
+    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let info = mock_info("owner0001", &[]);
+    let msg = ExecuteMsg::ClaimOwnership {};
+
     let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
     assert_eq!(0, res.messages.len());
 
@@ -75,7 +77,6 @@ fn update_config() {
     // Unauthorized err
     let info = mock_info("owner0000", &[]);
     let msg = ExecuteMsg::UpdateConfig {
-        owner: None,
         emission_cap: Some(Decimal256::from_uint256(100u64)),
         emission_floor: Some(Decimal256::from_uint256(10u64)),
         increment_multiplier: Some(Decimal256::percent(110)),
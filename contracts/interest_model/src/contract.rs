@@ -5,12 +5,22 @@ use cosmwasm_std::entry_point;
 
 use cosmwasm_bignumber::Decimal256;
 use cosmwasm_bignumber::Uint256;
-use cosmwasm_std::{to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
-use moneymarket::common::optional_addr_validate;
+use cosmwasm_std::{
+    to_binary, Binary, CanonicalAddr, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
+};
+use cw2::set_contract_version;
+use moneymarket::common::legacy_rate_to_rate_per_second;
 use moneymarket::interest_model::{
-    BorrowRateResponse, ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg,
+    BorrowRateResponse, ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg,
+};
+use moneymarket::migration::{assert_legacy, assert_upgrade};
+use moneymarket::ownership::{
+    claim_ownership, drop_ownership_proposal, propose_new_owner, query_ownership_proposal,
 };
 
+pub const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
+pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
@@ -18,6 +28,8 @@ pub fn instantiate(
     _info: MessageInfo,
     msg: InstantiateMsg,
 ) -> StdResult<Response> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
     store_config(
         deps.storage,
         &Config {
@@ -30,35 +42,60 @@ pub fn instantiate(
     Ok(Response::default())
 }
 
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> StdResult<Response> {
+    match msg {
+        MigrateMsg::FromLegacy {} => {
+            assert_legacy(deps.storage)?;
+
+            // legacy rates are per block
+            let mut config: Config = read_config(deps.storage)?;
+            config.base_rate = legacy_rate_to_rate_per_second(config.base_rate);
+            config.interest_multiplier = legacy_rate_to_rate_per_second(config.interest_multiplier);
+            store_config(deps.storage, &config)?;
+        }
+        MigrateMsg::Upgrade {} => assert_upgrade(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?,
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::default())
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
         ExecuteMsg::UpdateConfig {
-            owner,
             base_rate,
             interest_multiplier,
-        } => {
-            let api = deps.api;
-            update_config(
+        } => update_config(deps, info, base_rate, interest_multiplier),
+        ExecuteMsg::ProposeNewOwner { owner, expires_in } => {
+            let config: Config = read_config(deps.storage)?;
+            Ok(propose_new_owner(
                 deps,
+                env,
                 info,
-                optional_addr_validate(api, owner)?,
-                base_rate,
-                interest_multiplier,
-            )
+                config.owner,
+                owner,
+                expires_in,
+            )?)
+        }
+        ExecuteMsg::DropOwnershipProposal {} => {
+            let config: Config = read_config(deps.storage)?;
+            Ok(drop_ownership_proposal(deps, info, config.owner)?)
         }
+        ExecuteMsg::ClaimOwnership {} => Ok(claim_ownership(deps, env, info, update_owner)?),
     }
 }
 
 pub fn update_config(
     deps: DepsMut,
     info: MessageInfo,
-    owner: Option<Addr>,
     base_rate: Option<Decimal256>,
     interest_multiplier: Option<Decimal256>,
 ) -> Result<Response, ContractError> {
@@ -67,10 +104,6 @@ pub fn update_config(
         return Err(ContractError::Unauthorized {});
     }
 
-    if let Some(owner) = owner {
-        config.owner = deps.api.addr_canonicalize(owner.as_str())?;
-    }
-
     if let Some(base_rate) = base_rate {
         config.base_rate = base_rate;
     }
@@ -83,10 +116,17 @@ pub fn update_config(
     Ok(Response::default())
 }
 
+pub fn update_owner(deps: DepsMut, owner: CanonicalAddr) -> StdResult<()> {
+    let mut config: Config = read_config(deps.storage)?;
+    config.owner = owner;
+    store_config(deps.storage, &config)
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::OwnershipProposal {} => to_binary(&query_ownership_proposal(deps)?),
         QueryMsg::BorrowRate {
             market_balance,
             total_liabilities,
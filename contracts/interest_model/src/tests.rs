@@ -1,11 +1,12 @@
-use crate::contract::{execute, instantiate, query};
+use crate::contract::{execute, instantiate, migrate, query};
 use crate::error::ContractError;
 use cosmwasm_bignumber::{Decimal256, Uint256};
-use cosmwasm_std::from_binary;
 use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+use cosmwasm_std::{from_binary, StdError};
 use moneymarket::interest_model::{
-    BorrowRateResponse, ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg,
+    BorrowRateResponse, ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg,
 };
+use std::str::FromStr;
 
 #[test]
 fn proper_initialization() {
@@ -64,14 +65,18 @@ fn update_config() {
     let info = mock_info("addr0000", &[]);
     let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
 
-    // update owner
+    // transfer the ownership
     let info = mock_info("owner0000", &[]);
-    let msg = ExecuteMsg::UpdateConfig {
-        owner: Some("owner0001".to_string()),
-        base_rate: None,
-        interest_multiplier: None,
+    let msg = ExecuteMsg::ProposeNewOwner {
+        owner: "owner0001".to_string(),
+        expires_in: 86400u64,
     };
 
+    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let info = mock_info("owner0001", &[]);
+    let msg = ExecuteMsg::ClaimOwnership {};
+
     let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
     assert_eq!(0, res.messages.len());
 
@@ -85,7 +90,6 @@ fn update_config() {
     // Unauthorized err
     let info = mock_info("owner0000", &[]);
     let msg = ExecuteMsg::UpdateConfig {
-        owner: None,
         base_rate: Some(Decimal256::percent(1)),
         interest_multiplier: Some(Decimal256::percent(1)),
     };
@@ -96,3 +100,60 @@ fn update_config() {
         _ => panic!("Must return unauthorized error"),
     }
 }
+
+#[test]
+fn proper_migrate() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner: "owner0000".to_string(),
+        base_rate: Decimal256::percent(1),
+        interest_multiplier: Decimal256::percent(10),
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // versioned contracts cannot be migrated from the legacy layout
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg::FromLegacy {});
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "Contract is already at version 0.0.0; legacy migration not allowed"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // legacy contracts do not store a contract version
+    deps.as_mut().storage.remove(b"contract_info");
+
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg::Upgrade {});
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "Contract version is missing; migrate from the legacy layout"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // per-block rates are converted into per-second rates
+    let _res = migrate(deps.as_mut(), mock_env(), MigrateMsg::FromLegacy {}).unwrap();
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let value: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        value.base_rate,
+        Decimal256::from_str("0.001476664764079147").unwrap()
+    );
+    assert_eq!(
+        value.interest_multiplier,
+        Decimal256::from_str("0.014766647640791476").unwrap()
+    );
+    // the version is recorded, so upgrades leave the rates untouched
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let _res = migrate(deps.as_mut(), mock_env(), MigrateMsg::Upgrade {}).unwrap();
+    assert_eq!(
+        query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap(),
+        res
+    );
+}
@@ -70,19 +70,18 @@ fn update_config() {
     let info = mock_info("addr0000", &[]);
     let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
 
-    // update owner
+    // transfer the ownership
     let info = mock_info("owner0000", &[]);
-    let msg = ExecuteMsg::UpdateConfig {
-        owner: Some("owner0001".to_string()),
-        oracle_contract: None,
-        stable_denom: None,
-        safe_ratio: None,
-        bid_fee: None,
-        max_premium_rate: None,
-        liquidation_threshold: None,
-        price_timeframe: None,
+    let msg = ExecuteMsg::ProposeNewOwner {
+        owner: "owner0001".to_string(),
+        expires_in: 86400u64,
     };
 
+    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let info = mock_info("owner0001", &[]);
+    let msg = ExecuteMsg::ClaimOwnership {};
+
     let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
     assert_eq!(0, res.messages.len());
 
@@ -106,7 +105,6 @@ fn update_config() {
     // Update left items
     let info = mock_info("owner0001", &[]);
     let msg = ExecuteMsg::UpdateConfig {
-        owner: None,
         oracle_contract: Some("oracle0001".to_string()),
         stable_denom: Some("ukrw".to_string()),
         safe_ratio: Some(Decimal256::percent(15)),
@@ -139,7 +137,6 @@ fn update_config() {
     // Unauthorized err
     let info = mock_info("owner0000", &[]);
     let msg = ExecuteMsg::UpdateConfig {
-        owner: None,
         oracle_contract: Some("oracle0001".to_string()),
         stable_denom: Some("ukrw".to_string()),
         safe_ratio: Some(Decimal256::percent(1)),
@@ -13,6 +13,7 @@ use cosmwasm_std::{
 };
 use cw20::Cw20ExecuteMsg;
 use moneymarket::oracle::PriceResponse;
+use moneymarket::pause::{assert_not_paused, PauseAction};
 use moneymarket::querier::{deduct_tax, query_price, TimeConstraints};
 
 /// Stable asset is submitted to create a bid record. If available bids for the collateral is under
@@ -24,6 +25,8 @@ pub fn submit_bid(
     collateral_token: String,
     premium_slot: u8,
 ) -> StdResult<Response> {
+    assert_not_paused(deps.storage, PauseAction::Bid)?;
+
     let config: Config = read_config(deps.storage)?;
     let collateral_token_raw: CanonicalAddr = deps.api.addr_canonicalize(&collateral_token)?;
     let collateral_info: CollateralInfo =
@@ -108,6 +111,8 @@ pub fn activate_bids(
     collateral_token: String,
     bids_idx: Option<Vec<Uint128>>,
 ) -> StdResult<Response> {
+    assert_not_paused(deps.storage, PauseAction::Bid)?;
+
     let sender_raw: CanonicalAddr = deps.api.addr_canonicalize(info.sender.as_str())?;
     let collateral_token_raw: CanonicalAddr = deps.api.addr_canonicalize(&collateral_token)?;
     let collateral_info: CollateralInfo =
@@ -299,6 +304,8 @@ pub fn execute_liquidation(
     collateral_token: String,
     amount: Uint256,
 ) -> StdResult<Response> {
+    assert_not_paused(deps.storage, PauseAction::Liquidate)?;
+
     let config: Config = read_config(deps.storage)?;
     let collateral_token_raw = deps.api.addr_canonicalize(&collateral_token)?;
     let collateral_info: CollateralInfo =
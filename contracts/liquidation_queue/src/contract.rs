@@ -14,10 +14,20 @@ use crate::state::{
 
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{
-    from_binary, to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult,
+    from_binary, to_binary, Binary, CanonicalAddr, Deps, DepsMut, Env, MessageInfo, Response,
+    StdError, StdResult,
 };
+use cw2::set_contract_version;
 use cw20::Cw20ReceiveMsg;
 use moneymarket::liquidation_queue::{Cw20HookMsg, ExecuteMsg, InstantiateMsg, QueryMsg};
+use moneymarket::overseer::WhitelistStatus;
+use moneymarket::ownership::{
+    claim_ownership, drop_ownership_proposal, propose_new_owner, query_ownership_proposal,
+};
+use moneymarket::pause::{pause, query_pause_info, unpause, update_guardian};
+
+pub const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
+pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -26,6 +36,8 @@ pub fn instantiate(
     _info: MessageInfo,
     msg: InstantiateMsg,
 ) -> StdResult<Response> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
     assert_fees(msg.liquidator_fee + msg.bid_fee)?;
 
     store_config(
@@ -52,7 +64,6 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> S
     match msg {
         ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
         ExecuteMsg::UpdateConfig {
-            owner,
             oracle_contract,
             safe_ratio,
             bid_fee,
@@ -64,7 +75,6 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> S
         } => update_config(
             deps,
             info,
-            owner,
             oracle_contract,
             safe_ratio,
             bid_fee,
@@ -74,6 +84,27 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> S
             waiting_period,
             overseer,
         ),
+        ExecuteMsg::ProposeNewOwner { owner, expires_in } => {
+            let config: Config = read_config(deps.storage)?;
+            propose_new_owner(deps, env, info, config.owner, owner, expires_in)
+        }
+        ExecuteMsg::DropOwnershipProposal {} => {
+            let config: Config = read_config(deps.storage)?;
+            drop_ownership_proposal(deps, info, config.owner)
+        }
+        ExecuteMsg::ClaimOwnership {} => claim_ownership(deps, env, info, update_owner),
+        ExecuteMsg::UpdateGuardian { guardian } => {
+            let config: Config = read_config(deps.storage)?;
+            update_guardian(deps, info, config.owner, guardian)
+        }
+        ExecuteMsg::Pause { actions } => {
+            let config: Config = read_config(deps.storage)?;
+            pause(deps, info, config.owner, actions)
+        }
+        ExecuteMsg::Unpause { actions } => {
+            let config: Config = read_config(deps.storage)?;
+            unpause(deps, info, config.owner, actions)
+        }
         ExecuteMsg::WhitelistCollateral {
             collateral_token,
             bid_threshold,
@@ -143,7 +174,6 @@ pub fn receive_cw20(
 pub fn update_config(
     deps: DepsMut,
     info: MessageInfo,
-    owner: Option<String>,
     oracle_contract: Option<String>,
     safe_ratio: Option<Decimal256>,
     bid_fee: Option<Decimal256>,
@@ -158,10 +188,6 @@ pub fn update_config(
         return Err(StdError::generic_err("unauthorized"));
     }
 
-    if let Some(owner) = owner {
-        config.owner = deps.api.addr_canonicalize(&owner)?;
-    }
-
     if let Some(oracle_contract) = oracle_contract {
         config.oracle_contract = deps.api.addr_canonicalize(&oracle_contract)?;
     }
@@ -200,6 +226,12 @@ pub fn update_config(
     Ok(Response::new())
 }
 
+pub fn update_owner(deps: DepsMut, owner: CanonicalAddr) -> StdResult<()> {
+    let mut config: Config = read_config(deps.storage)?;
+    config.owner = owner;
+    store_config(deps.storage, &config)
+}
+
 pub fn whitelist_collateral(
     deps: DepsMut,
     info: MessageInfo,
@@ -221,10 +253,18 @@ pub fn whitelist_collateral(
 
     // check if the colalteral is whitelisted in overseer
     let overseer = deps.api.addr_humanize(&config.overseer)?;
-    query_collateral_whitelist_info(&deps.querier, overseer.to_string(), collateral_token)
-        .map_err(|_| {
-            StdError::generic_err("This collateral is not whitelisted in Anchor overseer")
-        })?;
+    let whitelist_elem =
+        query_collateral_whitelist_info(&deps.querier, overseer.to_string(), collateral_token)
+            .map_err(|_| {
+                StdError::generic_err("This collateral is not whitelisted in Anchor overseer")
+            })?;
+
+    // paused or deprecated collaterals cannot be added
+    if whitelist_elem.status != WhitelistStatus::Active {
+        return Err(StdError::generic_err(
+            "This collateral is not active in Anchor overseer",
+        ));
+    }
 
     // assert max slot does not exceed cap and max premium rate does not exceed 1
     assert_max_slot(max_slot)?;
@@ -283,6 +323,8 @@ pub fn update_collateral_info(
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::OwnershipProposal {} => to_binary(&query_ownership_proposal(deps)?),
+        QueryMsg::PauseInfo {} => to_binary(&query_pause_info(deps)?),
         QueryMsg::LiquidationAmount {
             borrow_amount,
             borrow_limit,
@@ -55,7 +55,7 @@ pub fn query_liquidation_amount(
     }
 
     // calculate value of all collaterals and weights
-    let (collaterals_value, total_weight, collateral_weights, max_ltvs) =
+    let (collaterals_value, total_weight, collateral_weights, liquidation_ltvs) =
         compute_collateral_weights(deps, overseer, &collaterals, &collateral_prices)?;
 
     // check partial liquidation condition
@@ -80,7 +80,11 @@ pub fn query_liquidation_amount(
 
     let mut result: Vec<(String, Uint256)> = vec![];
     for (i, collateral) in collaterals.iter().enumerate() {
-        let (price, weight, max_ltv) = (collateral_prices[i], collateral_weights[i], max_ltvs[i]);
+        let (price, weight, liquidation_ltv) = (
+            collateral_prices[i],
+            collateral_weights[i],
+            liquidation_ltvs[i],
+        );
 
         let collateral_token_raw = deps.api.addr_canonicalize(&collateral.0)?;
         let collateral_info = read_collateral_info(deps.storage, &collateral_token_raw)?;
@@ -112,7 +116,8 @@ pub fn query_liquidation_amount(
             x += slot_available_bids / discounted_price;
 
             let safe_borrow = safe_ratio * collateral_borrow_limit;
-            let f_x = ((safe_ratio * max_ltv * price) * x) + collateral_borrow_amount - safe_borrow
+            let f_x = ((safe_ratio * liquidation_ltv * price) * x) + collateral_borrow_amount
+                - safe_borrow
                 + tax_cap_adj;
 
             g_x += slot_available_bids;
@@ -124,7 +129,7 @@ pub fn query_liquidation_amount(
                     - prev_g_x;
                 let denominator = price
                     * (((Decimal256::one() - premium_rate) * base_fee_deductor)
-                        - (safe_ratio * max_ltv));
+                        - (safe_ratio * liquidation_ltv));
 
                 let liquidation_amount = (nominator / denominator) + Uint256::one(); // round up
 
@@ -150,7 +155,7 @@ pub fn query_liquidation_amount(
 }
 
 /// The portion of collateral that liquidated from the available set is calculated
-/// based on weight = min(collateral_value, available_bids) / max_ltv
+/// based on weight = min(collateral_value, available_bids) / liquidation_ltv
 #[allow(clippy::ptr_arg)]
 fn compute_collateral_weights(
     deps: Deps,
@@ -161,33 +166,33 @@ fn compute_collateral_weights(
     let mut collaterals_value = Uint256::zero();
     let mut total_weight = Uint256::zero();
     let mut collateral_weights: Vec<Uint256> = vec![];
-    let mut max_ltvs: Vec<Decimal256> = vec![];
+    let mut liquidation_ltvs: Vec<Decimal256> = vec![];
 
     for (collateral, price) in collaterals.iter().zip(collateral_prices.iter()) {
         let collateral_available_bids =
             read_total_bids(deps.storage, &deps.api.addr_canonicalize(&collateral.0)?)
                 .unwrap_or_default();
-        let max_ltv = query_collateral_whitelist_info(
+        let liquidation_ltv = query_collateral_whitelist_info(
             &deps.querier,
             overseer.to_string(),
             collateral.0.to_string(),
         )?
-        .max_ltv;
+        .liquidation_ltv;
 
         let collateral_value = collateral.1 * *price;
-        let weigth = collateral_value.min(collateral_available_bids) / max_ltv;
+        let weigth = collateral_value.min(collateral_available_bids) / liquidation_ltv;
 
         total_weight += weigth;
         collaterals_value += collateral_value;
         collateral_weights.push(weigth);
-        max_ltvs.push(max_ltv);
+        liquidation_ltvs.push(liquidation_ltv);
     }
 
     Ok((
         collaterals_value,
         total_weight,
         collateral_weights,
-        max_ltvs,
+        liquidation_ltvs,
     ))
 }
 
@@ -1,4 +1,4 @@
-use moneymarket::overseer::{WhitelistResponse, WhitelistResponseElem};
+use moneymarket::overseer::{WhitelistResponse, WhitelistResponseElem, WhitelistStatus};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
@@ -51,12 +51,14 @@ pub struct WasmMockQuerier {
 #[derive(Clone, Default)]
 pub struct CollateralQuerier {
     collaterals: HashMap<String, Decimal256>,
+    statuses: HashMap<String, WhitelistStatus>,
 }
 
 impl CollateralQuerier {
     pub fn new(collaterals: &[(&String, &Decimal256)]) -> Self {
         CollateralQuerier {
             collaterals: collaterals_to_map(collaterals),
+            statuses: HashMap::new(),
         }
     }
 }
@@ -190,19 +192,26 @@ impl WasmMockQuerier {
                     start_after: _,
                     limit: _,
                 } => {
-                    match self
-                        .collateral_querier
-                        .collaterals
-                        .get(&collateral_token.unwrap())
-                    {
+                    let collateral_token = collateral_token.unwrap();
+                    match self.collateral_querier.collaterals.get(&collateral_token) {
                         Some(v) => {
                             SystemResult::Ok(ContractResult::from(to_binary(&WhitelistResponse {
                                 elems: vec![WhitelistResponseElem {
                                     name: "name".to_string(),
                                     symbol: "symbol".to_string(),
                                     max_ltv: *v,
+                                    liquidation_ltv: *v,
                                     custody_contract: "custody0000".to_string(),
                                     collateral_token: "token0000".to_string(),
+                                    max_collateral_amount: None,
+                                    max_borrow_value: None,
+                                    status: self
+                                        .collateral_querier
+                                        .statuses
+                                        .get(&collateral_token)
+                                        .cloned()
+                                        .unwrap_or(WhitelistStatus::Active),
+                                    max_ltv_ramp: None,
                                 }],
                             })))
                         }
@@ -244,4 +253,10 @@ impl WasmMockQuerier {
     pub fn with_collateral_max_ltv(&mut self, collaterals: &[(&String, &Decimal256)]) {
         self.collateral_querier = CollateralQuerier::new(collaterals);
     }
+
+    pub fn with_collateral_status(&mut self, collateral_token: &str, status: WhitelistStatus) {
+        self.collateral_querier
+            .statuses
+            .insert(collateral_token.to_string(), status);
+    }
 }
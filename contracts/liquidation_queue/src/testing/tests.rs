@@ -11,6 +11,7 @@ use moneymarket::liquidation_queue::{
     BidResponse, CollateralInfoResponse, ConfigResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg,
     QueryMsg,
 };
+use moneymarket::overseer::WhitelistStatus;
 
 #[test]
 fn proper_initialization() {
@@ -75,20 +76,18 @@ fn update_config() {
     let info = mock_info("addr0000", &[]);
     let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
 
-    // update owner
+    // transfer the ownership
     let info = mock_info("owner0000", &[]);
-    let msg = ExecuteMsg::UpdateConfig {
-        owner: Some("owner0001".to_string()),
-        oracle_contract: None,
-        safe_ratio: None,
-        bid_fee: None,
-        liquidator_fee: None,
-        liquidation_threshold: None,
-        price_timeframe: None,
-        waiting_period: None,
-        overseer: None,
+    let msg = ExecuteMsg::ProposeNewOwner {
+        owner: "owner0001".to_string(),
+        expires_in: 86400u64,
     };
 
+    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let info = mock_info("owner0001", &[]);
+    let msg = ExecuteMsg::ClaimOwnership {};
+
     let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
     assert_eq!(0, res.messages.len());
 
@@ -114,7 +113,6 @@ fn update_config() {
     // Update left items
     let info = mock_info("owner0001", &[]);
     let msg = ExecuteMsg::UpdateConfig {
-        owner: None,
         oracle_contract: Some("oracle0001".to_string()),
         safe_ratio: Some(Decimal256::percent(15)),
         bid_fee: Some(Decimal256::percent(2)),
@@ -150,7 +148,6 @@ fn update_config() {
     // Unauthorized err
     let info = mock_info("owner0000", &[]);
     let msg = ExecuteMsg::UpdateConfig {
-        owner: None,
         oracle_contract: Some("oracle0001".to_string()),
         safe_ratio: Some(Decimal256::percent(1)),
         bid_fee: Some(Decimal256::percent(2)),
@@ -896,3 +893,58 @@ fn update_collateral_info() {
         }
     );
 }
+
+#[test]
+fn whitelist_inactive_collateral() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier
+        .with_collateral_max_ltv(&[(&"token0000".to_string(), &Decimal256::percent(90))]);
+    deps.querier
+        .with_collateral_status("token0000", WhitelistStatus::Paused);
+
+    let msg = InstantiateMsg {
+        owner: "owner0000".to_string(),
+        oracle_contract: "oracle0000".to_string(),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::percent(0),
+        liquidation_threshold: Uint256::from(100000000u64),
+        price_timeframe: 60u64,
+        waiting_period: 60u64,
+        overseer: "overseer0000".to_string(),
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::WhitelistCollateral {
+        collateral_token: "token0000".to_string(),
+        max_slot: 30u8,
+        bid_threshold: Uint256::from(10000u128),
+        premium_rate_per_slot: Decimal256::percent(1),
+    };
+    let info = mock_info("owner0000", &[]);
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("This collateral is not active in Anchor overseer")
+    );
+
+    deps.querier.with_collateral_status(
+        "token0000",
+        WhitelistStatus::Deprecated {
+            start_time: 100u64,
+            end_time: 200u64,
+        },
+    );
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("This collateral is not active in Anchor overseer")
+    );
+
+    deps.querier
+        .with_collateral_status("token0000", WhitelistStatus::Active);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+}
@@ -6,15 +6,20 @@ use cosmwasm_std::{
 };
 use moneymarket::interest_model::BorrowRateResponse;
 use moneymarket::market::{BorrowerInfoResponse, BorrowerInfosResponse};
-use moneymarket::overseer::BorrowLimitResponse;
-use moneymarket::querier::{deduct_tax, query_balance, query_supply};
+use moneymarket::overseer::{BorrowLimitResponse, ExecuteMsg as OverseerExecuteMsg};
+use moneymarket::pause::{assert_not_paused, PauseAction};
+use moneymarket::querier::{deduct_tax, query_balance};
 
 use crate::deposit::compute_exchange_rate_raw;
 use crate::error::ContractError;
-use crate::querier::{query_borrow_limit, query_borrow_rate, query_target_deposit_rate};
+use crate::fixed_loan::{compute_fixed_liabilities, compute_fixed_loan_amount, repay_fixed_loans};
+use crate::flash_loan::query_market_balance;
+use crate::querier::{
+    query_borrow_limit, query_borrow_rate, query_collateral_usage, query_target_deposit_rate,
+};
 use crate::state::{
-    read_borrower_info, read_borrower_infos, read_config, read_state, store_borrower_info,
-    store_state, BorrowerInfo, Config, State,
+    read_borrower_info, read_borrower_infos, read_config, read_fixed_loan_book, read_state,
+    store_borrower_info, store_state, BorrowerInfo, Config, FixedLoanBook, State,
 };
 
 pub fn borrow_stable(
@@ -24,6 +29,8 @@ pub fn borrow_stable(
     borrow_amount: Uint256,
     to: Option<Addr>,
 ) -> Result<Response, ContractError> {
+    assert_not_paused(deps.storage, PauseAction::Borrow)?;
+
     let config: Config = read_config(deps.storage)?;
 
     let mut state: State = read_state(deps.storage)?;
@@ -33,26 +40,35 @@ pub fn borrow_stable(
     let mut liability: BorrowerInfo = read_borrower_info(deps.storage, &borrower_raw);
 
     // Compute interest
-    compute_interest(deps.as_ref(), &config, &mut state, env.block.height, None)?;
+    compute_interest(
+        deps.as_ref(),
+        &config,
+        &mut state,
+        env.block.time.seconds(),
+        None,
+    )?;
     compute_borrower_interest(&state, &mut liability);
 
     // Compute ANC reward
-    compute_reward(&mut state, env.block.height);
+    compute_reward(&mut state, env.block.time.seconds());
     compute_borrower_reward(&state, &mut liability);
 
     let overseer = deps.api.addr_humanize(&config.overseer_contract)?;
     let borrow_limit_res: BorrowLimitResponse = query_borrow_limit(
         deps.as_ref(),
-        overseer,
+        overseer.clone(),
         borrower.clone(),
         Some(env.block.time.seconds()),
     )?;
 
-    if borrow_limit_res.borrow_limit < borrow_amount + liability.loan_amount {
+    let fixed_loan_amount =
+        compute_fixed_loan_amount(deps.storage, &borrower_raw, env.block.time.seconds())?;
+    if borrow_limit_res.borrow_limit < borrow_amount + liability.loan_amount + fixed_loan_amount {
         return Err(ContractError::BorrowExceedsLimit(
             borrow_limit_res.borrow_limit.into(),
         ));
     }
+    assert_max_borrow_value(deps.as_ref(), overseer, borrower.clone())?;
 
     let current_balance = query_balance(
         deps.as_ref(),
@@ -117,6 +133,19 @@ pub fn repay_stable_from_liquidation(
 }
 
 pub fn repay_stable(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let borrower = info.sender.clone();
+    repay_stable_for(deps, env, info, borrower)
+}
+
+/// Applies the sent stable asset to the fixed-rate loans of the borrower
+/// and then to the variable-rate loan; the part exceeding both goes back
+/// to the sender
+pub fn repay_stable_for(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    borrower: Addr,
+) -> Result<Response, ContractError> {
     let config: Config = read_config(deps.storage)?;
 
     // Check stable denom deposit
@@ -132,10 +161,11 @@ pub fn repay_stable(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Respon
         return Err(ContractError::ZeroRepay(config.stable_denom));
     }
 
-    let mut state: State = read_state(deps.storage)?;
-
-    let borrower = info.sender;
+    // Fixed-rate loans are repaid first; the rest goes to the variable-rate loan
     let borrower_raw = deps.api.addr_canonicalize(borrower.as_str())?;
+    let fixed_repay_amount = repay_fixed_loans(deps.branch(), &env, &borrower_raw, amount)?;
+
+    let mut state: State = read_state(deps.storage)?;
     let mut liability: BorrowerInfo = read_borrower_info(deps.storage, &borrower_raw);
 
     // Compute interest
@@ -143,15 +173,17 @@ pub fn repay_stable(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Respon
         deps.as_ref(),
         &config,
         &mut state,
-        env.block.height,
+        env.block.time.seconds(),
         Some(amount),
     )?;
     compute_borrower_interest(&state, &mut liability);
 
     // Compute ANC reward
-    compute_reward(&mut state, env.block.height);
+    compute_reward(&mut state, env.block.time.seconds());
     compute_borrower_reward(&state, &mut liability);
 
+    let clear_margin_call = clear_margin_call_msg(deps.as_ref(), &config, &borrower)?;
+    let amount = amount - fixed_repay_amount;
     let repay_amount: Uint256;
     let mut messages: Vec<CosmosMsg> = vec![];
     if liability.loan_amount < amount {
@@ -160,7 +192,7 @@ pub fn repay_stable(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Respon
 
         // Payback left repay amount to sender
         messages.push(CosmosMsg::Bank(BankMsg::Send {
-            to_address: borrower.to_string(),
+            to_address: info.sender.to_string(),
             amount: vec![deduct_tax(
                 deps.as_ref(),
                 Coin {
@@ -178,14 +210,35 @@ pub fn repay_stable(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Respon
 
     store_borrower_info(deps.storage, &borrower_raw, &liability)?;
     store_state(deps.storage, &state)?;
+    messages.push(clear_margin_call);
 
     Ok(Response::new().add_messages(messages).add_attributes(vec![
         attr("action", "repay_stable"),
         attr("borrower", borrower),
         attr("repay_amount", repay_amount),
+        attr("fixed_repay_amount", fixed_repay_amount),
     ]))
 }
 
+/// Asks the overseer to clear the margin call of
+/// a borrower whose loan is safe after a repayment
+pub(crate) fn clear_margin_call_msg(
+    deps: Deps,
+    config: &Config,
+    borrower: &Addr,
+) -> StdResult<CosmosMsg> {
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: deps
+            .api
+            .addr_humanize(&config.overseer_contract)?
+            .to_string(),
+        funds: vec![],
+        msg: to_binary(&OverseerExecuteMsg::ClearMarginCall {
+            borrower: borrower.to_string(),
+        })?,
+    }))
+}
+
 pub fn claim_rewards(
     deps: DepsMut,
     env: Env,
@@ -200,11 +253,17 @@ pub fn claim_rewards(
     let mut liability: BorrowerInfo = read_borrower_info(deps.storage, &borrower_raw);
 
     // Compute interest
-    compute_interest(deps.as_ref(), &config, &mut state, env.block.height, None)?;
+    compute_interest(
+        deps.as_ref(),
+        &config,
+        &mut state,
+        env.block.time.seconds(),
+        None,
+    )?;
     compute_borrower_interest(&state, &mut liability);
 
     // Compute ANC reward
-    compute_reward(&mut state, env.block.height);
+    compute_reward(&mut state, env.block.time.seconds());
     compute_borrower_reward(&state, &mut liability);
 
     let claim_amount = liability.pending_rewards * Uint256::one();
@@ -245,19 +304,16 @@ pub fn compute_interest(
     deps: Deps,
     config: &Config,
     state: &mut State,
-    block_height: u64,
+    block_time: u64,
     deposit_amount: Option<Uint256>,
 ) -> StdResult<()> {
-    if state.last_interest_updated >= block_height {
+    if state.last_interest_updated >= block_time {
         return Ok(());
     }
 
-    let aterra_supply = query_supply(deps, deps.api.addr_humanize(&config.aterra_contract)?)?;
-    let balance: Uint256 = query_balance(
-        deps,
-        deps.api.addr_humanize(&config.contract_addr)?,
-        config.stable_denom.to_string(),
-    )? - deposit_amount.unwrap_or_else(Uint256::zero);
+    let aterra_supply = state.aterra_supply;
+    let balance: Uint256 =
+        query_market_balance(deps, config)? - deposit_amount.unwrap_or_else(Uint256::zero);
 
     let borrow_rate_res: BorrowRateResponse = query_borrow_rate(
         deps,
@@ -272,7 +328,8 @@ pub fn compute_interest(
 
     compute_interest_raw(
         state,
-        block_height,
+        &read_fixed_loan_book(deps.storage)?,
+        block_time,
         balance,
         aterra_supply,
         borrow_rate_res.rate,
@@ -289,20 +346,31 @@ pub fn compute_interest(
 // * state.last_interest_updated
 pub fn compute_interest_raw(
     state: &mut State,
-    block_height: u64,
+    fixed_loan_book: &FixedLoanBook,
+    block_time: u64,
     balance: Uint256,
     aterra_supply: Uint256,
     borrow_rate: Decimal256,
     target_deposit_rate: Decimal256,
 ) {
-    if state.last_interest_updated >= block_height {
+    if state.last_interest_updated >= block_time {
         return;
     }
 
-    let passed_blocks = Decimal256::from_uint256(block_height - state.last_interest_updated);
+    let passed_seconds = Decimal256::from_uint256(block_time - state.last_interest_updated);
+
+    // Fixed-rate loans accrue their own interest; the borrow rate
+    // only applies to the variable-rate part of the liabilities
+    let fixed_liabilities = compute_fixed_liabilities(fixed_loan_book, state.last_interest_updated);
+    let variable_liabilities = if state.total_liabilities > fixed_liabilities {
+        state.total_liabilities - fixed_liabilities
+    } else {
+        Decimal256::zero()
+    };
 
-    let interest_factor = passed_blocks * borrow_rate;
-    let interest_accrued = state.total_liabilities * interest_factor;
+    let interest_factor = passed_seconds * borrow_rate;
+    let interest_accrued = variable_liabilities * interest_factor
+        + fixed_loan_book.interest_per_second * passed_seconds;
 
     state.global_interest_index =
         state.global_interest_index * (Decimal256::one() + interest_factor);
@@ -310,16 +378,16 @@ pub fn compute_interest_raw(
 
     let mut exchange_rate = compute_exchange_rate_raw(state, aterra_supply, balance);
     let effective_deposit_rate = exchange_rate / state.prev_exchange_rate;
-    let deposit_rate = (effective_deposit_rate - Decimal256::one()) / passed_blocks;
+    let deposit_rate = (effective_deposit_rate - Decimal256::one()) / passed_seconds;
 
     if deposit_rate > target_deposit_rate {
-        // excess_deposit_rate(_per_block)
+        // excess_deposit_rate(_per_second)
         let excess_deposit_rate = deposit_rate - target_deposit_rate;
         let prev_deposits =
             Decimal256::from_uint256(state.prev_aterra_supply * state.prev_exchange_rate);
 
-        // excess_yield = prev_deposits * excess_deposit_rate(_per_block) * blocks
-        let excess_yield = prev_deposits * passed_blocks * excess_deposit_rate;
+        // excess_yield = prev_deposits * excess_deposit_rate(_per_second) * seconds
+        let excess_yield = prev_deposits * passed_seconds * excess_deposit_rate;
 
         state.total_reserves += excess_yield;
         exchange_rate = compute_exchange_rate_raw(state, aterra_supply, balance);
@@ -327,7 +395,7 @@ pub fn compute_interest_raw(
 
     state.prev_aterra_supply = aterra_supply;
     state.prev_exchange_rate = exchange_rate;
-    state.last_interest_updated = block_height;
+    state.last_interest_updated = block_time;
 }
 
 /// Compute new interest and apply to liability
@@ -338,20 +406,20 @@ pub(crate) fn compute_borrower_interest(state: &State, liability: &mut BorrowerI
 }
 
 /// Compute distributed reward and update global index
-pub fn compute_reward(state: &mut State, block_height: u64) {
-    if state.last_reward_updated >= block_height {
+pub fn compute_reward(state: &mut State, block_time: u64) {
+    if state.last_reward_updated >= block_time {
         return;
     }
 
-    let passed_blocks = Decimal256::from_uint256(block_height - state.last_reward_updated);
-    let reward_accrued = passed_blocks * state.anc_emission_rate;
+    let passed_seconds = Decimal256::from_uint256(block_time - state.last_reward_updated);
+    let reward_accrued = passed_seconds * state.anc_emission_rate;
     let borrow_amount = state.total_liabilities / state.global_interest_index;
 
     if !reward_accrued.is_zero() && !borrow_amount.is_zero() {
         state.global_reward_index += reward_accrued / borrow_amount;
     }
 
-    state.last_reward_updated = block_height;
+    state.last_reward_updated = block_time;
 }
 
 /// Compute reward amount a borrower received
@@ -366,26 +434,24 @@ pub fn query_borrower_info(
     deps: Deps,
     env: Env,
     borrower: Addr,
-    block_height: Option<u64>,
+    block_time: Option<u64>,
 ) -> StdResult<BorrowerInfoResponse> {
-    let mut borrower_info: BorrowerInfo = read_borrower_info(
-        deps.storage,
-        &deps.api.addr_canonicalize(borrower.as_str())?,
-    );
+    let borrower_raw = deps.api.addr_canonicalize(borrower.as_str())?;
+    let mut borrower_info: BorrowerInfo = read_borrower_info(deps.storage, &borrower_raw);
 
-    let block_height = if let Some(block_height) = block_height {
-        block_height
+    let block_time = if let Some(block_time) = block_time {
+        block_time
     } else {
-        env.block.height
+        env.block.time.seconds()
     };
 
     let config: Config = read_config(deps.storage)?;
     let mut state: State = read_state(deps.storage)?;
 
-    compute_interest(deps, &config, &mut state, block_height, None)?;
+    compute_interest(deps, &config, &mut state, block_time, None)?;
     compute_borrower_interest(&state, &mut borrower_info);
 
-    compute_reward(&mut state, block_height);
+    compute_reward(&mut state, block_time);
     compute_borrower_reward(&state, &mut borrower_info);
 
     Ok(BorrowerInfoResponse {
@@ -393,6 +459,7 @@ pub fn query_borrower_info(
         interest_index: borrower_info.interest_index,
         reward_index: borrower_info.reward_index,
         loan_amount: borrower_info.loan_amount,
+        fixed_loan_amount: compute_fixed_loan_amount(deps.storage, &borrower_raw, block_time)?,
         pending_rewards: borrower_info.pending_rewards,
     })
 }
@@ -412,7 +479,7 @@ pub fn query_borrower_infos(
     Ok(BorrowerInfosResponse { borrower_infos })
 }
 
-fn assert_max_borrow_factor(
+pub(crate) fn assert_max_borrow_factor(
     config: &Config,
     state: &State,
     current_balance: Uint256,
@@ -431,8 +498,17 @@ fn assert_max_borrow_factor(
         ));
     }
 
-    // Assert available balance
-    if borrow_amount + state.total_reserves > current_balance {
+    assert_stable_available(config, state, current_balance, borrow_amount)
+}
+
+/// Reserves cannot be lent out
+pub fn assert_stable_available(
+    config: &Config,
+    state: &State,
+    current_balance: Decimal256,
+    amount: Decimal256,
+) -> Result<(), ContractError> {
+    if amount + state.total_reserves > current_balance {
         return Err(ContractError::NoStableAvailable(
             config.stable_denom.clone(),
         ));
@@ -440,3 +516,23 @@ fn assert_max_borrow_factor(
 
     Ok(())
 }
+
+/// New borrows cannot be backed by a collateral whose locked
+/// amounts already provide more than its max_borrow_value
+pub fn assert_max_borrow_value(
+    deps: Deps,
+    overseer: Addr,
+    borrower: Addr,
+) -> Result<(), ContractError> {
+    for elem in query_collateral_usage(deps, overseer, borrower)? {
+        if let Some(max_borrow_value) = elem.max_borrow_value {
+            if elem.borrow_value > max_borrow_value {
+                return Err(ContractError::MaxBorrowValueExceeded(
+                    max_borrow_value.into(),
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -3,31 +3,51 @@ use cosmwasm_std::entry_point;
 
 use crate::borrow::{
     compute_interest, compute_interest_raw, compute_reward, query_borrower_info,
-    query_borrower_infos, repay_stable_from_liquidation,
+    query_borrower_infos, repay_stable, repay_stable_for, repay_stable_from_liquidation,
 };
 use crate::deposit::{compute_exchange_rate_raw, deposit_stable, redeem_stable};
 use crate::error::ContractError;
+use crate::fixed_loan::{query_fixed_loans, repay_stable_fixed, rollover_fixed_loan};
+use crate::flash_loan::{
+    assert_no_flash_loan, flash_loan, query_market_balance, repay_flash_loan, FLASH_LOAN_REPLY_ID,
+};
 use crate::querier::{query_borrow_rate, query_target_deposit_rate};
 use crate::response::MsgInstantiateContractResponse;
-use crate::state::{read_config, read_state, store_config, store_state, Config, State};
+use crate::state::{
+    read_config, read_fixed_loan_book, read_fixed_rate_premium, read_flash_loan_fee, read_state,
+    store_config, store_fixed_rate_premium, store_flash_loan_fee, store_state, Config, State,
+};
 
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{
     attr, from_binary, to_binary, Addr, BankMsg, Binary, CanonicalAddr, Coin, CosmosMsg, Deps,
     DepsMut, Env, MessageInfo, Reply, Response, StdError, StdResult, SubMsg, Uint128, WasmMsg,
 };
+use cw2::set_contract_version;
 use cw20::{Cw20Coin, Cw20ReceiveMsg, MinterResponse};
 
-use moneymarket::common::optional_addr_validate;
+use moneymarket::common::{legacy_rate_to_rate_per_second, optional_addr_validate};
 use moneymarket::interest_model::BorrowRateResponse;
 use moneymarket::market::{
     ConfigResponse, Cw20HookMsg, EpochStateResponse, ExecuteMsg, InstantiateMsg, MigrateMsg,
-    QueryMsg, StateResponse,
+    QueryMsg, StateResponse, SupplyReconciliationResponse,
+};
+use moneymarket::migration::{assert_legacy, assert_upgrade};
+use moneymarket::operator::{
+    approve_operator, query_operator_approval, query_operator_approvals, revoke_operator,
+    OperatorPermission,
 };
-use moneymarket::querier::{deduct_tax, query_balance, query_supply};
+use moneymarket::ownership::{
+    claim_ownership, drop_ownership_proposal, propose_new_owner, query_ownership_proposal,
+};
+use moneymarket::pause::{pause, query_pause_info, unpause, update_guardian};
+use moneymarket::querier::{deduct_tax, query_supply};
 use moneymarket::terraswap::InstantiateMsg as TokenInstantiateMsg;
 use protobuf::Message;
 
+pub const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
+pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
 pub const INITIAL_DEPOSIT_AMOUNT: u128 = 1000000;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -37,6 +57,8 @@ pub fn instantiate(
     info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
     let initial_deposit = info
         .funds
         .iter()
@@ -72,11 +94,12 @@ pub fn instantiate(
         &State {
             total_liabilities: Decimal256::zero(),
             total_reserves: Decimal256::zero(),
-            last_interest_updated: env.block.height,
-            last_reward_updated: env.block.height,
+            last_interest_updated: env.block.time.seconds(),
+            last_reward_updated: env.block.time.seconds(),
             global_interest_index: Decimal256::one(),
             global_reward_index: Decimal256::zero(),
             anc_emission_rate: msg.anc_emission_rate,
+            aterra_supply: Uint256::from(INITIAL_DEPOSIT_AMOUNT),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
         },
@@ -118,6 +141,8 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
+    assert_no_flash_loan(deps.storage)?;
+
     match msg {
         ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
         ExecuteMsg::RegisterContracts {
@@ -138,34 +163,97 @@ pub fn execute(
             )
         }
         ExecuteMsg::UpdateConfig {
-            owner_addr,
             interest_model,
             distribution_model,
             max_borrow_factor,
+            flash_loan_fee,
+            fixed_rate_premium,
         } => {
             let api = deps.api;
             update_config(
                 deps,
                 env,
                 info,
-                optional_addr_validate(api, owner_addr)?,
                 optional_addr_validate(api, interest_model)?,
                 optional_addr_validate(api, distribution_model)?,
                 max_borrow_factor,
+                flash_loan_fee,
+                fixed_rate_premium,
             )
         }
+        ExecuteMsg::ProposeNewOwner { owner, expires_in } => {
+            let config: Config = read_config(deps.storage)?;
+            Ok(propose_new_owner(
+                deps,
+                env,
+                info,
+                config.owner_addr,
+                owner,
+                expires_in,
+            )?)
+        }
+        ExecuteMsg::DropOwnershipProposal {} => {
+            let config: Config = read_config(deps.storage)?;
+            Ok(drop_ownership_proposal(deps, info, config.owner_addr)?)
+        }
+        ExecuteMsg::ClaimOwnership {} => Ok(claim_ownership(deps, env, info, update_owner)?),
+        ExecuteMsg::UpdateGuardian { guardian } => {
+            let config: Config = read_config(deps.storage)?;
+            Ok(update_guardian(deps, info, config.owner_addr, guardian)?)
+        }
+        ExecuteMsg::Pause { actions } => {
+            let config: Config = read_config(deps.storage)?;
+            Ok(pause(deps, info, config.owner_addr, actions)?)
+        }
+        ExecuteMsg::Unpause { actions } => {
+            let config: Config = read_config(deps.storage)?;
+            Ok(unpause(deps, info, config.owner_addr, actions)?)
+        }
         ExecuteMsg::ExecuteEpochOperations {
-            deposit_rate: _,
-            target_deposit_rate: _,
-            threshold_deposit_rate: _,
-            distributed_interest: _,
-        } => Err(ContractError::Deprecated {}),
+            deposit_rate,
+            target_deposit_rate,
+            threshold_deposit_rate,
+            distributed_interest,
+        } => execute_epoch_operations(
+            deps,
+            env,
+            info,
+            deposit_rate,
+            target_deposit_rate,
+            threshold_deposit_rate,
+            distributed_interest,
+        ),
         ExecuteMsg::DepositStable {} => deposit_stable(deps, env, info),
         ExecuteMsg::BorrowStable {
             borrow_amount: _,
             to: _,
         } => Err(ContractError::Deprecated {}),
-        ExecuteMsg::RepayStable {} => Err(ContractError::Deprecated {}),
+        ExecuteMsg::RepayStable {} => repay_stable(deps, env, info),
+        ExecuteMsg::RepayStableFor { borrower } => {
+            let api = deps.api;
+            repay_stable_for(deps, env, info, api.addr_validate(&borrower)?)
+        }
+        ExecuteMsg::FlashLoan { amount, msg } => flash_loan(deps, env, info, amount, msg),
+        ExecuteMsg::BorrowStableFixed {
+            borrow_amount: _,
+            maturity: _,
+            to: _,
+            borrower: _,
+        } => Err(ContractError::Deprecated {}),
+        ExecuteMsg::RepayStableFixed { loan_id, borrower } => {
+            let api = deps.api;
+            repay_stable_fixed(
+                deps,
+                env,
+                info,
+                loan_id,
+                optional_addr_validate(api, borrower)?,
+            )
+        }
+        ExecuteMsg::RolloverFixedLoan { borrower, loan_id } => {
+            let api = deps.api;
+            rollover_fixed_loan(deps, env, api.addr_validate(&borrower)?, loan_id)
+        }
         ExecuteMsg::RepayStableFromLiquidation {
             borrower,
             prev_balance,
@@ -180,11 +268,27 @@ pub fn execute(
             )
         }
         ExecuteMsg::ClaimRewards { to: _ } => Err(ContractError::Deprecated {}),
+        ExecuteMsg::ApproveOperator {
+            operator,
+            permissions,
+            borrow_allowance,
+            expires_at,
+        } => Ok(approve_operator(
+            deps,
+            env,
+            info,
+            &[OperatorPermission::Repay, OperatorPermission::Borrow],
+            operator,
+            permissions,
+            borrow_allowance,
+            expires_at,
+        )?),
+        ExecuteMsg::RevokeOperator { operator } => Ok(revoke_operator(deps, info, operator)?),
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
     match msg.id {
         1 => {
             // get new token's contract address
@@ -201,6 +305,7 @@ pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractE
 
             register_aterra(deps, token_addr)
         }
+        FLASH_LOAN_REPLY_ID => repay_flash_loan(deps, env),
         _ => Err(ContractError::InvalidReplyId {}),
     }
 }
@@ -267,14 +372,16 @@ pub fn register_contracts(
     Ok(Response::default())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn update_config(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    owner_addr: Option<Addr>,
     interest_model: Option<Addr>,
     distribution_model: Option<Addr>,
     max_borrow_factor: Option<Decimal256>,
+    flash_loan_fee: Option<Decimal256>,
+    fixed_rate_premium: Option<Decimal256>,
 ) -> Result<Response, ContractError> {
     let mut config: Config = read_config(deps.storage)?;
 
@@ -283,13 +390,15 @@ pub fn update_config(
         return Err(ContractError::Unauthorized {});
     }
 
-    if let Some(owner_addr) = owner_addr {
-        config.owner_addr = deps.api.addr_canonicalize(owner_addr.as_str())?;
-    }
-
     if interest_model.is_some() {
         let mut state: State = read_state(deps.storage)?;
-        compute_interest(deps.as_ref(), &config, &mut state, env.block.height, None)?;
+        compute_interest(
+            deps.as_ref(),
+            &config,
+            &mut state,
+            env.block.time.seconds(),
+            None,
+        )?;
         store_state(deps.storage, &state)?;
 
         if let Some(interest_model) = interest_model {
@@ -305,10 +414,24 @@ pub fn update_config(
         config.max_borrow_factor = max_borrow_factor;
     }
 
+    if let Some(flash_loan_fee) = flash_loan_fee {
+        store_flash_loan_fee(deps.storage, &flash_loan_fee)?;
+    }
+
+    if let Some(fixed_rate_premium) = fixed_rate_premium {
+        store_fixed_rate_premium(deps.storage, &fixed_rate_premium)?;
+    }
+
     store_config(deps.storage, &config)?;
     Ok(Response::new().add_attributes(vec![attr("action", "update_config")]))
 }
 
+pub fn update_owner(deps: DepsMut, owner_addr: CanonicalAddr) -> StdResult<()> {
+    let mut config: Config = read_config(deps.storage)?;
+    config.owner_addr = owner_addr;
+    store_config(deps.storage, &config)
+}
+
 pub fn execute_epoch_operations(
     deps: DepsMut,
     env: Env,
@@ -326,15 +449,8 @@ pub fn execute_epoch_operations(
     let mut state: State = read_state(deps.storage)?;
 
     // Compute interest and reward before updating anc_emission_rate
-    let aterra_supply = query_supply(
-        deps.as_ref(),
-        deps.api.addr_humanize(&config.aterra_contract)?,
-    )?;
-    let balance: Uint256 = query_balance(
-        deps.as_ref(),
-        deps.api.addr_humanize(&config.contract_addr)?,
-        config.stable_denom.to_string(),
-    )? - distributed_interest;
+    let aterra_supply = state.aterra_supply;
+    let balance: Uint256 = query_market_balance(deps.as_ref(), &config)? - distributed_interest;
 
     let borrow_rate_res: BorrowRateResponse = query_borrow_rate(
         deps.as_ref(),
@@ -346,7 +462,8 @@ pub fn execute_epoch_operations(
 
     compute_interest_raw(
         &mut state,
-        env.block.height,
+        &read_fixed_loan_book(deps.storage)?,
+        env.block.time.seconds(),
         balance,
         aterra_supply,
         borrow_rate_res.rate,
@@ -357,7 +474,7 @@ pub fn execute_epoch_operations(
     state.prev_exchange_rate =
         compute_exchange_rate_raw(&state, aterra_supply, balance + distributed_interest);
 
-    compute_reward(&mut state, env.block.height);
+    compute_reward(&mut state, env.block.time.seconds());
 
     // Compute total_reserves to fund collector contract
     // Update total_reserves and send it to collector contract
@@ -396,29 +513,45 @@ pub fn execute_epoch_operations(
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
-        QueryMsg::State { block_height } => to_binary(&query_state(deps, env, block_height)?),
+        QueryMsg::OwnershipProposal {} => to_binary(&query_ownership_proposal(deps)?),
+        QueryMsg::PauseInfo {} => to_binary(&query_pause_info(deps)?),
+        QueryMsg::State { block_time } => to_binary(&query_state(deps, env, block_time)?),
         QueryMsg::EpochState {
-            block_height,
+            block_time,
             distributed_interest,
-        } => to_binary(&query_epoch_state(
-            deps,
-            block_height,
-            distributed_interest,
-        )?),
+        } => to_binary(&query_epoch_state(deps, block_time, distributed_interest)?),
         QueryMsg::BorrowerInfo {
             borrower,
-            block_height,
+            block_time,
         } => to_binary(&query_borrower_info(
             deps,
             env,
             deps.api.addr_validate(&borrower)?,
-            block_height,
+            block_time,
         )?),
         QueryMsg::BorrowerInfos { start_after, limit } => to_binary(&query_borrower_infos(
             deps,
             optional_addr_validate(deps.api, start_after)?,
             limit,
         )?),
+        QueryMsg::OperatorApproval { owner, operator } => {
+            to_binary(&query_operator_approval(deps, owner, operator)?)
+        }
+        QueryMsg::OperatorApprovals {
+            owner,
+            start_after,
+            limit,
+        } => to_binary(&query_operator_approvals(deps, owner, start_after, limit)?),
+        QueryMsg::SupplyReconciliation {} => to_binary(&query_supply_reconciliation(deps)?),
+        QueryMsg::FixedLoans {
+            borrower,
+            block_time,
+        } => to_binary(&query_fixed_loans(
+            deps,
+            env,
+            deps.api.addr_validate(&borrower)?,
+            block_time,
+        )?),
     }
 }
 
@@ -446,37 +579,39 @@ pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
             .to_string(),
         stable_denom: config.stable_denom,
         max_borrow_factor: config.max_borrow_factor,
+        flash_loan_fee: read_flash_loan_fee(deps.storage)?,
+        fixed_rate_premium: read_fixed_rate_premium(deps.storage)?,
     })
 }
 
-pub fn query_state(deps: Deps, env: Env, block_height: Option<u64>) -> StdResult<StateResponse> {
+pub fn query_state(deps: Deps, env: Env, block_time: Option<u64>) -> StdResult<StateResponse> {
     let mut state: State = read_state(deps.storage)?;
 
-    let block_height = if let Some(block_height) = block_height {
-        block_height
+    let block_time = if let Some(block_time) = block_time {
+        block_time
     } else {
-        env.block.height
+        env.block.time.seconds()
     };
 
-    if block_height < state.last_interest_updated {
+    if block_time < state.last_interest_updated {
         return Err(StdError::generic_err(
-            "block_height must bigger than last_interest_updated",
+            "block_time must bigger than last_interest_updated",
         ));
     }
 
-    if block_height < state.last_reward_updated {
+    if block_time < state.last_reward_updated {
         return Err(StdError::generic_err(
-            "block_height must bigger than last_reward_updated",
+            "block_time must bigger than last_reward_updated",
         ));
     }
 
     let config: Config = read_config(deps.storage)?;
 
-    // Compute interest rate with given block height
-    compute_interest(deps, &config, &mut state, block_height, None)?;
+    // Compute interest rate with given block time
+    compute_interest(deps, &config, &mut state, block_time, None)?;
 
-    // Compute reward rate with given block height
-    compute_reward(&mut state, block_height);
+    // Compute reward rate with given block time
+    compute_reward(&mut state, block_time);
 
     Ok(StateResponse {
         total_liabilities: state.total_liabilities,
@@ -486,6 +621,7 @@ pub fn query_state(deps: Deps, env: Env, block_height: Option<u64>) -> StdResult
         global_interest_index: state.global_interest_index,
         global_reward_index: state.global_reward_index,
         anc_emission_rate: state.anc_emission_rate,
+        aterra_supply: state.aterra_supply,
         prev_aterra_supply: state.prev_aterra_supply,
         prev_exchange_rate: state.prev_exchange_rate,
     })
@@ -493,24 +629,20 @@ pub fn query_state(deps: Deps, env: Env, block_height: Option<u64>) -> StdResult
 
 pub fn query_epoch_state(
     deps: Deps,
-    block_height: Option<u64>,
+    block_time: Option<u64>,
     distributed_interest: Option<Uint256>,
 ) -> StdResult<EpochStateResponse> {
     let config: Config = read_config(deps.storage)?;
     let mut state: State = read_state(deps.storage)?;
 
     let distributed_interest = distributed_interest.unwrap_or_else(Uint256::zero);
-    let aterra_supply = query_supply(deps, deps.api.addr_humanize(&config.aterra_contract)?)?;
-    let balance = query_balance(
-        deps,
-        deps.api.addr_humanize(&config.contract_addr)?,
-        config.stable_denom.to_string(),
-    )? - distributed_interest;
-
-    if let Some(block_height) = block_height {
-        if block_height < state.last_interest_updated {
+    let aterra_supply = state.aterra_supply;
+    let balance = query_market_balance(deps, &config)? - distributed_interest;
+
+    if let Some(block_time) = block_time {
+        if block_time < state.last_interest_updated {
             return Err(StdError::generic_err(
-                "block_height must bigger than last_interest_updated",
+                "block_time must bigger than last_interest_updated",
             ));
         }
 
@@ -528,7 +660,8 @@ pub fn query_epoch_state(
         // Compute interest rate to return latest epoch state
         compute_interest_raw(
             &mut state,
-            block_height,
+            &read_fixed_loan_book(deps.storage)?,
+            block_time,
             balance,
             aterra_supply,
             borrow_rate_res.rate,
@@ -547,7 +680,76 @@ pub fn query_epoch_state(
     })
 }
 
+pub fn query_supply_reconciliation(deps: Deps) -> StdResult<SupplyReconciliationResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let state: State = read_state(deps.storage)?;
+
+    let token_supply = query_supply(deps, deps.api.addr_humanize(&config.aterra_contract)?)?;
+    Ok(SupplyReconciliationResponse {
+        ledger_supply: state.aterra_supply,
+        token_supply,
+        reconciled: state.aterra_supply == token_supply,
+    })
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
+pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> StdResult<Response> {
+    match msg {
+        MigrateMsg::FromLegacy {
+            borrow_rate,
+            target_deposit_rate,
+        } => {
+            assert_legacy(deps.storage)?;
+
+            let config: Config = read_config(deps.storage)?;
+            let mut state: State = read_state(deps.storage)?;
+
+            // Seed the supply ledger from the token before any
+            // exchange rate is computed
+            state.aterra_supply = query_supply(
+                deps.as_ref(),
+                deps.api.addr_humanize(&config.aterra_contract)?,
+            )?;
+            store_state(deps.storage, &state)?;
+
+            // Legacy state tracks block heights instead of block times.
+            // Settle interest and rewards with the per-block rates before
+            // switching units; the rates are passed in since the interest
+            // model and the overseer may already report per-second ones
+            let aterra_supply = state.aterra_supply;
+            compute_interest_raw(
+                &mut state,
+                &read_fixed_loan_book(deps.storage)?,
+                env.block.height,
+                query_market_balance(deps.as_ref(), &config)?,
+                aterra_supply,
+                borrow_rate,
+                target_deposit_rate,
+            );
+            compute_reward(&mut state, env.block.height);
+
+            state.last_interest_updated = env.block.time.seconds();
+            state.last_reward_updated = env.block.time.seconds();
+            state.anc_emission_rate = legacy_rate_to_rate_per_second(state.anc_emission_rate);
+            store_state(deps.storage, &state)?;
+        }
+        MigrateMsg::Upgrade {} => {
+            assert_upgrade(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+            // Versions without the supply ledger
+            let mut state: State = read_state(deps.storage)?;
+            if state.aterra_supply.is_zero() {
+                let config: Config = read_config(deps.storage)?;
+                state.aterra_supply = query_supply(
+                    deps.as_ref(),
+                    deps.api.addr_humanize(&config.aterra_contract)?,
+                )?;
+                store_state(deps.storage, &state)?;
+            }
+        }
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
     Ok(Response::default())
 }
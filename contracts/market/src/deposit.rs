@@ -6,16 +6,20 @@ use cosmwasm_std::{
 
 use crate::borrow::{compute_interest, compute_reward};
 use crate::error::ContractError;
+use crate::flash_loan::query_market_balance;
 use crate::state::{read_config, read_state, store_state, Config, State};
 
 use cw20::Cw20ExecuteMsg;
-use moneymarket::querier::{deduct_tax, query_balance, query_supply};
+use moneymarket::pause::{assert_not_paused, PauseAction};
+use moneymarket::querier::{deduct_tax, query_balance};
 
 pub fn deposit_stable(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
 ) -> Result<Response, ContractError> {
+    assert_not_paused(deps.storage, PauseAction::Deposit)?;
+
     let config: Config = read_config(deps.storage)?;
 
     // Check base denom deposit
@@ -37,16 +41,17 @@ pub fn deposit_stable(
         deps.as_ref(),
         &config,
         &mut state,
-        env.block.height,
+        env.block.time.seconds(),
         Some(deposit_amount),
     )?;
-    compute_reward(&mut state, env.block.height);
+    compute_reward(&mut state, env.block.time.seconds());
 
     // Load anchor token exchange rate with updated state
     let exchange_rate =
         compute_exchange_rate(deps.as_ref(), &config, &state, Some(deposit_amount))?;
     let mint_amount = deposit_amount / exchange_rate;
 
+    state.aterra_supply += mint_amount;
     state.prev_aterra_supply += mint_amount;
     store_state(deps.storage, &state)?;
     Ok(Response::new()
@@ -72,12 +77,20 @@ pub fn redeem_stable(
     sender: Addr,
     burn_amount: Uint128,
 ) -> Result<Response, ContractError> {
+    assert_not_paused(deps.storage, PauseAction::Redeem)?;
+
     let config: Config = read_config(deps.storage)?;
 
     // Update interest related state
     let mut state: State = read_state(deps.storage)?;
-    compute_interest(deps.as_ref(), &config, &mut state, env.block.height, None)?;
-    compute_reward(&mut state, env.block.height);
+    compute_interest(
+        deps.as_ref(),
+        &config,
+        &mut state,
+        env.block.time.seconds(),
+        None,
+    )?;
+    compute_reward(&mut state, env.block.time.seconds());
 
     // Load anchor token exchange rate with updated state
     let exchange_rate = compute_exchange_rate(deps.as_ref(), &config, &state, None)?;
@@ -92,6 +105,7 @@ pub fn redeem_stable(
     // Assert redeem amount
     assert_redeem_amount(&config, &state, current_balance, redeem_amount)?;
 
+    state.aterra_supply = state.aterra_supply - Uint256::from(burn_amount);
     state.prev_aterra_supply = state.prev_aterra_supply - Uint256::from(burn_amount);
     store_state(deps.storage, &state)?;
     Ok(Response::new()
@@ -144,14 +158,14 @@ pub(crate) fn compute_exchange_rate(
     state: &State,
     deposit_amount: Option<Uint256>,
 ) -> StdResult<Decimal256> {
-    let aterra_supply = query_supply(deps, deps.api.addr_humanize(&config.aterra_contract)?)?;
-    let balance = query_balance(
-        deps,
-        deps.api.addr_humanize(&config.contract_addr)?,
-        config.stable_denom.to_string(),
-    )? - deposit_amount.unwrap_or_else(Uint256::zero);
-
-    Ok(compute_exchange_rate_raw(state, aterra_supply, balance))
+    let balance =
+        query_market_balance(deps, config)? - deposit_amount.unwrap_or_else(Uint256::zero);
+
+    Ok(compute_exchange_rate_raw(
+        state,
+        state.aterra_supply,
+        balance,
+    ))
 }
 
 pub fn compute_exchange_rate_raw(
@@ -15,6 +15,9 @@ pub enum ContractError {
     #[error("Borrow amount too high; Loan liability becomes greater than borrow limit: {0}")]
     BorrowExceedsLimit(u128),
 
+    #[error("Collateral provides more than its borrow value cap; max borrow value: {0}")]
+    MaxBorrowValueExceeded(u128),
+
     #[error("Must deposit initial funds {0}{0}")]
     InitialFundsNotDeposited(u128, String),
 
@@ -38,4 +41,25 @@ pub enum ContractError {
 
     #[error("Functionality deprecated")]
     Deprecated {},
+
+    #[error("Flash loans are disabled")]
+    FlashLoanDisabled {},
+
+    #[error("Another flash loan is in progress")]
+    FlashLoanInProgress {},
+
+    #[error("Flash loan not repaid; the amount and a fee of {0} must be returned")]
+    FlashLoanNotRepaid(u128),
+
+    #[error("Fixed-rate borrowing is disabled")]
+    FixedRateDisabled {},
+
+    #[error("Maturity must be later than the current block time")]
+    InvalidMaturity {},
+
+    #[error("Fixed-rate loan has not matured")]
+    FixedLoanNotMatured {},
+
+    #[error("Cannot open more than {0} fixed-rate loans")]
+    TooManyFixedLoans(u64),
 }
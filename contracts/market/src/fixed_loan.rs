@@ -0,0 +1,445 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    attr, Addr, BankMsg, CanonicalAddr, Coin, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Response,
+    StdResult, Storage,
+};
+use moneymarket::interest_model::BorrowRateResponse;
+use moneymarket::market::{FixedLoanResponse, FixedLoansResponse};
+use moneymarket::operator::{assert_operator, use_borrow_allowance, OperatorPermission};
+use moneymarket::overseer::BorrowLimitResponse;
+use moneymarket::pause::{assert_not_paused, PauseAction};
+use moneymarket::querier::{deduct_tax, query_balance};
+
+use crate::borrow::{
+    assert_max_borrow_factor, assert_max_borrow_value, clear_margin_call_msg,
+    compute_borrower_interest, compute_borrower_reward, compute_interest, compute_reward,
+};
+use crate::error::ContractError;
+use crate::querier::{query_borrow_limit, query_borrow_rate};
+use crate::state::{
+    read_borrower_info, read_config, read_fixed_loan, read_fixed_loan_book, read_fixed_loans,
+    read_fixed_rate_premium, read_state, remove_fixed_loan, store_borrower_info, store_fixed_loan,
+    store_fixed_loan_book, store_state, BorrowerInfo, Config, FixedLoan, FixedLoanBook, State,
+};
+
+/// Bounds the loans iterated for the borrow limit checks
+pub const MAX_FIXED_LOANS: u64 = 10;
+
+pub fn borrow_stable_fixed(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    borrow_amount: Uint256,
+    maturity: Option<u64>,
+    to: Option<Addr>,
+    borrower: Option<Addr>,
+) -> Result<Response, ContractError> {
+    assert_not_paused(deps.storage, PauseAction::Borrow)?;
+
+    let rate_premium = match read_fixed_rate_premium(deps.storage)? {
+        Some(rate_premium) => rate_premium,
+        None => return Err(ContractError::FixedRateDisabled {}),
+    };
+
+    let block_time = env.block.time.seconds();
+    if let Some(maturity) = maturity {
+        if maturity <= block_time {
+            return Err(ContractError::InvalidMaturity {});
+        }
+    }
+
+    let config: Config = read_config(deps.storage)?;
+    let mut state: State = read_state(deps.storage)?;
+
+    let borrower = borrower.unwrap_or_else(|| info.sender.clone());
+    let borrower_raw = deps.api.addr_canonicalize(borrower.as_str())?;
+    if borrower != info.sender {
+        use_borrow_allowance(
+            deps.storage,
+            &borrower_raw,
+            &deps.api.addr_canonicalize(info.sender.as_str())?,
+            borrow_amount,
+            block_time,
+        )?;
+    }
+
+    let mut liability: BorrowerInfo = read_borrower_info(deps.storage, &borrower_raw);
+
+    // Compute interest
+    compute_interest(deps.as_ref(), &config, &mut state, block_time, None)?;
+    compute_borrower_interest(&state, &mut liability);
+    compute_reward(&mut state, block_time);
+
+    let loans = read_fixed_loans(deps.storage, &borrower_raw)?;
+    if loans.len() as u64 >= MAX_FIXED_LOANS {
+        return Err(ContractError::TooManyFixedLoans(MAX_FIXED_LOANS));
+    }
+
+    let overseer = deps.api.addr_humanize(&config.overseer_contract)?;
+    let borrow_limit_res: BorrowLimitResponse = query_borrow_limit(
+        deps.as_ref(),
+        overseer.clone(),
+        borrower.clone(),
+        Some(block_time),
+    )?;
+
+    let fixed_loan_amount = compute_fixed_loan_amount(deps.storage, &borrower_raw, block_time)?;
+    if borrow_limit_res.borrow_limit < borrow_amount + liability.loan_amount + fixed_loan_amount {
+        return Err(ContractError::BorrowExceedsLimit(
+            borrow_limit_res.borrow_limit.into(),
+        ));
+    }
+    assert_max_borrow_value(deps.as_ref(), overseer, borrower.clone())?;
+
+    let current_balance = query_balance(
+        deps.as_ref(),
+        env.contract.address,
+        config.stable_denom.to_string(),
+    )?;
+
+    // Assert borrow amount
+    assert_max_borrow_factor(&config, &state, current_balance, borrow_amount)?;
+
+    // Lock the borrow rate before the loan changes the utilization
+    let borrow_rate_res: BorrowRateResponse = query_borrow_rate(
+        deps.as_ref(),
+        deps.api.addr_humanize(&config.interest_model)?,
+        current_balance,
+        state.total_liabilities,
+        state.total_reserves,
+    )?;
+    let rate = borrow_rate_res.rate + rate_premium;
+
+    let mut book: FixedLoanBook = read_fixed_loan_book(deps.storage)?;
+    compute_fixed_loan_book(&mut book, block_time);
+
+    let loan_id = book.next_loan_id;
+    book.next_loan_id += 1;
+    book.total_liabilities += Decimal256::from_uint256(borrow_amount);
+    book.interest_per_second += Decimal256::from_uint256(borrow_amount) * rate;
+    state.total_liabilities += Decimal256::from_uint256(borrow_amount);
+
+    store_fixed_loan(
+        deps.storage,
+        &borrower_raw,
+        loan_id,
+        &FixedLoan {
+            principal: borrow_amount,
+            interest: Decimal256::zero(),
+            rate,
+            last_accrued: block_time,
+            maturity,
+        },
+    )?;
+    store_fixed_loan_book(deps.storage, &book)?;
+    store_state(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_message(CosmosMsg::Bank(BankMsg::Send {
+            to_address: to.unwrap_or_else(|| borrower.clone()).to_string(),
+            amount: vec![deduct_tax(
+                deps.as_ref(),
+                Coin {
+                    denom: config.stable_denom,
+                    amount: borrow_amount.into(),
+                },
+            )?],
+        }))
+        .add_attributes(vec![
+            attr("action", "borrow_stable_fixed"),
+            attr("borrower", borrower),
+            attr("loan_id", loan_id.to_string()),
+            attr("borrow_amount", borrow_amount),
+            attr("rate", rate.to_string()),
+        ]))
+}
+
+pub fn repay_stable_fixed(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    loan_id: u64,
+    borrower: Option<Addr>,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+
+    // Check stable denom deposit
+    let amount: Uint256 = info
+        .funds
+        .iter()
+        .find(|c| c.denom == config.stable_denom)
+        .map(|c| Uint256::from(c.amount))
+        .unwrap_or_else(Uint256::zero);
+
+    // Cannot deposit zero amount
+    if amount.is_zero() {
+        return Err(ContractError::ZeroRepay(config.stable_denom));
+    }
+
+    let block_time = env.block.time.seconds();
+    let borrower = borrower.unwrap_or_else(|| info.sender.clone());
+    let borrower_raw = deps.api.addr_canonicalize(borrower.as_str())?;
+    if borrower != info.sender {
+        assert_operator(
+            deps.storage,
+            &borrower_raw,
+            &deps.api.addr_canonicalize(info.sender.as_str())?,
+            OperatorPermission::Repay,
+            block_time,
+        )?;
+    }
+
+    let mut loan: FixedLoan = read_fixed_loan(deps.storage, &borrower_raw, loan_id)?;
+
+    let mut state: State = read_state(deps.storage)?;
+    compute_interest(deps.as_ref(), &config, &mut state, block_time, Some(amount))?;
+    compute_reward(&mut state, block_time);
+
+    let mut book: FixedLoanBook = read_fixed_loan_book(deps.storage)?;
+    compute_fixed_loan_book(&mut book, block_time);
+    compute_fixed_loan_interest(&mut loan, block_time);
+
+    let repay_amount = repay_fixed_loan(&mut state, &mut book, &mut loan, amount);
+    if loan.principal.is_zero() {
+        remove_fixed_loan(deps.storage, &borrower_raw, loan_id);
+    } else {
+        store_fixed_loan(deps.storage, &borrower_raw, loan_id, &loan)?;
+    }
+
+    store_fixed_loan_book(deps.storage, &book)?;
+    store_state(deps.storage, &state)?;
+
+    let clear_margin_call = clear_margin_call_msg(deps.as_ref(), &config, &borrower)?;
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if repay_amount < amount {
+        // Payback left repay amount to sender
+        messages.push(CosmosMsg::Bank(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![deduct_tax(
+                deps.as_ref(),
+                Coin {
+                    denom: config.stable_denom,
+                    amount: (amount - repay_amount).into(),
+                },
+            )?],
+        }));
+    }
+    messages.push(clear_margin_call);
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "repay_stable_fixed"),
+        attr("borrower", borrower),
+        attr("loan_id", loan_id.to_string()),
+        attr("repay_amount", repay_amount),
+    ]))
+}
+
+/// Moves a matured loan with its interest into the variable-rate loan
+pub fn rollover_fixed_loan(
+    deps: DepsMut,
+    env: Env,
+    borrower: Addr,
+    loan_id: u64,
+) -> Result<Response, ContractError> {
+    let block_time = env.block.time.seconds();
+    let borrower_raw = deps.api.addr_canonicalize(borrower.as_str())?;
+    let mut loan: FixedLoan = read_fixed_loan(deps.storage, &borrower_raw, loan_id)?;
+    match loan.maturity {
+        Some(maturity) if maturity <= block_time => {}
+        _ => return Err(ContractError::FixedLoanNotMatured {}),
+    }
+
+    let config: Config = read_config(deps.storage)?;
+    let mut state: State = read_state(deps.storage)?;
+    let mut liability: BorrowerInfo = read_borrower_info(deps.storage, &borrower_raw);
+
+    // Compute interest
+    compute_interest(deps.as_ref(), &config, &mut state, block_time, None)?;
+    compute_borrower_interest(&state, &mut liability);
+
+    // Compute ANC reward
+    compute_reward(&mut state, block_time);
+    compute_borrower_reward(&state, &mut liability);
+
+    let mut book: FixedLoanBook = read_fixed_loan_book(deps.storage)?;
+    compute_fixed_loan_book(&mut book, block_time);
+    compute_fixed_loan_interest(&mut loan, block_time);
+
+    // Close the fixed-rate loan with its full amount and
+    // open the same debt on the variable-rate side
+    let loan_amount = loan.principal + loan.interest * Uint256::one();
+    repay_fixed_loan(&mut state, &mut book, &mut loan, loan_amount);
+    liability.loan_amount += loan_amount;
+    state.total_liabilities += Decimal256::from_uint256(loan_amount);
+
+    remove_fixed_loan(deps.storage, &borrower_raw, loan_id);
+    store_borrower_info(deps.storage, &borrower_raw, &liability)?;
+    store_fixed_loan_book(deps.storage, &book)?;
+    store_state(deps.storage, &state)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "rollover_fixed_loan"),
+        attr("borrower", borrower),
+        attr("loan_id", loan_id.to_string()),
+        attr("loan_amount", loan_amount),
+    ]))
+}
+
+/// Repays the fixed-rate loans of the borrower in the order they were
+/// opened and returns the amount used; the funds must already be held
+/// by the market
+pub(crate) fn repay_fixed_loans(
+    deps: DepsMut,
+    env: &Env,
+    borrower_raw: &CanonicalAddr,
+    amount: Uint256,
+) -> Result<Uint256, ContractError> {
+    let loans = read_fixed_loans(deps.storage, borrower_raw)?;
+    if loans.is_empty() {
+        return Ok(Uint256::zero());
+    }
+
+    let block_time = env.block.time.seconds();
+    let config: Config = read_config(deps.storage)?;
+    let mut state: State = read_state(deps.storage)?;
+    compute_interest(deps.as_ref(), &config, &mut state, block_time, Some(amount))?;
+    compute_reward(&mut state, block_time);
+
+    let mut book: FixedLoanBook = read_fixed_loan_book(deps.storage)?;
+    compute_fixed_loan_book(&mut book, block_time);
+
+    let mut repay_amount = Uint256::zero();
+    for (loan_id, mut loan) in loans.into_iter() {
+        if repay_amount == amount {
+            break;
+        }
+
+        compute_fixed_loan_interest(&mut loan, block_time);
+        repay_amount += repay_fixed_loan(&mut state, &mut book, &mut loan, amount - repay_amount);
+        if loan.principal.is_zero() {
+            remove_fixed_loan(deps.storage, borrower_raw, loan_id);
+        } else {
+            store_fixed_loan(deps.storage, borrower_raw, loan_id, &loan)?;
+        }
+    }
+
+    store_fixed_loan_book(deps.storage, &book)?;
+    store_state(deps.storage, &state)?;
+
+    Ok(repay_amount)
+}
+
+/// Applies amount to the accrued interest and then to the principal;
+/// the loan is closed, leaving a zero principal, once it is fully
+/// repaid. Returns the amount used
+fn repay_fixed_loan(
+    state: &mut State,
+    book: &mut FixedLoanBook,
+    loan: &mut FixedLoan,
+    amount: Uint256,
+) -> Uint256 {
+    let loan_amount = loan.principal + loan.interest * Uint256::one();
+    if amount >= loan_amount {
+        let liabilities = Decimal256::from_uint256(loan.principal) + loan.interest;
+        book.total_liabilities = book.total_liabilities - liabilities;
+        book.interest_per_second =
+            book.interest_per_second - Decimal256::from_uint256(loan.principal) * loan.rate;
+        state.total_liabilities = state.total_liabilities - liabilities;
+
+        loan.principal = Uint256::zero();
+        loan.interest = Decimal256::zero();
+        return loan_amount;
+    }
+
+    let interest = loan.interest * Uint256::one();
+    if amount > interest {
+        let principal_repaid = amount - interest;
+        book.interest_per_second =
+            book.interest_per_second - Decimal256::from_uint256(principal_repaid) * loan.rate;
+        loan.principal = loan.principal - principal_repaid;
+        loan.interest = loan.interest - Decimal256::from_uint256(interest);
+    } else {
+        loan.interest = loan.interest - Decimal256::from_uint256(amount);
+    }
+
+    book.total_liabilities = book.total_liabilities - Decimal256::from_uint256(amount);
+    state.total_liabilities = state.total_liabilities - Decimal256::from_uint256(amount);
+
+    amount
+}
+
+/// Compute the simple interest of the loan since its last accrual
+pub(crate) fn compute_fixed_loan_interest(loan: &mut FixedLoan, block_time: u64) {
+    if loan.last_accrued >= block_time {
+        return;
+    }
+
+    let passed_seconds = Decimal256::from_uint256(block_time - loan.last_accrued);
+    loan.interest += Decimal256::from_uint256(loan.principal) * loan.rate * passed_seconds;
+    loan.last_accrued = block_time;
+}
+
+pub(crate) fn compute_fixed_loan_book(book: &mut FixedLoanBook, block_time: u64) {
+    book.total_liabilities = compute_fixed_liabilities(book, block_time);
+    if book.last_updated < block_time {
+        book.last_updated = block_time;
+    }
+}
+
+/// Principal and interest of all fixed-rate loans at block_time
+pub fn compute_fixed_liabilities(book: &FixedLoanBook, block_time: u64) -> Decimal256 {
+    if book.last_updated >= block_time {
+        return book.total_liabilities;
+    }
+
+    let passed_seconds = Decimal256::from_uint256(block_time - book.last_updated);
+    book.total_liabilities + book.interest_per_second * passed_seconds
+}
+
+/// Principal and interest of the fixed-rate loans of the borrower
+pub(crate) fn compute_fixed_loan_amount(
+    storage: &dyn Storage,
+    borrower_raw: &CanonicalAddr,
+    block_time: u64,
+) -> StdResult<Uint256> {
+    let mut loan_amount = Uint256::zero();
+    for (_, mut loan) in read_fixed_loans(storage, borrower_raw)?.into_iter() {
+        compute_fixed_loan_interest(&mut loan, block_time);
+        loan_amount += loan.principal + loan.interest * Uint256::one();
+    }
+
+    Ok(loan_amount)
+}
+
+pub fn query_fixed_loans(
+    deps: Deps,
+    env: Env,
+    borrower: Addr,
+    block_time: Option<u64>,
+) -> StdResult<FixedLoansResponse> {
+    let block_time = if let Some(block_time) = block_time {
+        block_time
+    } else {
+        env.block.time.seconds()
+    };
+
+    let borrower_raw = deps.api.addr_canonicalize(borrower.as_str())?;
+    let loans: Vec<FixedLoanResponse> = read_fixed_loans(deps.storage, &borrower_raw)?
+        .into_iter()
+        .map(|(loan_id, mut loan)| {
+            compute_fixed_loan_interest(&mut loan, block_time);
+            FixedLoanResponse {
+                loan_id,
+                principal: loan.principal,
+                loan_amount: loan.principal + loan.interest * Uint256::one(),
+                rate: loan.rate,
+                maturity: loan.maturity,
+            }
+        })
+        .collect();
+
+    Ok(FixedLoansResponse {
+        borrower: borrower.to_string(),
+        loans,
+    })
+}
@@ -0,0 +1,148 @@
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    attr, BankMsg, Binary, Coin, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Response, StdError,
+    StdResult, Storage, SubMsg, WasmMsg,
+};
+
+use crate::borrow::{assert_stable_available, compute_interest, compute_reward};
+use crate::error::ContractError;
+use crate::state::{
+    read_config, read_flash_loan, read_flash_loan_fee, read_state, remove_flash_loan,
+    store_flash_loan, store_state, Config, FlashLoan, State,
+};
+
+use moneymarket::pause::{assert_not_paused, PauseAction};
+use moneymarket::querier::{deduct_tax, query_balance};
+
+pub const FLASH_LOAN_REPLY_ID: u64 = 2;
+
+/// Sends amount to the sender and executes msg on it; the balance is
+/// checked once the callback returns. Flash loans are not recorded as
+/// liabilities, so the max borrow factor does not apply to them
+pub fn flash_loan(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Uint256,
+    msg: Binary,
+) -> Result<Response, ContractError> {
+    assert_not_paused(deps.storage, PauseAction::Borrow)?;
+
+    let config: Config = read_config(deps.storage)?;
+    let fee_rate = match read_flash_loan_fee(deps.storage)? {
+        Some(fee_rate) => fee_rate,
+        None => return Err(ContractError::FlashLoanDisabled {}),
+    };
+
+    // Settle interest and rewards with the balance before the loan
+    let mut state: State = read_state(deps.storage)?;
+    compute_interest(
+        deps.as_ref(),
+        &config,
+        &mut state,
+        env.block.time.seconds(),
+        None,
+    )?;
+    compute_reward(&mut state, env.block.time.seconds());
+    store_state(deps.storage, &state)?;
+
+    let prev_balance = query_balance(
+        deps.as_ref(),
+        env.contract.address,
+        config.stable_denom.to_string(),
+    )?;
+    assert_stable_available(
+        &config,
+        &state,
+        Decimal256::from_uint256(prev_balance),
+        Decimal256::from_uint256(amount),
+    )?;
+
+    let fee = amount * fee_rate;
+    store_flash_loan(
+        deps.storage,
+        &FlashLoan {
+            amount,
+            prev_balance,
+            fee,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_message(CosmosMsg::Bank(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![deduct_tax(
+                deps.as_ref(),
+                Coin {
+                    denom: config.stable_denom,
+                    amount: amount.into(),
+                },
+            )?],
+        }))
+        .add_submessage(SubMsg::reply_on_success(
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: info.sender.to_string(),
+                msg,
+                funds: vec![],
+            }),
+            FLASH_LOAN_REPLY_ID,
+        ))
+        .add_attributes(vec![
+            attr("action", "flash_loan"),
+            attr("borrower", info.sender),
+            attr("amount", amount),
+            attr("fee", fee),
+        ]))
+}
+
+/// Fails the whole transaction unless the lent amount and
+/// the fee are back; the fee is added to the reserves
+pub fn repay_flash_loan(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let flash_loan = read_flash_loan(deps.storage)?
+        .ok_or_else(|| StdError::generic_err("No flash loan in progress"))?;
+    remove_flash_loan(deps.storage);
+
+    let cur_balance = query_balance(
+        deps.as_ref(),
+        env.contract.address,
+        config.stable_denom.to_string(),
+    )?;
+    if cur_balance < flash_loan.prev_balance + flash_loan.fee {
+        return Err(ContractError::FlashLoanNotRepaid(flash_loan.fee.into()));
+    }
+
+    let mut state: State = read_state(deps.storage)?;
+    state.total_reserves += Decimal256::from_uint256(flash_loan.fee);
+    store_state(deps.storage, &state)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "repay_flash_loan"),
+        attr("fee", flash_loan.fee),
+    ]))
+}
+
+/// The callback must not change the market state while
+/// the lent amount is missing from the balance
+pub fn assert_no_flash_loan(storage: &dyn Storage) -> Result<(), ContractError> {
+    if read_flash_loan(storage)?.is_some() {
+        return Err(ContractError::FlashLoanInProgress {});
+    }
+
+    Ok(())
+}
+
+/// Stable balance of the market, counting the amount
+/// of an open flash loan as still held
+pub(crate) fn query_market_balance(deps: Deps, config: &Config) -> StdResult<Uint256> {
+    let balance = query_balance(
+        deps,
+        deps.api.addr_humanize(&config.contract_addr)?,
+        config.stable_denom.to_string(),
+    )?;
+
+    Ok(match read_flash_loan(deps.storage)? {
+        Some(flash_loan) => balance + flash_loan.amount,
+        None => balance,
+    })
+}
@@ -2,6 +2,8 @@ pub mod borrow;
 pub mod contract;
 pub mod deposit;
 pub mod error;
+pub mod fixed_loan;
+pub mod flash_loan;
 pub mod querier;
 pub mod response;
 pub mod state;
@@ -3,7 +3,10 @@ use cosmwasm_std::{to_binary, Addr, Deps, QueryRequest, StdResult, WasmQuery};
 
 use moneymarket::distribution_model::{AncEmissionRateResponse, QueryMsg as DistributionQueryMsg};
 use moneymarket::interest_model::{BorrowRateResponse, QueryMsg as InterestQueryMsg};
-use moneymarket::overseer::{BorrowLimitResponse, ConfigResponse, QueryMsg as OverseerQueryMsg};
+use moneymarket::overseer::{
+    BorrowLimitResponse, CollateralUsageResponse, CollateralUsageResponseElem, CollateralsResponse,
+    ConfigResponse, QueryMsg as OverseerQueryMsg,
+};
 
 pub fn query_borrow_rate(
     deps: Deps,
@@ -43,6 +46,37 @@ pub fn query_borrow_limit(
     Ok(borrow_limit)
 }
 
+/// Usage of the collaterals locked by the borrower
+pub fn query_collateral_usage(
+    deps: Deps,
+    overseer_addr: Addr,
+    borrower: Addr,
+) -> StdResult<Vec<CollateralUsageResponseElem>> {
+    let collaterals: CollateralsResponse =
+        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: overseer_addr.to_string(),
+            msg: to_binary(&OverseerQueryMsg::Collaterals {
+                borrower: borrower.to_string(),
+            })?,
+        }))?;
+
+    let mut elems: Vec<CollateralUsageResponseElem> = vec![];
+    for (collateral_token, _) in collaterals.collaterals {
+        let usage: CollateralUsageResponse =
+            deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+                contract_addr: overseer_addr.to_string(),
+                msg: to_binary(&OverseerQueryMsg::CollateralUsage {
+                    collateral_token: Some(collateral_token),
+                    start_after: None,
+                    limit: None,
+                })?,
+            }))?;
+        elems.extend(usage.elems);
+    }
+
+    Ok(elems)
+}
+
 pub fn query_anc_emission_rate(
     deps: Deps,
     distribution_model: Addr,
@@ -3,14 +3,19 @@ use serde::{Deserialize, Serialize};
 
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{CanonicalAddr, Deps, Order, StdResult, Storage};
-use cosmwasm_storage::{bucket, bucket_read, ReadonlyBucket, ReadonlySingleton, Singleton};
+use cosmwasm_storage::{bucket, bucket_read, Bucket, ReadonlyBucket, ReadonlySingleton, Singleton};
 
 use moneymarket::market::BorrowerInfoResponse;
 
 pub const KEY_CONFIG: &[u8] = b"config";
 pub const KEY_STATE: &[u8] = b"state";
+pub const KEY_FLASH_LOAN_FEE: &[u8] = b"flash_loan_fee";
+pub const KEY_FLASH_LOAN: &[u8] = b"flash_loan";
+pub const KEY_FIXED_RATE_PREMIUM: &[u8] = b"fixed_rate_premium";
+pub const KEY_FIXED_LOAN_BOOK: &[u8] = b"fixed_loan_book";
 
 const PREFIX_LIABILITY: &[u8] = b"liability";
+const PREFIX_FIXED_LOAN: &[u8] = b"fixed_loan";
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
@@ -35,10 +40,44 @@ pub struct State {
     pub global_interest_index: Decimal256,
     pub global_reward_index: Decimal256,
     pub anc_emission_rate: Decimal256,
+    /// aterra supply ledger; the token supply only mirrors it
+    #[serde(default = "Uint256::zero")]
+    pub aterra_supply: Uint256,
     pub prev_aterra_supply: Uint256,
     pub prev_exchange_rate: Decimal256,
 }
 
+/// Flash loan waiting for its repayment check in the reply
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FlashLoan {
+    pub amount: Uint256,
+    pub prev_balance: Uint256,
+    pub fee: Uint256,
+}
+
+/// Totals of all fixed-rate loans. Those accrue simple interest on
+/// their principal, so the book grows by interest_per_second
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FixedLoanBook {
+    /// Principal and interest of the open loans at last_updated
+    pub total_liabilities: Decimal256,
+    /// Sum of principal * rate over the open loans
+    pub interest_per_second: Decimal256,
+    pub last_updated: u64,
+    pub next_loan_id: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FixedLoan {
+    pub principal: Uint256,
+    /// Interest accrued up to last_accrued
+    pub interest: Decimal256,
+    /// Borrow rate per second locked at origination
+    pub rate: Decimal256,
+    pub last_accrued: u64,
+    pub maturity: Option<u64>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct BorrowerInfo {
     pub interest_index: Decimal256,
@@ -63,6 +102,91 @@ pub fn read_state(storage: &dyn Storage) -> StdResult<State> {
     ReadonlySingleton::new(storage, KEY_STATE).load()
 }
 
+pub fn store_flash_loan_fee(storage: &mut dyn Storage, data: &Decimal256) -> StdResult<()> {
+    Singleton::new(storage, KEY_FLASH_LOAN_FEE).save(data)
+}
+
+pub fn read_flash_loan_fee(storage: &dyn Storage) -> StdResult<Option<Decimal256>> {
+    ReadonlySingleton::new(storage, KEY_FLASH_LOAN_FEE).may_load()
+}
+
+pub fn store_flash_loan(storage: &mut dyn Storage, data: &FlashLoan) -> StdResult<()> {
+    Singleton::new(storage, KEY_FLASH_LOAN).save(data)
+}
+
+pub fn read_flash_loan(storage: &dyn Storage) -> StdResult<Option<FlashLoan>> {
+    ReadonlySingleton::new(storage, KEY_FLASH_LOAN).may_load()
+}
+
+pub fn remove_flash_loan(storage: &mut dyn Storage) {
+    Singleton::<FlashLoan>::new(storage, KEY_FLASH_LOAN).remove()
+}
+
+pub fn store_fixed_rate_premium(storage: &mut dyn Storage, data: &Decimal256) -> StdResult<()> {
+    Singleton::new(storage, KEY_FIXED_RATE_PREMIUM).save(data)
+}
+
+pub fn read_fixed_rate_premium(storage: &dyn Storage) -> StdResult<Option<Decimal256>> {
+    ReadonlySingleton::new(storage, KEY_FIXED_RATE_PREMIUM).may_load()
+}
+
+pub fn store_fixed_loan_book(storage: &mut dyn Storage, data: &FixedLoanBook) -> StdResult<()> {
+    Singleton::new(storage, KEY_FIXED_LOAN_BOOK).save(data)
+}
+
+pub fn read_fixed_loan_book(storage: &dyn Storage) -> StdResult<FixedLoanBook> {
+    match ReadonlySingleton::new(storage, KEY_FIXED_LOAN_BOOK).may_load()? {
+        Some(book) => Ok(book),
+        None => Ok(FixedLoanBook {
+            total_liabilities: Decimal256::zero(),
+            interest_per_second: Decimal256::zero(),
+            last_updated: 0,
+            next_loan_id: 1,
+        }),
+    }
+}
+
+pub fn store_fixed_loan(
+    storage: &mut dyn Storage,
+    borrower: &CanonicalAddr,
+    loan_id: u64,
+    loan: &FixedLoan,
+) -> StdResult<()> {
+    Bucket::multilevel(storage, &[PREFIX_FIXED_LOAN, borrower.as_slice()])
+        .save(&loan_id.to_be_bytes(), loan)
+}
+
+pub fn read_fixed_loan(
+    storage: &dyn Storage,
+    borrower: &CanonicalAddr,
+    loan_id: u64,
+) -> StdResult<FixedLoan> {
+    ReadonlyBucket::multilevel(storage, &[PREFIX_FIXED_LOAN, borrower.as_slice()])
+        .load(&loan_id.to_be_bytes())
+}
+
+pub fn remove_fixed_loan(storage: &mut dyn Storage, borrower: &CanonicalAddr, loan_id: u64) {
+    Bucket::<FixedLoan>::multilevel(storage, &[PREFIX_FIXED_LOAN, borrower.as_slice()])
+        .remove(&loan_id.to_be_bytes())
+}
+
+/// Returns the open fixed-rate loans of the borrower ordered by id;
+/// their number is capped at origination
+pub fn read_fixed_loans(
+    storage: &dyn Storage,
+    borrower: &CanonicalAddr,
+) -> StdResult<Vec<(u64, FixedLoan)>> {
+    ReadonlyBucket::multilevel(storage, &[PREFIX_FIXED_LOAN, borrower.as_slice()])
+        .range(None, None, Order::Ascending)
+        .map(|elem| {
+            let (k, v) = elem?;
+            let mut loan_id = [0u8; 8];
+            loan_id.copy_from_slice(&k);
+            Ok((u64::from_be_bytes(loan_id), v))
+        })
+        .collect()
+}
+
 pub fn store_borrower_info(
     storage: &mut dyn Storage,
     borrower: &CanonicalAddr,
@@ -102,12 +226,18 @@ pub fn read_borrower_infos(
         .take(limit)
         .map(|elem| {
             let (k, v) = elem?;
-            let borrower = deps.api.addr_humanize(&CanonicalAddr::from(k))?.to_string();
+            let borrower_raw = CanonicalAddr::from(k);
+            let borrower = deps.api.addr_humanize(&borrower_raw)?.to_string();
             Ok(BorrowerInfoResponse {
                 borrower,
                 interest_index: v.interest_index,
                 reward_index: v.reward_index,
                 loan_amount: v.loan_amount,
+                fixed_loan_amount: read_fixed_loans(deps.storage, &borrower_raw)?
+                    .iter()
+                    .fold(Uint256::zero(), |sum, (_, loan)| {
+                        sum + loan.principal + loan.interest * Uint256::one()
+                    }),
                 pending_rewards: v.pending_rewards,
             })
         })
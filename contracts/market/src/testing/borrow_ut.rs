@@ -11,11 +11,12 @@ fn proper_compute_borrower_interest() {
     let mock_state = State {
         total_liabilities: Decimal256::from_uint256(1000000u128),
         total_reserves: Decimal256::from_uint256(0u128),
-        last_interest_updated: env.block.height,
-        last_reward_updated: env.block.height,
+        last_interest_updated: env.block.time.seconds(),
+        last_reward_updated: env.block.time.seconds(),
         global_interest_index: Decimal256::one(),
         global_reward_index: Decimal256::zero(),
         anc_emission_rate: Decimal256::one(),
+        aterra_supply: Uint256::zero(),
         prev_aterra_supply: Uint256::zero(),
         prev_exchange_rate: Decimal256::one(),
     };
@@ -37,11 +38,12 @@ fn proper_compute_borrower_interest() {
     let mock_state2 = State {
         total_liabilities: Decimal256::from_uint256(300000u128),
         total_reserves: Decimal256::from_uint256(1000u128),
-        last_interest_updated: env.block.height,
-        last_reward_updated: env.block.height,
+        last_interest_updated: env.block.time.seconds(),
+        last_reward_updated: env.block.time.seconds(),
         global_interest_index: Decimal256::from_uint256(2u128),
         global_reward_index: Decimal256::zero(),
         anc_emission_rate: Decimal256::zero(),
+        aterra_supply: Uint256::zero(),
         prev_aterra_supply: Uint256::zero(),
         prev_exchange_rate: Decimal256::one(),
     };
@@ -94,11 +96,12 @@ fn proper_compute_interest() {
     let mut mock_state = State {
         total_liabilities: Decimal256::from_uint256(1000000u128),
         total_reserves: Decimal256::zero(),
-        last_interest_updated: env.block.height,
-        last_reward_updated: env.block.height,
+        last_interest_updated: env.block.time.seconds(),
+        last_reward_updated: env.block.time.seconds(),
         global_interest_index: Decimal256::one(),
         global_reward_index: Decimal256::zero(),
         anc_emission_rate: Decimal256::one(),
+        aterra_supply: Uint256::from(2000000u64),
         prev_aterra_supply: Uint256::zero(),
         prev_exchange_rate: Decimal256::one(),
     };
@@ -110,7 +113,7 @@ fn proper_compute_interest() {
         deps.as_ref(),
         &mock_config,
         &mut mock_state,
-        env.block.height,
+        env.block.time.seconds(),
         mock_deposit_amount,
     )
     .unwrap();
@@ -119,23 +122,24 @@ fn proper_compute_interest() {
         State {
             total_liabilities: Decimal256::from_uint256(1000000u128),
             total_reserves: Decimal256::zero(),
-            last_interest_updated: env.block.height,
-            last_reward_updated: env.block.height,
+            last_interest_updated: env.block.time.seconds(),
+            last_reward_updated: env.block.time.seconds(),
             global_interest_index: Decimal256::one(),
             global_reward_index: Decimal256::zero(),
             anc_emission_rate: Decimal256::one(),
+            aterra_supply: Uint256::from(2000000u64),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
         }
     );
 
-    env.block.height += 100;
+    env.block.time = env.block.time.plus_seconds(100);
 
     compute_interest(
         deps.as_ref(),
         &mock_config,
         &mut mock_state,
-        env.block.height,
+        env.block.time.seconds(),
         mock_deposit_amount,
     )
     .unwrap();
@@ -144,11 +148,12 @@ fn proper_compute_interest() {
         State {
             total_liabilities: Decimal256::from_uint256(2000000u128),
             total_reserves: Decimal256::zero(),
-            last_interest_updated: env.block.height,
-            last_reward_updated: env.block.height - 100,
+            last_interest_updated: env.block.time.seconds(),
+            last_reward_updated: env.block.time.seconds() - 100,
             global_interest_index: Decimal256::from_uint256(2u128),
             global_reward_index: Decimal256::zero(),
             anc_emission_rate: Decimal256::one(),
+            aterra_supply: Uint256::from(2000000u64),
             prev_aterra_supply: Uint256::from(2000000u64),
             prev_exchange_rate: Decimal256::from_ratio(19995, 10000),
         }
@@ -158,11 +163,12 @@ fn proper_compute_interest() {
     let mut mock_state = State {
         total_liabilities: Decimal256::zero(),
         total_reserves: Decimal256::zero(),
-        last_interest_updated: env.block.height,
-        last_reward_updated: env.block.height,
+        last_interest_updated: env.block.time.seconds(),
+        last_reward_updated: env.block.time.seconds(),
         global_interest_index: Decimal256::one(),
         global_reward_index: Decimal256::zero(),
         anc_emission_rate: Decimal256::one(),
+        aterra_supply: Uint256::from(2000000u64),
         prev_aterra_supply: Uint256::from(2000000u128),
         prev_exchange_rate: Decimal256::one(),
     };
@@ -176,7 +182,7 @@ fn proper_compute_interest() {
         }],
     );
 
-    env.block.height += 100;
+    env.block.time = env.block.time.plus_seconds(100);
 
     // deposit_rate: 0.02
     // target_deposit_rate: 0.01
@@ -184,7 +190,7 @@ fn proper_compute_interest() {
         deps.as_ref(),
         &mock_config,
         &mut mock_state,
-        env.block.height,
+        env.block.time.seconds(),
         None,
     )
     .unwrap();
@@ -193,11 +199,12 @@ fn proper_compute_interest() {
         State {
             total_liabilities: Decimal256::zero(),
             total_reserves: Decimal256::from_uint256(2000000u64),
-            last_interest_updated: env.block.height,
-            last_reward_updated: env.block.height - 100,
+            last_interest_updated: env.block.time.seconds(),
+            last_reward_updated: env.block.time.seconds() - 100,
             global_interest_index: Decimal256::from_uint256(2u128),
             global_reward_index: Decimal256::zero(),
             anc_emission_rate: Decimal256::one(),
+            aterra_supply: Uint256::from(2000000u64),
             prev_aterra_supply: Uint256::from(2000000u64),
             prev_exchange_rate: Decimal256::from_uint256(2u64),
         }
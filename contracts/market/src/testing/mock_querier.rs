@@ -13,7 +13,10 @@ use std::collections::HashMap;
 use cw20::TokenInfoResponse;
 use moneymarket::distribution_model::AncEmissionRateResponse;
 use moneymarket::interest_model::BorrowRateResponse;
-use moneymarket::overseer::{BorrowLimitResponse, ConfigResponse};
+use moneymarket::overseer::{
+    BorrowLimitResponse, CollateralUsageResponse, CollateralUsageResponseElem, CollateralsResponse,
+    ConfigResponse,
+};
 use terra_cosmwasm::{TaxCapResponse, TaxRateResponse, TerraQuery, TerraQueryWrapper, TerraRoute};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -39,6 +42,14 @@ pub enum QueryMsg {
     },
     /// Query overseer config to get target deposit rate
     Config {},
+    /// Query collaterals of a borrower to overseer contract
+    Collaterals { borrower: String },
+    /// Query collateral usage to overseer contract
+    CollateralUsage {
+        collateral_token: Option<String>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
     /// Query cw20 Token Info
     TokenInfo {},
 }
@@ -64,6 +75,7 @@ pub struct WasmMockQuerier {
     tax_querier: TaxQuerier,
     borrow_rate_querier: BorrowRateQuerier,
     borrow_limit_querier: BorrowLimitQuerier,
+    collateral_querier: CollateralQuerier,
 }
 
 #[derive(Clone, Default)]
@@ -167,6 +179,41 @@ pub(crate) fn borrow_limit_to_map(
     borrow_limit_map
 }
 
+#[derive(Clone, Default)]
+pub struct CollateralQuerier {
+    collaterals: HashMap<String, Vec<(String, Uint256)>>,
+    // collateral token => (borrow value, max borrow value)
+    usage: HashMap<String, (Uint256, Uint256)>,
+}
+
+impl CollateralQuerier {
+    pub fn new(
+        collaterals: &[(&String, &[(&String, &Uint256)])],
+        usage: &[(&String, &(Uint256, Uint256))],
+    ) -> Self {
+        let mut collaterals_map: HashMap<String, Vec<(String, Uint256)>> = HashMap::new();
+        for (borrower, collaterals) in collaterals.iter() {
+            collaterals_map.insert(
+                borrower.to_string(),
+                collaterals
+                    .iter()
+                    .map(|(collateral_token, amount)| (collateral_token.to_string(), **amount))
+                    .collect(),
+            );
+        }
+
+        let mut usage_map: HashMap<String, (Uint256, Uint256)> = HashMap::new();
+        for (collateral_token, usage) in usage.iter() {
+            usage_map.insert(collateral_token.to_string(), **usage);
+        }
+
+        CollateralQuerier {
+            collaterals: collaterals_map,
+            usage: usage_map,
+        }
+    }
+}
+
 impl Querier for WasmMockQuerier {
     fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
         // MockQuerier doesn't support Custom, so we ignore it completely here
@@ -268,8 +315,7 @@ impl WasmMockQuerier {
                             epoch_period: 100u64,
                             price_timeframe: 100u64,
                             dyn_rate_epoch: 8600u64,
-                            dyn_rate_maxchange: Decimal256::permille(5),
-                            dyn_rate_yr_increase_expectation: Decimal256::permille(1),
+                            deposit_rate_model: "".to_string(),
                             dyn_rate_min: Decimal256::from_ratio(
                                 1000000000000u64,
                                 1000000000000000000u64,
@@ -278,8 +324,50 @@ impl WasmMockQuerier {
                                 1200000000000u64,
                                 1000000000000000000u64,
                             ),
+                            bad_debt_handler: None,
+                            max_write_off_per_epoch: Uint256::zero(),
+                            close_factor: Decimal256::one(),
+                            liquidation_interval: 0,
+                            margin_call_grace_period: 0,
+                            hard_liquidation_ltv: Decimal256::one(),
                         })))
                     }
+                    QueryMsg::Collaterals { borrower } => {
+                        let collaterals = self
+                            .collateral_querier
+                            .collaterals
+                            .get(&borrower)
+                            .cloned()
+                            .unwrap_or_default();
+                        SystemResult::Ok(ContractResult::from(to_binary(&CollateralsResponse {
+                            borrower,
+                            collaterals,
+                        })))
+                    }
+                    QueryMsg::CollateralUsage {
+                        collateral_token,
+                        start_after: _,
+                        limit: _,
+                    } => {
+                        let collateral_token = collateral_token.unwrap();
+                        match self.collateral_querier.usage.get(&collateral_token) {
+                            Some((borrow_value, max_borrow_value)) => SystemResult::Ok(
+                                ContractResult::from(to_binary(&CollateralUsageResponse {
+                                    elems: vec![CollateralUsageResponseElem {
+                                        collateral_token,
+                                        total_amount: Uint256::zero(),
+                                        max_collateral_amount: None,
+                                        borrow_value: *borrow_value,
+                                        max_borrow_value: Some(*max_borrow_value),
+                                    }],
+                                })),
+                            ),
+                            None => SystemResult::Err(SystemError::InvalidRequest {
+                                error: "No collateral usage exists".to_string(),
+                                request: msg.as_slice().into(),
+                            }),
+                        }
+                    }
                     QueryMsg::TokenInfo {} => {
                         let balances: HashMap<String, Uint128> =
                             match self.token_querier.balances.get(contract_addr) {
@@ -353,6 +441,7 @@ impl WasmMockQuerier {
             tax_querier: TaxQuerier::default(),
             borrow_rate_querier: BorrowRateQuerier::default(),
             borrow_limit_querier: BorrowLimitQuerier::default(),
+            collateral_querier: CollateralQuerier::default(),
         }
     }
 
@@ -382,4 +471,12 @@ impl WasmMockQuerier {
     pub fn with_borrow_limit(&mut self, borrow_limit: &[(&String, &Uint256)]) {
         self.borrow_limit_querier = BorrowLimitQuerier::new(borrow_limit);
     }
+
+    pub fn with_collateral_usage(
+        &mut self,
+        collaterals: &[(&String, &[(&String, &Uint256)])],
+        usage: &[(&String, &(Uint256, Uint256))],
+    ) {
+        self.collateral_querier = CollateralQuerier::new(collaterals, usage);
+    }
 }
@@ -1,6 +1,7 @@
 use crate::borrow::borrow_stable as _borrow_stable;
-use crate::contract::{execute, instantiate, query, reply, INITIAL_DEPOSIT_AMOUNT};
+use crate::contract::{execute, instantiate, migrate, query, reply, INITIAL_DEPOSIT_AMOUNT};
 use crate::error::ContractError;
+use crate::fixed_loan::borrow_stable_fixed as _borrow_stable_fixed;
 use crate::response::MsgInstantiateContractResponse;
 use crate::state::{read_borrower_infos, read_state, store_state, State};
 use crate::testing::mock_querier::mock_dependencies;
@@ -9,14 +10,18 @@ use anchor_token::distributor::ExecuteMsg as FaucetExecuteMsg;
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    attr, from_binary, to_binary, Addr, BankMsg, Coin, ContractResult, CosmosMsg, Decimal, Reply,
-    SubMsg, SubMsgExecutionResponse, Uint128, WasmMsg,
+    attr, from_binary, to_binary, Addr, BankMsg, Binary, Coin, ContractResult, CosmosMsg, Decimal,
+    Reply, StdError, SubMsg, SubMsgExecutionResponse, Uint128, WasmMsg,
 };
 use cw20::{Cw20Coin, Cw20ExecuteMsg, Cw20ReceiveMsg, MinterResponse};
 use moneymarket::market::{
-    BorrowerInfoResponse, ConfigResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, QueryMsg,
-    StateResponse,
+    BorrowerInfoResponse, ConfigResponse, Cw20HookMsg, EpochStateResponse, ExecuteMsg,
+    FixedLoanResponse, FixedLoansResponse, InstantiateMsg, MigrateMsg, QueryMsg, StateResponse,
+    SupplyReconciliationResponse,
 };
+use moneymarket::operator::OperatorPermission;
+use moneymarket::overseer::ExecuteMsg as OverseerExecuteMsg;
+use moneymarket::pause::{PauseAction, PauseInfoResponse};
 use moneymarket::querier::deduct_tax;
 use moneymarket::terraswap::InstantiateMsg as TokenInstantiateMsg;
 use protobuf::Message;
@@ -122,19 +127,21 @@ fn proper_initialization() {
     assert_eq!("overseer".to_string(), config_res.overseer_contract);
     assert_eq!("uusd".to_string(), config_res.stable_denom);
     assert_eq!(Decimal256::one(), config_res.max_borrow_factor);
+    assert_eq!(None, config_res.flash_loan_fee);
 
     let query_res = query(
         deps.as_ref(),
         mock_env(),
-        QueryMsg::State { block_height: None },
+        QueryMsg::State { block_time: None },
     )
     .unwrap();
     let state: StateResponse = from_binary(&query_res).unwrap();
     assert_eq!(Decimal256::zero(), state.total_liabilities);
     assert_eq!(Decimal256::zero(), state.total_reserves);
-    assert_eq!(mock_env().block.height, state.last_interest_updated);
+    assert_eq!(mock_env().block.time.seconds(), state.last_interest_updated);
     assert_eq!(Decimal256::one(), state.global_interest_index);
     assert_eq!(Decimal256::one(), state.anc_emission_rate);
+    assert_eq!(Uint256::from(INITIAL_DEPOSIT_AMOUNT), state.aterra_supply);
     assert_eq!(Uint256::zero(), state.prev_aterra_supply);
     assert_eq!(Decimal256::one(), state.prev_exchange_rate);
 }
@@ -190,15 +197,18 @@ fn update_config() {
     let info = mock_info("addr0000", &[]);
     let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
-    // update owner
+    // transfer the ownership
     let info = mock_info("owner", &[]);
-    let msg = ExecuteMsg::UpdateConfig {
-        owner_addr: Some("owner1".to_string()),
-        interest_model: None,
-        distribution_model: None,
-        max_borrow_factor: None,
+    let msg = ExecuteMsg::ProposeNewOwner {
+        owner: "owner1".to_string(),
+        expires_in: 86400u64,
     };
 
+    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let info = mock_info("owner1", &[]);
+    let msg = ExecuteMsg::ClaimOwnership {};
+
     let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
     assert_eq!(0, res.messages.len());
 
@@ -210,10 +220,11 @@ fn update_config() {
     // update left items
     let info = mock_info("owner1", &[]);
     let msg = ExecuteMsg::UpdateConfig {
-        owner_addr: None,
         interest_model: Some("interest2".to_string()),
         distribution_model: Some("distribution2".to_string()),
         max_borrow_factor: Some(Decimal256::percent(100)),
+        flash_loan_fee: Some(Decimal256::permille(1)),
+        fixed_rate_premium: None,
     };
 
     let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -226,14 +237,16 @@ fn update_config() {
     assert_eq!("interest2".to_string(), config_res.interest_model);
     assert_eq!("distribution2".to_string(), config_res.distribution_model);
     assert_eq!(Decimal256::percent(100), config_res.max_borrow_factor);
+    assert_eq!(Some(Decimal256::permille(1)), config_res.flash_loan_fee);
 
     // Unauthorized err
     let info = mock_info("owner", &[]);
     let msg = ExecuteMsg::UpdateConfig {
-        owner_addr: None,
         interest_model: None,
         distribution_model: None,
         max_borrow_factor: None,
+        flash_loan_fee: None,
+        fixed_rate_premium: None,
     };
 
     let res = execute(deps.as_mut(), mock_env(), info, msg);
@@ -362,7 +375,7 @@ fn deposit_stable_huge_amount() {
         MOCK_CONTRACT_ADDR.to_string(),
         vec![Coin {
             denom: "uusd".to_string(),
-            amount: Uint128::from(111_111_110_000_000u128),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT + 111_111_110_000_000u128),
         }],
     );
 
@@ -370,7 +383,7 @@ fn deposit_stable_huge_amount() {
         &"at-uusd".to_string(),
         &[(
             &MOCK_CONTRACT_ADDR.to_string(),
-            &Uint128::from(55_555_555_000_000u128),
+            &Uint128::from(INITIAL_DEPOSIT_AMOUNT + 55_555_555_000_000u128),
         )],
     )]);
 
@@ -507,9 +520,9 @@ fn deposit_stable() {
 
     let res = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
     // 1- As the last place to modify the state is compute_interest, a check on the state ensures the invocation of compute_interest.
-    // However, because passed_blocks = 0, interest factor & interest accrued are also 0, and thus the values do not change
+    // However, because passed_seconds = 0, interest factor & interest accrued are also 0, and thus the values do not change
     // (looking as if the function might not have been invoked at all.)
-    // Thus, later, the invocation of compute interest will be tested after increasing the block height.
+    // Thus, later, the invocation of compute interest will be tested after increasing the block time.
     assert_eq!(
         read_state(deps.as_ref().storage).unwrap(),
         State {
@@ -517,9 +530,10 @@ fn deposit_stable() {
             global_reward_index: Decimal256::zero(),
             total_liabilities: Decimal256::zero(),
             total_reserves: Decimal256::zero(),
-            last_interest_updated: mock_env().block.height,
-            last_reward_updated: mock_env().block.height,
+            last_interest_updated: mock_env().block.time.seconds(),
+            last_reward_updated: mock_env().block.time.seconds(),
             anc_emission_rate: Decimal256::one(),
+            aterra_supply: Uint256::from(INITIAL_DEPOSIT_AMOUNT + 1000000u128),
             prev_aterra_supply: Uint256::from(1000000u64),
             prev_exchange_rate: Decimal256::one(),
         }
@@ -548,17 +562,44 @@ fn deposit_stable() {
         }))]
     );
 
+    // the token supply lags behind the ledger until the mint is executed
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::SupplyReconciliation {}).unwrap();
+    let reconciliation: SupplyReconciliationResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        reconciliation,
+        SupplyReconciliationResponse {
+            ledger_supply: Uint256::from(INITIAL_DEPOSIT_AMOUNT + 1000000u128),
+            token_supply: Uint256::from(INITIAL_DEPOSIT_AMOUNT),
+            reconciled: false,
+        }
+    );
+
+    deps.querier.with_token_balances(&[(
+        &"at-uusd".to_string(),
+        &[
+            (
+                &MOCK_CONTRACT_ADDR.to_string(),
+                &Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+            ),
+            (&"addr0000".to_string(), &Uint128::from(1000000u128)),
+        ],
+    )]);
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::SupplyReconciliation {}).unwrap();
+    let reconciliation: SupplyReconciliationResponse = from_binary(&res).unwrap();
+    assert!(reconciliation.reconciled);
+
     // make exchange rate to 50%
     store_state(
         deps.as_mut().storage,
         &State {
             total_liabilities: Decimal256::from_uint256(50000u128),
             total_reserves: Decimal256::from_uint256(550000u128),
-            last_interest_updated: mock_env().block.height,
-            last_reward_updated: mock_env().block.height,
+            last_interest_updated: mock_env().block.time.seconds(),
+            last_reward_updated: mock_env().block.time.seconds(),
             global_interest_index: Decimal256::one(),
             global_reward_index: Decimal256::zero(),
             anc_emission_rate: Decimal256::one(),
+            aterra_supply: Uint256::from(1000000u64),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::from_ratio(1u64, 2u64),
         },
@@ -604,18 +645,19 @@ fn deposit_stable() {
         &State {
             total_liabilities: Decimal256::from_uint256(50000u128),
             total_reserves: Decimal256::from_uint256(550000u128),
-            last_interest_updated: env.block.height,
-            last_reward_updated: env.block.height,
+            last_interest_updated: env.block.time.seconds(),
+            last_reward_updated: env.block.time.seconds(),
             global_interest_index: Decimal256::one(),
             global_reward_index: Decimal256::zero(),
             anc_emission_rate: Decimal256::one(),
+            aterra_supply: Uint256::from(1000000u64),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::from_ratio(1u64, 2u64),
         },
     )
     .unwrap();
 
-    env.block.height += 100;
+    env.block.time = env.block.time.plus_seconds(100);
     let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     // State: global_interest_index: 1
@@ -633,9 +675,10 @@ fn deposit_stable() {
             global_reward_index: Decimal256::from_str("0.002").unwrap(),
             total_liabilities: Decimal256::from_uint256(100000u128),
             total_reserves: Decimal256::from_uint256(550000u128),
-            last_interest_updated: env.block.height,
-            last_reward_updated: env.block.height,
+            last_interest_updated: env.block.time.seconds(),
+            last_reward_updated: env.block.time.seconds(),
             anc_emission_rate: Decimal256::one(),
+            aterra_supply: Uint256::from(INITIAL_DEPOSIT_AMOUNT + 1818181),
             prev_aterra_supply: Uint256::from(INITIAL_DEPOSIT_AMOUNT + 1818181),
             prev_exchange_rate: Decimal256::from_ratio(55u64, 100u64),
         }
@@ -711,11 +754,12 @@ fn redeem_stable() {
         &State {
             total_liabilities: Decimal256::zero(),
             total_reserves: Decimal256::zero(),
-            last_interest_updated: mock_env().block.height,
-            last_reward_updated: mock_env().block.height,
+            last_interest_updated: mock_env().block.time.seconds(),
+            last_reward_updated: mock_env().block.time.seconds(),
             global_interest_index: Decimal256::one(),
             global_reward_index: Decimal256::zero(),
             anc_emission_rate: Decimal256::one(),
+            aterra_supply: Uint256::from(2000000u64),
             prev_aterra_supply: Uint256::from(Uint128::from(1000000u128)),
             prev_exchange_rate: Decimal256::one(),
         },
@@ -773,11 +817,12 @@ fn redeem_stable() {
         &State {
             total_liabilities: Decimal256::from_uint256(500000u128),
             total_reserves: Decimal256::from_uint256(100000u128),
-            last_interest_updated: mock_env().block.height,
-            last_reward_updated: mock_env().block.height,
+            last_interest_updated: mock_env().block.time.seconds(),
+            last_reward_updated: mock_env().block.time.seconds(),
             global_interest_index: Decimal256::one(),
             global_reward_index: Decimal256::zero(),
             anc_emission_rate: Decimal256::one(),
+            aterra_supply: Uint256::from(2000000u64),
             prev_aterra_supply: Uint256::from(2000000u64),
             prev_exchange_rate: Decimal256::one(),
         },
@@ -835,6 +880,114 @@ fn redeem_stable() {
     );
 }
 
+#[test]
+fn pause_deposit_and_redeem() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+    }]);
+
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // Register anchor token contract
+    let mut token_inst_res = MsgInstantiateContractResponse::new();
+    token_inst_res.set_contract_address("at-uusd".to_string());
+    let reply_msg = Reply {
+        id: 1,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
+        }),
+    };
+    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+
+    let msg = ExecuteMsg::UpdateGuardian {
+        guardian: Some("guardian".to_string()),
+    };
+    let info = mock_info("owner", &[]);
+    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // guardian pauses deposits and redemptions
+    let msg = ExecuteMsg::Pause {
+        actions: vec![PauseAction::Deposit, PauseAction::Redeem],
+    };
+    let info = mock_info("guardian", &[]);
+    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::DepositStable {};
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    match res {
+        Err(ContractError::Std(StdError::GenericErr { msg, .. })) => {
+            assert_eq!(msg, "deposit is paused")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0000".to_string(),
+        amount: Uint128::from(1000000u128),
+        msg: to_binary(&Cw20HookMsg::RedeemStable {}).unwrap(),
+    });
+    let info = mock_info("at-uusd", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    match res {
+        Err(ContractError::Std(StdError::GenericErr { msg, .. })) => {
+            assert_eq!(msg, "redeem is paused")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // only the owner can unpause
+    let msg = ExecuteMsg::Unpause {
+        actions: vec![PauseAction::Deposit],
+    };
+    let info = mock_info("guardian", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
+    match res {
+        Err(ContractError::Std(StdError::GenericErr { msg, .. })) => {
+            assert_eq!(msg, "unauthorized")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let info = mock_info("owner", &[]);
+    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::PauseInfo {}).unwrap();
+    let pause_info: PauseInfoResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        pause_info,
+        PauseInfoResponse {
+            guardian: Some("guardian".to_string()),
+            paused: vec![PauseAction::Redeem],
+        }
+    );
+}
+
 #[test]
 #[ignore = "deprecated functionality"]
 fn borrow_stable() {
@@ -900,11 +1053,12 @@ fn borrow_stable() {
         &State {
             total_liabilities: Decimal256::from_uint256(1000000u128),
             total_reserves: Decimal256::zero(),
-            last_interest_updated: env.block.height,
-            last_reward_updated: env.block.height,
+            last_interest_updated: env.block.time.seconds(),
+            last_reward_updated: env.block.time.seconds(),
             global_interest_index: Decimal256::one(),
             global_reward_index: Decimal256::zero(),
             anc_emission_rate: Decimal256::one(),
+            aterra_supply: Uint256::zero(),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
         },
@@ -916,7 +1070,7 @@ fn borrow_stable() {
         to: None,
     };
 
-    env.block.height += 100;
+    env.block.time = env.block.time.plus_seconds(100);
     let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
     // interest_factor = 1% * 100blocks = 1
@@ -957,7 +1111,7 @@ fn borrow_stable() {
             &query(
                 deps.as_ref(),
                 env.clone(),
-                QueryMsg::State { block_height: None }
+                QueryMsg::State { block_time: None }
             )
             .unwrap()
         )
@@ -965,11 +1119,12 @@ fn borrow_stable() {
         State {
             total_liabilities: Decimal256::from_uint256(2500000u128),
             total_reserves: Decimal256::zero(),
-            last_interest_updated: env.block.height,
-            last_reward_updated: env.block.height,
+            last_interest_updated: env.block.time.seconds(),
+            last_reward_updated: env.block.time.seconds(),
             global_interest_index: Decimal256::from_uint256(2u128),
             global_reward_index: Decimal256::from_str("0.0001").unwrap(),
             anc_emission_rate: Decimal256::one(),
+            aterra_supply: Uint256::zero(),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
         }
@@ -982,7 +1137,7 @@ fn borrow_stable() {
                 deps.as_ref(),
                 mock_env(),
                 QueryMsg::State {
-                    block_height: Some(env.block.height + 1u64)
+                    block_time: Some(env.block.time.seconds() + 1u64)
                 }
             )
             .unwrap()
@@ -991,11 +1146,12 @@ fn borrow_stable() {
         State {
             total_liabilities: Decimal256::from_uint256(2525000u128),
             total_reserves: Decimal256::from_uint256(0u128),
-            last_interest_updated: env.block.height + 1u64,
-            last_reward_updated: env.block.height + 1u64,
+            last_interest_updated: env.block.time.seconds() + 1u64,
+            last_reward_updated: env.block.time.seconds() + 1u64,
             global_interest_index: Decimal256::from_str("2.02").unwrap(),
             global_reward_index: Decimal256::from_str("0.0001008").unwrap(),
             anc_emission_rate: Decimal256::one(),
+            aterra_supply: Uint256::zero(),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
         }
@@ -1006,7 +1162,7 @@ fn borrow_stable() {
         mock_env(),
         QueryMsg::BorrowerInfo {
             borrower: "addr0000".to_string(),
-            block_height: None,
+            block_time: None,
         },
     )
     .unwrap();
@@ -1019,6 +1175,7 @@ fn borrow_stable() {
             interest_index: Decimal256::from_uint256(2u128),
             reward_index: Decimal256::from_str("0.0001").unwrap(),
             loan_amount: Uint256::from(500000u64),
+            fixed_loan_amount: Uint256::zero(),
             pending_rewards: Decimal256::zero(),
         }
     );
@@ -1028,7 +1185,7 @@ fn borrow_stable() {
         mock_env(),
         QueryMsg::BorrowerInfo {
             borrower: "addr0000".to_string(),
-            block_height: Some(env.block.height),
+            block_time: Some(env.block.time.seconds()),
         },
     )
     .unwrap();
@@ -1041,6 +1198,7 @@ fn borrow_stable() {
             interest_index: Decimal256::from_uint256(2u128),
             reward_index: Decimal256::from_str("0.0001").unwrap(),
             loan_amount: Uint256::from(500000u64),
+            fixed_loan_amount: Uint256::zero(),
             pending_rewards: Decimal256::zero(),
         }
     );
@@ -1052,7 +1210,7 @@ fn borrow_stable() {
         mock_env(),
         QueryMsg::BorrowerInfo {
             borrower: "addr0000".to_string(),
-            block_height: Some(env.block.height + 100),
+            block_time: Some(env.block.time.seconds() + 100),
         },
     )
     .unwrap();
@@ -1065,6 +1223,7 @@ fn borrow_stable() {
             interest_index: Decimal256::from_uint256(4u128),
             reward_index: Decimal256::from_str("0.00018").unwrap(),
             loan_amount: Uint256::from(1000000u64),
+            fixed_loan_amount: Uint256::zero(),
             pending_rewards: Decimal256::from_uint256(20u64),
         }
     );
@@ -1145,11 +1304,12 @@ fn assert_max_borrow_factor() {
         &State {
             total_liabilities: Decimal256::zero(),
             total_reserves: Decimal256::zero(),
-            last_interest_updated: mock_env().block.height,
-            last_reward_updated: mock_env().block.height,
+            last_interest_updated: mock_env().block.time.seconds(),
+            last_reward_updated: mock_env().block.time.seconds(),
             global_interest_index: Decimal256::one(),
             global_reward_index: Decimal256::zero(),
             anc_emission_rate: Decimal256::one(),
+            aterra_supply: Uint256::zero(),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
         },
@@ -1257,11 +1417,12 @@ fn repay_stable() {
         &State {
             total_liabilities: Decimal256::from_uint256(1000000u128),
             total_reserves: Decimal256::zero(),
-            last_interest_updated: env.block.height,
-            last_reward_updated: env.block.height,
+            last_interest_updated: env.block.time.seconds(),
+            last_reward_updated: env.block.time.seconds(),
             global_interest_index: Decimal256::one(),
             global_reward_index: Decimal256::zero(),
             anc_emission_rate: Decimal256::one(),
+            aterra_supply: Uint256::zero(),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
         },
@@ -1273,7 +1434,7 @@ fn repay_stable() {
         to: None,
     };
 
-    env.block.height += 100;
+    env.block.time = env.block.time.plus_seconds(100);
     let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
     let msg = ExecuteMsg::RepayStable {};
@@ -1440,11 +1601,12 @@ fn repay_stable_from_liquidation() {
         &State {
             total_liabilities: Decimal256::from_uint256(1000000u128),
             total_reserves: Decimal256::zero(),
-            last_interest_updated: env.block.height,
-            last_reward_updated: env.block.height,
+            last_interest_updated: env.block.time.seconds(),
+            last_reward_updated: env.block.time.seconds(),
             global_interest_index: Decimal256::one(),
             global_reward_index: Decimal256::zero(),
             anc_emission_rate: Decimal256::one(),
+            aterra_supply: Uint256::zero(),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
         },
@@ -1458,6 +1620,7 @@ fn repay_stable_from_liquidation() {
         info.clone(),
         Uint256::from(500000u64),
         Some(Addr::unchecked("")),
+        None,
     )
     .unwrap();
 
@@ -1512,6 +1675,7 @@ fn repay_stable_from_liquidation() {
             attr("action", "repay_stable"),
             attr("borrower", "addr0000"),
             attr("repay_amount", "100000"),
+            attr("fixed_repay_amount", "0"),
         ]
     );
 
@@ -1530,28 +1694,38 @@ fn repay_stable_from_liquidation() {
             attr("action", "repay_stable"),
             attr("borrower", "addr0000"),
             attr("repay_amount", "400000"),
+            attr("fixed_repay_amount", "0"),
         ]
     );
 
     assert_eq!(
         res.messages,
-        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-            to_address: "addr0000".to_string(),
-            amount: vec![deduct_tax(
-                deps.as_ref(),
-                Coin {
-                    denom: "uusd".to_string(),
-                    amount: Uint128::from(100000u128),
-                }
-            )
-            .unwrap()]
-        }))]
+        vec![
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "addr0000".to_string(),
+                amount: vec![deduct_tax(
+                    deps.as_ref(),
+                    Coin {
+                        denom: "uusd".to_string(),
+                        amount: Uint128::from(100000u128),
+                    }
+                )
+                .unwrap()]
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "overseer".to_string(),
+                funds: vec![],
+                msg: to_binary(&OverseerExecuteMsg::ClearMarginCall {
+                    borrower: "addr0000".to_string(),
+                })
+                .unwrap(),
+            })),
+        ]
     );
 }
 
 #[test]
-#[ignore = "deprecated functionality"]
-fn claim_rewards() {
+fn repay_stable_for() {
     let mut deps = mock_dependencies(&[Coin {
         denom: "uusd".to_string(),
         amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
@@ -1600,8 +1774,8 @@ fn claim_rewards() {
         collector_contract: "collector".to_string(),
         distributor_contract: "distributor".to_string(),
     };
+    let env = mock_env();
     let info = mock_info("addr0000", &[]);
-    let mut env = mock_env();
     let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
     deps.querier
@@ -1614,105 +1788,1143 @@ fn claim_rewards() {
         &State {
             total_liabilities: Decimal256::from_uint256(1000000u128),
             total_reserves: Decimal256::zero(),
-            last_interest_updated: env.block.height,
-            last_reward_updated: env.block.height,
+            last_interest_updated: env.block.time.seconds(),
+            last_reward_updated: env.block.time.seconds(),
             global_interest_index: Decimal256::one(),
             global_reward_index: Decimal256::zero(),
             anc_emission_rate: Decimal256::one(),
+            aterra_supply: Uint256::zero(),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
         },
     )
     .unwrap();
 
-    // zero loan claim, will return empty messages
-    let msg = ExecuteMsg::ClaimRewards { to: None };
-    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
-    assert_eq!(res.messages.len(), 0);
+    // simulate borrow stable
+    _borrow_stable(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        Uint256::from(500000u64),
+        Some(Addr::unchecked("")),
+        None,
+    )
+    .unwrap();
 
-    let msg = ExecuteMsg::BorrowStable {
-        borrow_amount: Uint256::from(500000u64),
-        to: None,
+    let msg = ExecuteMsg::RepayStableFor {
+        borrower: "addr0000".to_string(),
     };
-    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
-    // zero block passed
-    let msg = ExecuteMsg::ClaimRewards {
-        to: Some("addr0001".to_string()),
-    };
-    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
-    assert_eq!(res.messages.len(), 0);
+    let info = mock_info("addr0001", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, msg.clone());
+    match res {
+        Err(ContractError::ZeroRepay(denom)) => assert_eq!(denom, "uusd"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
 
-    // 100 blocks passed
-    env.block.height += 100;
-    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    // anyone can pay down the loan
+    let info = mock_info(
+        "addr0001",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(100000u128),
+        }],
+    );
+    let res = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "repay_stable"),
+            attr("borrower", "addr0000"),
+            attr("repay_amount", "100000"),
+            attr("fixed_repay_amount", "0"),
+        ]
+    );
     assert_eq!(
         res.messages,
         vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: "distributor".to_string(),
+            contract_addr: "overseer".to_string(),
             funds: vec![],
-            msg: to_binary(&FaucetExecuteMsg::Spend {
-                recipient: "addr0001".to_string(),
-                amount: Uint128::from(33u128),
+            msg: to_binary(&OverseerExecuteMsg::ClearMarginCall {
+                borrower: "addr0000".to_string(),
             })
             .unwrap(),
-        }))]
-    );
-
-    let res: BorrowerInfoResponse = from_binary(
-        &query(
-            deps.as_ref(),
-            mock_env(),
-            QueryMsg::BorrowerInfo {
-                borrower: "addr0000".to_string(),
-                block_height: None,
-            },
-        )
-        .unwrap(),
-    )
-    .unwrap();
-    assert_eq!(
-        res.pending_rewards,
-        Decimal256::from_str("0.333333333333").unwrap()
-    );
-    assert_eq!(
-        res.reward_index,
-        Decimal256::from_str("0.000066666666666666").unwrap()
-    );
-}
-
-#[test]
-#[ignore = "deprecated functionality"]
-fn execute_epoch_operations() {
-    let mut deps = mock_dependencies(&[Coin {
-        denom: "uusd".to_string(),
-        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
-    }]);
-    deps.querier.with_tax(
-        Decimal::percent(1),
-        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+        })),]
     );
 
-    let msg = InstantiateMsg {
-        owner_addr: "owner".to_string(),
-        stable_denom: "uusd".to_string(),
-        aterra_code_id: 123u64,
-        anc_emission_rate: Decimal256::one(),
-        max_borrow_factor: Decimal256::one(),
-    };
-
+    // overpayment goes back to the sender
     let info = mock_info(
-        "addr0000",
+        "addr0001",
         &[Coin {
             denom: "uusd".to_string(),
-            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+            amount: Uint128::from(500000u128),
         }],
     );
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "repay_stable"),
+            attr("borrower", "addr0000"),
+            attr("repay_amount", "400000"),
+            attr("fixed_repay_amount", "0"),
+        ]
+    );
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "addr0001".to_string(),
+                amount: vec![deduct_tax(
+                    deps.as_ref(),
+                    Coin {
+                        denom: "uusd".to_string(),
+                        amount: Uint128::from(100000u128),
+                    }
+                )
+                .unwrap()]
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "overseer".to_string(),
+                funds: vec![],
+                msg: to_binary(&OverseerExecuteMsg::ClearMarginCall {
+                    borrower: "addr0000".to_string(),
+                })
+                .unwrap(),
+            })),
+        ]
+    );
 
-    // we can just call .unwrap() to assert this was a success
-    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
-
-    // Register anchor token contract
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::BorrowerInfo {
+            borrower: "addr0000".to_string(),
+            block_time: None,
+        },
+    )
+    .unwrap();
+    let borrower_info: BorrowerInfoResponse = from_binary(&res).unwrap();
+    assert_eq!(borrower_info.loan_amount, Uint256::zero());
+
+    // fixed-rate loans are repaid before the variable-rate loan
+    let _res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateConfig {
+            interest_model: None,
+            distribution_model: None,
+            max_borrow_factor: None,
+            flash_loan_fee: None,
+            fixed_rate_premium: Some(Decimal256::percent(1)),
+        },
+    )
+    .unwrap();
+    let _res = _borrow_stable_fixed(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[]),
+        Uint256::from(200000u64),
+        None,
+        None,
+        None,
+    )
+    .unwrap();
+
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(300000u128),
+        }],
+    );
+    let res = execute(deps.as_mut(), env.clone(), info, ExecuteMsg::RepayStable {}).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "repay_stable"),
+            attr("borrower", "addr0000"),
+            attr("repay_amount", "0"),
+            attr("fixed_repay_amount", "200000"),
+        ]
+    );
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "addr0000".to_string(),
+                amount: vec![deduct_tax(
+                    deps.as_ref(),
+                    Coin {
+                        denom: "uusd".to_string(),
+                        amount: Uint128::from(100000u128),
+                    }
+                )
+                .unwrap()]
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "overseer".to_string(),
+                funds: vec![],
+                msg: to_binary(&OverseerExecuteMsg::ClearMarginCall {
+                    borrower: "addr0000".to_string(),
+                })
+                .unwrap(),
+            })),
+        ]
+    );
+
+    let res = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::BorrowerInfo {
+            borrower: "addr0000".to_string(),
+            block_time: None,
+        },
+    )
+    .unwrap();
+    let borrower_info: BorrowerInfoResponse = from_binary(&res).unwrap();
+    assert_eq!(borrower_info.loan_amount, Uint256::zero());
+    assert_eq!(borrower_info.fixed_loan_amount, Uint256::zero());
+}
+
+#[test]
+fn flash_loan() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+    }]);
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // Register anchor token contract
+    let mut token_inst_res = MsgInstantiateContractResponse::new();
+    token_inst_res.set_contract_address("at-uusd".to_string());
+    let reply_msg = Reply {
+        id: 1,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
+        }),
+    };
+    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+
+    // Register overseer contract
+    let msg = ExecuteMsg::RegisterContracts {
+        overseer_contract: "overseer".to_string(),
+        interest_model: "interest".to_string(),
+        distribution_model: "distribution".to_string(),
+        collector_contract: "collector".to_string(),
+        distributor_contract: "distributor".to_string(),
+    };
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(10000000u128),
+        }],
+    );
+
+    let msg = ExecuteMsg::FlashLoan {
+        amount: Uint256::from(1000000u64),
+        msg: Binary::from(b"callback".to_vec()),
+    };
+    let info = mock_info("borrower0000", &[]);
+
+    // disabled until a fee is configured
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+    match res {
+        Err(ContractError::FlashLoanDisabled {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let update_msg = ExecuteMsg::UpdateConfig {
+        interest_model: None,
+        distribution_model: None,
+        max_borrow_factor: None,
+        flash_loan_fee: Some(Decimal256::percent(1)),
+        fixed_rate_premium: None,
+    };
+    let _res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        update_msg,
+    )
+    .unwrap();
+
+    // cannot lend more than the balance
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        ExecuteMsg::FlashLoan {
+            amount: Uint256::from(10000001u64),
+            msg: Binary::from(b"callback".to_vec()),
+        },
+    );
+    match res {
+        Err(ContractError::NoStableAvailable(denom)) => assert_eq!(denom, "uusd"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let epoch_state: EpochStateResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::EpochState {
+                block_time: None,
+                distributed_interest: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "borrower0000".to_string(),
+                amount: vec![deduct_tax(
+                    deps.as_ref(),
+                    Coin {
+                        denom: "uusd".to_string(),
+                        amount: Uint128::from(1000000u128),
+                    }
+                )
+                .unwrap()]
+            })),
+            SubMsg::reply_on_success(
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: "borrower0000".to_string(),
+                    msg: Binary::from(b"callback".to_vec()),
+                    funds: vec![],
+                }),
+                2u64
+            ),
+        ]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "flash_loan"),
+            attr("borrower", "borrower0000"),
+            attr("amount", "1000000"),
+            attr("fee", "10000"),
+        ]
+    );
+
+    // the callback cannot take another flash loan
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+    match res {
+        Err(ContractError::FlashLoanInProgress {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the lent amount still counts for the exchange rate
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(9000000u128),
+        }],
+    );
+    let res: EpochStateResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::EpochState {
+                block_time: None,
+                distributed_interest: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res, epoch_state);
+
+    // nor deposit the borrowed funds to pass the repayment check
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(10000000u128),
+        }],
+    );
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(
+            "borrower0000",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(1000000u128),
+            }],
+        ),
+        ExecuteMsg::DepositStable {},
+    );
+    match res {
+        Err(ContractError::FlashLoanInProgress {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let flash_loan_reply = Reply {
+        id: 2,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: None,
+        }),
+    };
+
+    // the fee is missing
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(10005000u128),
+        }],
+    );
+    let res = reply(deps.as_mut(), env.clone(), flash_loan_reply.clone());
+    match res {
+        Err(ContractError::FlashLoanNotRepaid(fee)) => assert_eq!(fee, 10000u128),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the failed reply reverts the whole transaction, so start over
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(10000000u128),
+        }],
+    );
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(10010000u128),
+        }],
+    );
+
+    let prev_state = read_state(deps.as_ref().storage).unwrap();
+    let res = reply(deps.as_mut(), env, flash_loan_reply).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![attr("action", "repay_flash_loan"), attr("fee", "10000"),]
+    );
+
+    // the fee goes to the reserves, the loan is not a liability
+    let state = read_state(deps.as_ref().storage).unwrap();
+    assert_eq!(
+        state.total_reserves,
+        prev_state.total_reserves + Decimal256::from_uint256(10000u64)
+    );
+    assert_eq!(state.total_liabilities, prev_state.total_liabilities);
+}
+
+#[test]
+fn borrow_stable_fixed() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+    }]);
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // Register anchor token contract
+    let mut token_inst_res = MsgInstantiateContractResponse::new();
+    token_inst_res.set_contract_address("at-uusd".to_string());
+    let reply_msg = Reply {
+        id: 1,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
+        }),
+    };
+    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+
+    // Register overseer contract
+    let msg = ExecuteMsg::RegisterContracts {
+        overseer_contract: "overseer".to_string(),
+        interest_model: "interest".to_string(),
+        distribution_model: "distribution".to_string(),
+        collector_contract: "collector".to_string(),
+        distributor_contract: "distributor".to_string(),
+    };
+    let mut env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
+
+    store_state(
+        deps.as_mut().storage,
+        &State {
+            total_liabilities: Decimal256::from_uint256(1000000u128),
+            total_reserves: Decimal256::zero(),
+            last_interest_updated: env.block.time.seconds(),
+            last_reward_updated: env.block.time.seconds(),
+            global_interest_index: Decimal256::one(),
+            global_reward_index: Decimal256::zero(),
+            anc_emission_rate: Decimal256::one(),
+            aterra_supply: Uint256::zero(),
+            prev_aterra_supply: Uint256::zero(),
+            prev_exchange_rate: Decimal256::one(),
+        },
+    )
+    .unwrap();
+
+    let maturity = env.block.time.seconds() + 100;
+
+    // new fixed borrows are deprecated along with variable ones
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        ExecuteMsg::BorrowStableFixed {
+            borrow_amount: Uint256::from(500000u64),
+            maturity: Some(maturity),
+            to: None,
+            borrower: None,
+        },
+    );
+    match res {
+        Err(ContractError::Deprecated {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // disabled until a premium is configured
+    let res = _borrow_stable_fixed(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        Uint256::from(500000u64),
+        Some(maturity),
+        None,
+        None,
+    );
+    match res {
+        Err(ContractError::FixedRateDisabled {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let update_msg = ExecuteMsg::UpdateConfig {
+        interest_model: None,
+        distribution_model: None,
+        max_borrow_factor: None,
+        flash_loan_fee: None,
+        fixed_rate_premium: Some(Decimal256::percent(1)),
+    };
+    let _res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        update_msg,
+    )
+    .unwrap();
+
+    let res = _borrow_stable_fixed(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        Uint256::from(500000u64),
+        Some(env.block.time.seconds()),
+        None,
+        None,
+    );
+    match res {
+        Err(ContractError::InvalidMaturity {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = _borrow_stable_fixed(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        Uint256::from(1000001u64),
+        None,
+        None,
+        None,
+    );
+    match res {
+        Err(ContractError::BorrowExceedsLimit(1000000)) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the rate is locked at the borrow rate plus the premium
+    let res = _borrow_stable_fixed(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        Uint256::from(500000u64),
+        Some(maturity),
+        None,
+        None,
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "borrow_stable_fixed"),
+            attr("borrower", "addr0000"),
+            attr("loan_id", "1"),
+            attr("borrow_amount", "500000"),
+            attr("rate", "0.02"),
+        ]
+    );
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "addr0000".to_string(),
+            amount: vec![deduct_tax(
+                deps.as_ref(),
+                Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(500000u128),
+                }
+            )
+            .unwrap()]
+        }))]
+    );
+
+    // the variable rate only applies to the variable part of the liabilities
+    env.block.time = env.block.time.plus_seconds(10);
+    let state: StateResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::State { block_time: None },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        state.total_liabilities,
+        Decimal256::from_uint256(1700000u128)
+    );
+    assert_eq!(state.global_interest_index, Decimal256::percent(110));
+
+    let borrower_info: BorrowerInfoResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::BorrowerInfo {
+                borrower: "addr0000".to_string(),
+                block_time: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(borrower_info.loan_amount, Uint256::zero());
+    assert_eq!(borrower_info.fixed_loan_amount, Uint256::from(600000u64));
+
+    // the borrow limit covers both loan books
+    let res = _borrow_stable_fixed(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        Uint256::from(400001u64),
+        None,
+        None,
+        None,
+    );
+    match res {
+        Err(ContractError::BorrowExceedsLimit(1000000)) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // interest is repaid before the principal
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(
+            "addr0000",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(150000u128),
+            }],
+        ),
+        ExecuteMsg::RepayStableFixed {
+            loan_id: 1,
+            borrower: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "repay_stable_fixed"),
+            attr("borrower", "addr0000"),
+            attr("loan_id", "1"),
+            attr("repay_amount", "150000"),
+        ]
+    );
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "overseer".to_string(),
+            funds: vec![],
+            msg: to_binary(&OverseerExecuteMsg::ClearMarginCall {
+                borrower: "addr0000".to_string(),
+            })
+            .unwrap(),
+        })),]
+    );
+
+    let loans: FixedLoansResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::FixedLoans {
+                borrower: "addr0000".to_string(),
+                block_time: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        loans,
+        FixedLoansResponse {
+            borrower: "addr0000".to_string(),
+            loans: vec![FixedLoanResponse {
+                loan_id: 1,
+                principal: Uint256::from(450000u64),
+                loan_amount: Uint256::from(450000u64),
+                rate: Decimal256::percent(2),
+                maturity: Some(maturity),
+            }],
+        }
+    );
+
+    // overpayment goes back to the borrower and closes the loan
+    let _res = _borrow_stable_fixed(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        Uint256::from(100000u64),
+        None,
+        None,
+        None,
+    )
+    .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(
+            "addr0000",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(200000u128),
+            }],
+        ),
+        ExecuteMsg::RepayStableFixed {
+            loan_id: 2,
+            borrower: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "repay_stable_fixed"),
+            attr("borrower", "addr0000"),
+            attr("loan_id", "2"),
+            attr("repay_amount", "100000"),
+        ]
+    );
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "addr0000".to_string(),
+                amount: vec![deduct_tax(
+                    deps.as_ref(),
+                    Coin {
+                        denom: "uusd".to_string(),
+                        amount: Uint128::from(100000u128),
+                    }
+                )
+                .unwrap()]
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "overseer".to_string(),
+                funds: vec![],
+                msg: to_binary(&OverseerExecuteMsg::ClearMarginCall {
+                    borrower: "addr0000".to_string(),
+                })
+                .unwrap(),
+            })),
+        ]
+    );
+
+    let msg = ExecuteMsg::RolloverFixedLoan {
+        borrower: "addr0000".to_string(),
+        loan_id: 1,
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0001", &[]),
+        msg.clone(),
+    );
+    match res {
+        Err(ContractError::FixedLoanNotMatured {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // anyone can move a matured loan into the variable-rate loan
+    env.block.time = env.block.time.plus_seconds(90);
+    let res = execute(deps.as_mut(), env.clone(), mock_info("addr0001", &[]), msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "rollover_fixed_loan"),
+            attr("borrower", "addr0000"),
+            attr("loan_id", "1"),
+            attr("loan_amount", "1260000"),
+        ]
+    );
+
+    let borrower_info: BorrowerInfoResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::BorrowerInfo {
+                borrower: "addr0000".to_string(),
+                block_time: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(borrower_info.loan_amount, Uint256::from(1260000u64));
+    assert_eq!(borrower_info.fixed_loan_amount, Uint256::zero());
+
+    // collaterals above their borrow value cap cannot back new borrows
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(10000000u64))]);
+    deps.querier.with_collateral_usage(
+        &[(
+            &"addr0000".to_string(),
+            &[(&"bluna".to_string(), &Uint256::from(1000u64))],
+        )],
+        &[(
+            &"bluna".to_string(),
+            &(Uint256::from(500000u64), Uint256::from(300000u64)),
+        )],
+    );
+    let res = _borrow_stable_fixed(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        Uint256::from(1000u64),
+        None,
+        None,
+        None,
+    );
+    match res {
+        Err(ContractError::MaxBorrowValueExceeded(300000)) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    deps.querier.with_collateral_usage(&[], &[]);
+
+    // unlocking is handled by the overseer
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        ExecuteMsg::ApproveOperator {
+            operator: "operator0000".to_string(),
+            permissions: vec![OperatorPermission::Unlock],
+            borrow_allowance: None,
+            expires_at: None,
+        },
+    );
+    match res {
+        Err(ContractError::Std(StdError::GenericErr { msg, .. })) => {
+            assert_eq!(msg, "Unsupported operator permission: unlock")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // approved operators borrow up to their allowance
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(10000000u64))]);
+    let _res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        ExecuteMsg::ApproveOperator {
+            operator: "operator0000".to_string(),
+            permissions: vec![OperatorPermission::Borrow],
+            borrow_allowance: Some(Uint256::from(100000u64)),
+            expires_at: None,
+        },
+    )
+    .unwrap();
+
+    let res = _borrow_stable_fixed(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("operator0000", &[]),
+        Uint256::from(100001u64),
+        None,
+        None,
+        Some(Addr::unchecked("addr0000")),
+    );
+    match res {
+        Err(ContractError::Std(StdError::GenericErr { msg, .. })) => {
+            assert_eq!(msg, "Borrow amount exceeds the operator allowance: 100000")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = _borrow_stable_fixed(
+        deps.as_mut(),
+        env,
+        mock_info("operator0000", &[]),
+        Uint256::from(100000u64),
+        None,
+        None,
+        Some(Addr::unchecked("addr0000")),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "addr0000".to_string(),
+            amount: vec![deduct_tax(
+                deps.as_ref(),
+                Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(100000u128),
+                }
+            )
+            .unwrap()]
+        }))]
+    );
+}
+
+#[test]
+#[ignore = "deprecated functionality"]
+fn claim_rewards() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+    }]);
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // Register anchor token contract
+    let mut token_inst_res = MsgInstantiateContractResponse::new();
+    token_inst_res.set_contract_address("at-uusd".to_string());
+    let reply_msg = Reply {
+        id: 1,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
+        }),
+    };
+    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+
+    // Register overseer contract
+    let msg = ExecuteMsg::RegisterContracts {
+        overseer_contract: "overseer".to_string(),
+        interest_model: "interest".to_string(),
+        distribution_model: "distribution".to_string(),
+        collector_contract: "collector".to_string(),
+        distributor_contract: "distributor".to_string(),
+    };
+    let info = mock_info("addr0000", &[]);
+    let mut env = mock_env();
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
+
+    store_state(
+        deps.as_mut().storage,
+        &State {
+            total_liabilities: Decimal256::from_uint256(1000000u128),
+            total_reserves: Decimal256::zero(),
+            last_interest_updated: env.block.time.seconds(),
+            last_reward_updated: env.block.time.seconds(),
+            global_interest_index: Decimal256::one(),
+            global_reward_index: Decimal256::zero(),
+            anc_emission_rate: Decimal256::one(),
+            aterra_supply: Uint256::zero(),
+            prev_aterra_supply: Uint256::zero(),
+            prev_exchange_rate: Decimal256::one(),
+        },
+    )
+    .unwrap();
+
+    // zero loan claim, will return empty messages
+    let msg = ExecuteMsg::ClaimRewards { to: None };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    assert_eq!(res.messages.len(), 0);
+
+    let msg = ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(500000u64),
+        to: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    // zero block passed
+    let msg = ExecuteMsg::ClaimRewards {
+        to: Some("addr0001".to_string()),
+    };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+    assert_eq!(res.messages.len(), 0);
+
+    // 100 blocks passed
+    env.block.time = env.block.time.plus_seconds(100);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "distributor".to_string(),
+            funds: vec![],
+            msg: to_binary(&FaucetExecuteMsg::Spend {
+                recipient: "addr0001".to_string(),
+                amount: Uint128::from(33u128),
+            })
+            .unwrap(),
+        }))]
+    );
+
+    let res: BorrowerInfoResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::BorrowerInfo {
+                borrower: "addr0000".to_string(),
+                block_time: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.pending_rewards,
+        Decimal256::from_str("0.333333333333").unwrap()
+    );
+    assert_eq!(
+        res.reward_index,
+        Decimal256::from_str("0.000066666666666666").unwrap()
+    );
+}
+
+#[test]
+fn execute_epoch_operations() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+    }]);
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // Register anchor token contract
     let mut token_inst_res = MsgInstantiateContractResponse::new();
     token_inst_res.set_contract_address("at-uusd".to_string());
     let reply_msg = Reply {
@@ -1746,18 +2958,19 @@ fn execute_epoch_operations() {
         &State {
             total_liabilities: Decimal256::from_uint256(1000000u128),
             total_reserves: Decimal256::from_uint256(3000u128),
-            last_interest_updated: env.block.height,
-            last_reward_updated: env.block.height,
+            last_interest_updated: env.block.time.seconds(),
+            last_reward_updated: env.block.time.seconds(),
             global_interest_index: Decimal256::one(),
             global_reward_index: Decimal256::zero(),
             anc_emission_rate: Decimal256::one(),
+            aterra_supply: Uint256::zero(),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
         },
     )
     .unwrap();
 
-    env.block.height += 100;
+    env.block.time = env.block.time.plus_seconds(100);
 
     // reserve == 3000
     let msg = ExecuteMsg::ExecuteEpochOperations {
@@ -1793,11 +3006,12 @@ fn execute_epoch_operations() {
         State {
             total_liabilities: Decimal256::from_uint256(2000000u128),
             total_reserves: Decimal256::zero(),
-            last_interest_updated: env.block.height,
-            last_reward_updated: env.block.height,
+            last_interest_updated: env.block.time.seconds(),
+            last_reward_updated: env.block.time.seconds(),
             global_interest_index: Decimal256::from_uint256(2u64),
             global_reward_index: Decimal256::from_str("0.0001").unwrap(),
             anc_emission_rate: Decimal256::one(),
+            aterra_supply: Uint256::zero(),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
         }
@@ -1820,18 +3034,19 @@ fn execute_epoch_operations() {
         &State {
             total_liabilities: Decimal256::from_uint256(1000000u128),
             total_reserves: Decimal256::from_uint256(3000u128),
-            last_interest_updated: env.block.height,
-            last_reward_updated: env.block.height,
+            last_interest_updated: env.block.time.seconds(),
+            last_reward_updated: env.block.time.seconds(),
             global_interest_index: Decimal256::one(),
             global_reward_index: Decimal256::zero(),
             anc_emission_rate: Decimal256::one(),
+            aterra_supply: Uint256::zero(),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
         },
     )
     .unwrap();
 
-    env.block.height += 100;
+    env.block.time = env.block.time.plus_seconds(100);
 
     // reserve == 3000
     let msg = ExecuteMsg::ExecuteEpochOperations {
@@ -1850,11 +3065,12 @@ fn execute_epoch_operations() {
         State {
             total_liabilities: Decimal256::from_uint256(2000000u128),
             total_reserves: Decimal256::from_uint256(3000u128),
-            last_interest_updated: env.block.height,
-            last_reward_updated: env.block.height,
+            last_interest_updated: env.block.time.seconds(),
+            last_reward_updated: env.block.time.seconds(),
             global_interest_index: Decimal256::from_uint256(2u64),
             global_reward_index: Decimal256::from_str("0.0001").unwrap(),
             anc_emission_rate: Decimal256::one(),
+            aterra_supply: Uint256::zero(),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
         }
@@ -1929,8 +3145,8 @@ fn execute_epoch_operations() {
 //         &State {
 //             total_liabilities: Decimal256::from_str("8.198749212085782102").unwrap(),
 //             total_reserves: Decimal256::from_str("372025697.802295205294219818").unwrap(),
-//             last_interest_updated: env.block.height,
-//             last_reward_updated: env.block.height,
+//             last_interest_updated: env.block.time.seconds(),
+//             last_reward_updated: env.block.time.seconds(),
 //             global_interest_index: Decimal256::from_str("1.000005078160215988").unwrap(),
 //             global_reward_index: Decimal256::from_str("119531.277425251814227128").unwrap(),
 //             anc_emission_rate: Decimal256::from_str("980001.99").unwrap(),
@@ -1940,7 +3156,7 @@ fn execute_epoch_operations() {
 //     )
 //     .unwrap();
 
-//     env.block.height += 100;
+//     env.block.time = env.block.time.plus_seconds(100);
 
 //     let msg = ExecuteMsg::ExecuteEpochOperations {
 //         deposit_rate: Decimal256::one(),
@@ -1951,3 +3167,120 @@ fn execute_epoch_operations() {
 //     // only overseer can execute this
 //     let _ = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
 // }
+
+#[test]
+fn migrate_block_based_state() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+    }]);
+
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        stable_denom: "uusd".to_string(),
+        aterra_code_id: 123u64,
+        anc_emission_rate: Decimal256::one(),
+        max_borrow_factor: Decimal256::one(),
+    };
+
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let env = mock_env();
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // Register anchor token contract
+    let mut token_inst_res = MsgInstantiateContractResponse::new();
+    token_inst_res.set_contract_address("at-uusd".to_string());
+    let reply_msg = Reply {
+        id: 1,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
+        }),
+    };
+    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+
+    let msg = ExecuteMsg::RegisterContracts {
+        overseer_contract: "overseer".to_string(),
+        interest_model: "interest".to_string(),
+        distribution_model: "distribution".to_string(),
+        collector_contract: "collector".to_string(),
+        distributor_contract: "distributor".to_string(),
+    };
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg).unwrap();
+
+    // the interest model got migrated first and reports per-second rates
+    deps.querier.with_borrow_rate(&[(
+        &"interest".to_string(),
+        &Decimal256::from_str("0.000000001").unwrap(),
+    )]);
+    deps.querier.with_token_balances(&[(
+        &"at-uusd".to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::from(2000000u128))],
+    )]);
+
+    // state stored by the block based contract, last updated 100 blocks ago
+    let legacy_state = State {
+        total_liabilities: Decimal256::from_uint256(1000000u128),
+        total_reserves: Decimal256::zero(),
+        last_interest_updated: env.block.height - 100,
+        last_reward_updated: env.block.height - 100,
+        global_interest_index: Decimal256::one(),
+        global_reward_index: Decimal256::zero(),
+        anc_emission_rate: Decimal256::percent(1),
+        // the supply ledger is not part of the legacy state
+        aterra_supply: Uint256::zero(),
+        prev_aterra_supply: Uint256::from(1000000u64),
+        prev_exchange_rate: Decimal256::one(),
+    };
+    store_state(deps.as_mut().storage, &legacy_state).unwrap();
+    // nor does it store a contract version
+    deps.as_mut().storage.remove(b"contract_info");
+
+    // interest settles with the legacy per-block rates
+    // interest = 1,000,000 * 0.0001 * 100 = 10,000
+    // exchange_rate = (1,000,000 + 1,010,000) / 2,000,000 = 1.005
+    // deposit_rate = 0.005 / 100 = 0.00005, above the 0.00001 target
+    // reserves = 1,000,000 * 100 * 0.00004 = 4,000
+    // reward_index = 100 * 0.01 / 1,000,000 = 0.000001
+    let msg = MigrateMsg::FromLegacy {
+        borrow_rate: Decimal256::from_str("0.0001").unwrap(),
+        target_deposit_rate: Decimal256::from_str("0.00001").unwrap(),
+    };
+    let _res = migrate(deps.as_mut(), env.clone(), msg.clone()).unwrap();
+    let state = read_state(deps.as_ref().storage).unwrap();
+    assert_eq!(
+        state,
+        State {
+            total_liabilities: Decimal256::from_uint256(1010000u128),
+            total_reserves: Decimal256::from_uint256(4000u128),
+            last_interest_updated: env.block.time.seconds(),
+            last_reward_updated: env.block.time.seconds(),
+            global_interest_index: Decimal256::from_str("1.01").unwrap(),
+            global_reward_index: Decimal256::from_str("0.000001").unwrap(),
+            anc_emission_rate: Decimal256::from_str("0.001476664764079147").unwrap(),
+            aterra_supply: Uint256::from(2000000u64),
+            prev_aterra_supply: Uint256::from(2000000u64),
+            prev_exchange_rate: Decimal256::from_str("1.003").unwrap(),
+        }
+    );
+
+    // the legacy migration cannot run twice
+    let res = migrate(deps.as_mut(), env.clone(), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "Contract is already at version 0.0.0; legacy migration not allowed"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // upgrades do not convert the rates again
+    let _res = migrate(deps.as_mut(), env, MigrateMsg::Upgrade {}).unwrap();
+    assert_eq!(read_state(deps.as_ref().storage).unwrap(), state);
+}
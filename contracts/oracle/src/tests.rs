@@ -1,8 +1,8 @@
 use crate::contract::{execute, instantiate, query};
 use crate::error::ContractError;
 use cosmwasm_bignumber::Decimal256;
-use cosmwasm_std::from_binary;
 use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+use cosmwasm_std::{from_binary, StdError};
 use moneymarket::oracle::{
     ConfigResponse, ExecuteMsg, FeederResponse, InstantiateMsg, PriceResponse, PricesResponse,
     PricesResponseElem, QueryMsg,
@@ -32,7 +32,7 @@ fn proper_initialization() {
 }
 
 #[test]
-fn update_config() {
+fn transfer_ownership() {
     let mut deps = mock_dependencies(&[]);
 
     let msg = InstantiateMsg {
@@ -43,12 +43,18 @@ fn update_config() {
     let info = mock_info("addr0000", &[]);
     let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
 
-    // update owner
+    // transfer the ownership
     let info = mock_info("owner0000", &[]);
-    let msg = ExecuteMsg::UpdateConfig {
-        owner: Some("owner0001".to_string()),
+    let msg = ExecuteMsg::ProposeNewOwner {
+        owner: "owner0001".to_string(),
+        expires_in: 86400u64,
     };
 
+    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let info = mock_info("owner0001", &[]);
+    let msg = ExecuteMsg::ClaimOwnership {};
+
     let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
     assert_eq!(0, res.messages.len());
 
@@ -60,11 +66,16 @@ fn update_config() {
 
     // Unauthorized err
     let info = mock_info("owner0000", &[]);
-    let msg = ExecuteMsg::UpdateConfig { owner: None };
+    let msg = ExecuteMsg::ProposeNewOwner {
+        owner: "owner0000".to_string(),
+        expires_in: 86400u64,
+    };
 
     let res = execute(deps.as_mut(), mock_env(), info, msg);
     match res {
-        Err(ContractError::Unauthorized {}) => (),
+        Err(ContractError::Std(StdError::GenericErr { msg, .. })) => {
+            assert_eq!(msg, "unauthorized")
+        }
         _ => panic!("Must return unauthorized error"),
     }
 }
@@ -1,26 +1,43 @@
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{
-    attr, to_binary, Addr, BankMsg, Coin, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Response,
-    StdResult, SubMsg, WasmMsg,
+    attr, to_binary, Addr, BankMsg, CanonicalAddr, Coin, CosmosMsg, Deps, DepsMut, Env,
+    MessageInfo, Response, StdResult, Storage, SubMsg, WasmMsg,
 };
+use std::cmp::min;
 
 use crate::error::ContractError;
 use crate::querier::{query_borrower_info, query_liquidation_amount};
 use crate::state::{
-    read_all_collaterals, read_collaterals, read_config, read_whitelist_elem, store_collaterals,
-    Config, WhitelistElem,
+    read_all_collaterals, read_bad_debt, read_bad_debts, read_borrowers_collaterals,
+    read_collaterals, read_config, read_dynrate_state, read_epoch_state,
+    read_last_liquidation_height, read_liquidation_config, read_margin_call, read_total_bad_debt,
+    read_total_collateral, read_whitelist, read_whitelist_elem, read_write_off_config,
+    read_write_off_info, read_write_off_state, read_write_offs, remove_margin_call, store_bad_debt,
+    store_collaterals, store_dynrate_state, store_epoch_state, store_last_liquidation_height,
+    store_margin_call, store_total_bad_debt, store_total_collateral, store_write_off_info,
+    store_write_off_state, BadDebtInfo, Config, DynrateState, EpochState, LiquidationConfig,
+    WhitelistElem, WriteOffConfig, WriteOffInfo, WriteOffState,
 };
 
 use moneymarket::custody::ExecuteMsg as CustodyExecuteMsg;
 use moneymarket::liquidation::LiquidationAmountResponse;
 use moneymarket::market::{BorrowerInfoResponse, ExecuteMsg as MarketExecuteMsg};
+use moneymarket::operator::{assert_operator, OperatorPermission};
 use moneymarket::oracle::PriceResponse;
-use moneymarket::overseer::{AllCollateralsResponse, BorrowLimitResponse, CollateralsResponse};
+use moneymarket::overseer::{
+    AllCollateralsResponse, BadDebtResponse, BadDebtsResponse, BorrowLimitResponse,
+    BorrowerHealthResponse, CollateralHealthResponse, CollateralUsageResponse,
+    CollateralUsageResponseElem, CollateralsResponse, ExecuteMsg, LiquidatableBorrowerResponse,
+    LiquidatableBorrowersResponse, MarginCallResponse, TotalBadDebtResponse, WhitelistStatus,
+    WriteOffResponse, WriteOffsResponse,
+};
+use moneymarket::pause::{assert_not_paused, PauseAction};
 use moneymarket::querier::{query_balance, query_price, TimeConstraints};
 use moneymarket::tokens::{Tokens, TokensHuman, TokensMath, TokensToHuman, TokensToRaw};
 
 pub fn lock_collateral(
-    deps: DepsMut,
+    mut deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     collaterals_human: TokensHuman,
 ) -> Result<Response, ContractError> {
@@ -29,12 +46,29 @@ pub fn lock_collateral(
 
     let collaterals: Tokens = collaterals_human.to_raw(deps.as_ref())?;
 
-    cur_collaterals.add(collaterals.clone());
-    store_collaterals(deps.storage, &borrower_raw, &cur_collaterals)?;
-
+    // Every collateral must be whitelisted before anything is stored;
+    // the custody contract then checks the borrower's spendable balance
     let mut messages: Vec<CosmosMsg> = vec![];
-    for collateral in collaterals {
+    for collateral in collaterals.iter() {
+        if collateral.1.is_zero() {
+            return Err(ContractError::ZeroLockAmount {});
+        }
+
         let whitelist_elem: WhitelistElem = read_whitelist_elem(deps.storage, &collateral.0)?;
+        if whitelist_elem.status == WhitelistStatus::Paused {
+            return Err(ContractError::CollateralPaused {});
+        }
+
+        let total_amount = read_total_collateral(deps.storage, &collateral.0) + collateral.1;
+        if let Some(max_collateral_amount) = whitelist_elem.max_collateral_amount {
+            if total_amount > max_collateral_amount {
+                return Err(ContractError::MaxCollateralAmountExceeded(
+                    max_collateral_amount.into(),
+                ));
+            }
+        }
+
+        store_total_collateral(deps.storage, &collateral.0, &total_amount)?;
         messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
             contract_addr: deps
                 .api
@@ -48,6 +82,10 @@ pub fn lock_collateral(
         }));
     }
 
+    cur_collaterals.add(collaterals);
+    store_collaterals(deps.storage, &borrower_raw, &cur_collaterals)?;
+    remove_safe_margin_call(deps.branch(), &env, &info.sender)?;
+
     // Logging stuff, so can be removed
     let collateral_logs: Vec<String> = collaterals_human
         .iter()
@@ -66,12 +104,26 @@ pub fn unlock_collateral(
     env: Env,
     info: MessageInfo,
     collaterals_human: TokensHuman,
+    borrower: Option<Addr>,
 ) -> Result<Response, ContractError> {
+    assert_not_paused(deps.storage, PauseAction::Unlock)?;
+
     let config: Config = read_config(deps.storage)?;
     let market = deps.api.addr_humanize(&config.market_contract)?;
 
-    let borrower = info.sender;
+    // Operators can unlock, but the collaterals are
+    // always released to the borrower in the custody
+    let borrower = borrower.unwrap_or_else(|| info.sender.clone());
     let borrower_raw = deps.api.addr_canonicalize(borrower.as_str())?;
+    if borrower != info.sender {
+        assert_operator(
+            deps.storage,
+            &borrower_raw,
+            &deps.api.addr_canonicalize(info.sender.as_str())?,
+            OperatorPermission::Unlock,
+            env.block.time.seconds(),
+        )?;
+    }
     let mut cur_collaterals: Tokens = read_collaterals(deps.storage, &borrower_raw);
     let collaterals: Tokens = collaterals_human.to_raw(deps.as_ref())?;
 
@@ -84,15 +136,21 @@ pub fn unlock_collateral(
     let (borrow_limit, _) = compute_borrow_limit(
         deps.as_ref(),
         &cur_collaterals,
+        env.block.time.seconds(),
         Some(env.block.time.seconds()),
     )?;
-    let borrow_amount_res: BorrowerInfoResponse =
-        query_borrower_info(deps.as_ref(), market, borrower.clone(), env.block.height)?;
-    if borrow_limit < borrow_amount_res.loan_amount {
+    let borrow_amount_res: BorrowerInfoResponse = query_borrower_info(
+        deps.as_ref(),
+        market,
+        borrower.clone(),
+        env.block.time.seconds(),
+    )?;
+    if borrow_limit < borrow_amount_res.total_loan_amount() {
         return Err(ContractError::UnlockTooLarge(borrow_limit.into()));
     }
 
     store_collaterals(deps.storage, &borrower_raw, &cur_collaterals)?;
+    decrease_total_collaterals(deps.storage, &collaterals)?;
 
     let mut messages: Vec<SubMsg> = vec![];
     for collateral in collaterals {
@@ -126,53 +184,241 @@ pub fn unlock_collateral(
 }
 
 pub fn liquidate_collateral(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    borrower: Addr,
+) -> Result<Response, ContractError> {
+    assert_not_paused(deps.storage, PauseAction::Liquidate)?;
+
+    let liquidation_amount = compute_liquidation_amount(deps.branch(), &env, &borrower)?;
+    let liquidation_messages = liquidation_messages(
+        deps.as_ref(),
+        &env,
+        &info.sender,
+        &borrower,
+        &liquidation_amount,
+    )?;
+
+    Ok(Response::new().add_messages(liquidation_messages))
+}
+
+/// Liquidate the collaterals of each unsafe borrower; safe loans and borrowers
+/// which were liquidated too recently or are in a grace period are skipped
+/// Executor: anyone
+pub fn liquidate_collaterals(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    borrowers: Vec<Addr>,
+) -> Result<Response, ContractError> {
+    assert_not_paused(deps.storage, PauseAction::Liquidate)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut attributes = vec![attr("action", "liquidate_collaterals")];
+    let mut liquidated_borrowers: Vec<Addr> = vec![];
+    for borrower in borrowers.into_iter() {
+        // the loan of a liquidated borrower is only repaid once the
+        // messages are executed, so it cannot be liquidated twice
+        if liquidated_borrowers.contains(&borrower) {
+            attributes.push(attr("borrower", borrower.as_str()));
+            attributes.push(attr("result", "skipped"));
+            continue;
+        }
+
+        let liquidation_amount = match compute_liquidation_amount(deps.branch(), &env, &borrower) {
+            Ok(liquidation_amount) => liquidation_amount,
+            Err(ContractError::CannotLiquidateSafeLoan {})
+            | Err(ContractError::LiquidationTooSoon(_))
+            | Err(ContractError::BorrowerNotFlagged {})
+            | Err(ContractError::GracePeriodNotPassed(_)) => {
+                attributes.push(attr("borrower", borrower.as_str()));
+                attributes.push(attr("result", "skipped"));
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
+
+        let liquidation_amount = liquidation_amount.to_human(deps.as_ref())?;
+        attributes.push(attr("borrower", borrower.as_str()));
+        attributes.push(attr("result", "liquidated"));
+        attributes.push(attr(
+            "collaterals",
+            liquidation_amount
+                .iter()
+                .map(|collateral| format!("{}{}", collateral.1, collateral.0))
+                .collect::<Vec<String>>()
+                .join(","),
+        ));
+
+        // each liquidation is executed by a separate call, so the market balance
+        // before the liquidation is queried after the previous ones completed
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: env.contract.address.to_string(),
+            funds: vec![],
+            msg: to_binary(&ExecuteMsg::ExecuteLiquidation {
+                liquidator: info.sender.to_string(),
+                borrower: borrower.to_string(),
+                collaterals: liquidation_amount,
+            })?,
+        }));
+        liquidated_borrowers.push(borrower);
+    }
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(attributes))
+}
+
+/// Send the collaterals computed by liquidate_collaterals to the liquidation
+/// Executor: overseer
+pub fn execute_liquidation(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
+    liquidator: Addr,
     borrower: Addr,
+    collaterals: TokensHuman,
 ) -> Result<Response, ContractError> {
+    if info.sender != env.contract.address {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let liquidation_amount: Tokens = collaterals.to_raw(deps.as_ref())?;
+    let liquidation_messages = liquidation_messages(
+        deps.as_ref(),
+        &env,
+        &liquidator,
+        &borrower,
+        &liquidation_amount,
+    )?;
+
+    Ok(Response::new().add_messages(liquidation_messages))
+}
+
+/// Computes the collaterals to liquidate and stores the left collaterals
+fn compute_liquidation_amount(
+    deps: DepsMut,
+    env: &Env,
+    borrower: &Addr,
+) -> Result<Tokens, ContractError> {
     let config: Config = read_config(deps.storage)?;
     let market = deps.api.addr_humanize(&config.market_contract)?;
 
     let borrower_raw = deps.api.addr_canonicalize(borrower.as_str())?;
     let mut cur_collaterals: Tokens = read_collaterals(deps.storage, &borrower_raw);
 
-    // Compute borrow limit with collaterals except unlock target collaterals
-    let (borrow_limit, collateral_prices) = compute_borrow_limit(
+    // Compute liquidation limit with the liquidation LTV of each collateral
+    let (liquidation_limit, collateral_prices) = compute_liquidation_limit(
         deps.as_ref(),
         &cur_collaterals,
         Some(env.block.time.seconds()),
     )?;
-    let borrow_amount_res: BorrowerInfoResponse =
-        query_borrower_info(deps.as_ref(), market, borrower.clone(), env.block.height)?;
-    let borrow_amount = borrow_amount_res.loan_amount;
+    let borrow_amount_res: BorrowerInfoResponse = query_borrower_info(
+        deps.as_ref(),
+        market,
+        borrower.clone(),
+        env.block.time.seconds(),
+    )?;
+    let borrow_amount = borrow_amount_res.total_loan_amount();
 
-    // borrow limit is equal or bigger than loan amount
+    // liquidation limit is equal or bigger than loan amount
     // cannot liquidation collaterals
-    if borrow_limit >= borrow_amount {
+    if liquidation_limit >= borrow_amount {
         return Err(ContractError::CannotLiquidateSafeLoan {});
     }
 
+    // During the grace period of a margin call, only loans
+    // above the hard liquidation LTV can be liquidated
+    let liquidation_config: LiquidationConfig = read_liquidation_config(deps.storage)?;
+    if liquidation_config.margin_call_grace_period > 0 {
+        let collaterals_value = compute_collaterals_value(&cur_collaterals, &collateral_prices);
+        if borrow_amount <= collaterals_value * liquidation_config.hard_liquidation_ltv {
+            let flagged_time = match read_margin_call(deps.storage, &borrower_raw) {
+                Some(flagged_time) => flagged_time,
+                None => return Err(ContractError::BorrowerNotFlagged {}),
+            };
+
+            let grace_period_end = flagged_time + liquidation_config.margin_call_grace_period;
+            if env.block.time.seconds() < grace_period_end {
+                return Err(ContractError::GracePeriodNotPassed(grace_period_end));
+            }
+        }
+    }
+
+    if let Some(last_liquidation_height) = read_last_liquidation_height(deps.storage, &borrower_raw)
+    {
+        let next_liquidation_height =
+            last_liquidation_height + liquidation_config.liquidation_interval;
+        if env.block.height < next_liquidation_height {
+            return Err(ContractError::LiquidationTooSoon(next_liquidation_height));
+        }
+    }
+
     let liquidation_amount_res: LiquidationAmountResponse = query_liquidation_amount(
         deps.as_ref(),
         deps.api.addr_humanize(&config.liquidation_contract)?,
         borrow_amount,
-        borrow_limit,
+        liquidation_limit,
         &cur_collaterals.to_human(deps.as_ref())?,
-        collateral_prices,
+        collateral_prices.clone(),
     )?;
 
-    let liquidation_amount = liquidation_amount_res.collaterals.to_raw(deps.as_ref())?;
+    let mut liquidation_amount = liquidation_amount_res.collaterals.to_raw(deps.as_ref())?;
+
+    // Cap the value of the liquidated collaterals, which bounds
+    // the repay amount, with the close factor share of the loan
+    let liquidation_value = liquidation_amount
+        .iter()
+        .map(|collateral| {
+            let price = cur_collaterals
+                .iter()
+                .zip(collateral_prices.iter())
+                .find(|(cur_collateral, _)| cur_collateral.0 == collateral.0)
+                .map(|(_, price)| *price)
+                .unwrap_or_else(Decimal256::zero);
+            collateral.1 * price
+        })
+        .fold(Uint256::zero(), |value, collateral_value| {
+            value + collateral_value
+        });
+    let max_liquidation_value = borrow_amount * liquidation_config.close_factor;
+    if liquidation_value > max_liquidation_value {
+        let ratio = Decimal256::from_uint256(max_liquidation_value)
+            / Decimal256::from_uint256(liquidation_value);
+        liquidation_amount = liquidation_amount
+            .into_iter()
+            .map(|collateral| (collateral.0, collateral.1 * ratio))
+            .filter(|collateral| !collateral.1.is_zero())
+            .collect();
+    }
 
     // Store left collaterals
     cur_collaterals.sub(liquidation_amount.clone())?;
     store_collaterals(deps.storage, &borrower_raw, &cur_collaterals)?;
+    decrease_total_collaterals(deps.storage, &liquidation_amount)?;
+    store_last_liquidation_height(deps.storage, &borrower_raw, env.block.height)?;
+
+    // A borrower still unsafe after the liquidation has to be flagged again
+    remove_margin_call(deps.storage, &borrower_raw);
 
+    Ok(liquidation_amount)
+}
+
+/// Builds the custody liquidation messages followed by the market repayment
+#[allow(clippy::ptr_arg)]
+fn liquidation_messages(
+    deps: Deps,
+    env: &Env,
+    liquidator: &Addr,
+    borrower: &Addr,
+    liquidation_amount: &Tokens,
+) -> StdResult<Vec<CosmosMsg>> {
+    let config: Config = read_config(deps.storage)?;
     let market_contract = deps.api.addr_humanize(&config.market_contract)?;
-    let prev_balance: Uint256 =
-        query_balance(deps.as_ref(), market_contract.clone(), config.stable_denom)?;
+    let prev_balance: Uint256 = query_balance(deps, market_contract.clone(), config.stable_denom)?;
 
-    let liquidation_messages: Vec<CosmosMsg> = liquidation_amount
+    let mut messages: Vec<CosmosMsg> = liquidation_amount
         .iter()
         .map(|collateral| {
             let whitelist_elem: WhitelistElem = read_whitelist_elem(deps.storage, &collateral.0)?;
@@ -184,7 +430,7 @@ pub fn liquidate_collateral(
                     .to_string(),
                 funds: vec![],
                 msg: to_binary(&CustodyExecuteMsg::LiquidateCollateral {
-                    liquidator: info.sender.to_string(),
+                    liquidator: liquidator.to_string(),
                     borrower: borrower.to_string(),
                     amount: collateral.1,
                 })?,
@@ -193,33 +439,305 @@ pub fn liquidate_collateral(
         .filter(|msg| msg.is_ok())
         .collect::<StdResult<Vec<CosmosMsg>>>()?;
 
-    Ok(Response::new()
-        .add_messages(liquidation_messages)
-        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: market_contract.to_string(),
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: market_contract.to_string(),
+        funds: vec![],
+        msg: to_binary(&MarketExecuteMsg::RepayStableFromLiquidation {
+            borrower: borrower.to_string(),
+            prev_balance,
+        })?,
+    }));
+
+    // No collaterals are left to cover the loan, record the remaining
+    // loan as bad debt once the market has applied the repayment
+    let borrower_raw = deps.api.addr_canonicalize(borrower.as_str())?;
+    if read_collaterals(deps.storage, &borrower_raw).is_empty() {
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: env.contract.address.to_string(),
             funds: vec![],
-            msg: to_binary(&MarketExecuteMsg::RepayStableFromLiquidation {
+            msg: to_binary(&ExecuteMsg::RecordBadDebt {
                 borrower: borrower.to_string(),
-                prev_balance,
             })?,
-        })))
+        }));
+    }
+
+    Ok(messages)
 }
 
+/// Flag a liquidatable borrower to start its grace period,
+/// or clear the flag of a borrower whose loan is safe again
+/// Executor: anyone
+pub fn flag_borrower(deps: DepsMut, env: Env, borrower: Addr) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let market = deps.api.addr_humanize(&config.market_contract)?;
+
+    let borrower_raw = deps.api.addr_canonicalize(borrower.as_str())?;
+    let collaterals: Tokens = read_collaterals(deps.storage, &borrower_raw);
+    let (liquidation_limit, _) =
+        compute_liquidation_limit(deps.as_ref(), &collaterals, Some(env.block.time.seconds()))?;
+    let borrow_amount_res: BorrowerInfoResponse = query_borrower_info(
+        deps.as_ref(),
+        market,
+        borrower.clone(),
+        env.block.time.seconds(),
+    )?;
+    let borrow_amount = borrow_amount_res.total_loan_amount();
+
+    let flagged_time = read_margin_call(deps.storage, &borrower_raw);
+    if liquidation_limit >= borrow_amount {
+        if flagged_time.is_none() {
+            return Err(ContractError::CannotFlagSafeLoan {});
+        }
+
+        remove_margin_call(deps.storage, &borrower_raw);
+        return Ok(Response::new().add_attributes(vec![
+            attr("action", "unflag_borrower"),
+            attr("borrower", borrower),
+        ]));
+    }
+
+    if flagged_time.is_some() {
+        return Err(ContractError::BorrowerAlreadyFlagged {});
+    }
+
+    store_margin_call(deps.storage, &borrower_raw, env.block.time.seconds())?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "flag_borrower"),
+        attr("borrower", borrower),
+        attr("flagged_time", env.block.time.seconds().to_string()),
+    ]))
+}
+
+/// Clear the margin call of a borrower whose loan is safe after a repayment
+/// and bring a recorded bad debt down to the remaining loan
+/// Executor: market
+pub fn clear_margin_call(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    borrower: Addr,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.market_contract {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let borrower_raw = deps.api.addr_canonicalize(borrower.as_str())?;
+    let bad_debt: BadDebtInfo = read_bad_debt(deps.storage, &borrower_raw);
+    if !bad_debt.amount.is_zero() {
+        let market = deps.api.addr_humanize(&config.market_contract)?;
+        let borrow_amount_res: BorrowerInfoResponse = query_borrower_info(
+            deps.as_ref(),
+            market,
+            borrower.clone(),
+            env.block.time.seconds(),
+        )?;
+        update_bad_debt(
+            deps.storage,
+            &borrower_raw,
+            borrow_amount_res.total_loan_amount(),
+            bad_debt.recorded_height,
+        )?;
+    }
+
+    let cleared = remove_safe_margin_call(deps, &env, &borrower)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "clear_margin_call"),
+        attr("borrower", borrower),
+        attr("cleared", cleared.to_string()),
+    ]))
+}
+
+/// Removes the margin call of a flagged borrower once
+/// the liquidation limit covers the loan again
+fn remove_safe_margin_call(
+    deps: DepsMut,
+    env: &Env,
+    borrower: &Addr,
+) -> Result<bool, ContractError> {
+    let borrower_raw = deps.api.addr_canonicalize(borrower.as_str())?;
+    if read_margin_call(deps.storage, &borrower_raw).is_none() {
+        return Ok(false);
+    }
+
+    let config: Config = read_config(deps.storage)?;
+    let market = deps.api.addr_humanize(&config.market_contract)?;
+    let collaterals: Tokens = read_collaterals(deps.storage, &borrower_raw);
+    let (liquidation_limit, _) =
+        compute_liquidation_limit(deps.as_ref(), &collaterals, Some(env.block.time.seconds()))?;
+    let borrow_amount_res: BorrowerInfoResponse = query_borrower_info(
+        deps.as_ref(),
+        market,
+        borrower.clone(),
+        env.block.time.seconds(),
+    )?;
+    if liquidation_limit < borrow_amount_res.total_loan_amount() {
+        return Ok(false);
+    }
+
+    remove_margin_call(deps.storage, &borrower_raw);
+    Ok(true)
+}
+
+/// Record the loan left over after a liquidation as bad debt
+/// Executor: overseer
+pub fn record_bad_debt(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    borrower: Addr,
+) -> Result<Response, ContractError> {
+    if info.sender != env.contract.address {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let config: Config = read_config(deps.storage)?;
+    let market = deps.api.addr_humanize(&config.market_contract)?;
+    let borrow_amount_res: BorrowerInfoResponse = query_borrower_info(
+        deps.as_ref(),
+        market,
+        borrower.clone(),
+        env.block.time.seconds(),
+    )?;
+    let loan_amount = borrow_amount_res.total_loan_amount();
+
+    let borrower_raw = deps.api.addr_canonicalize(borrower.as_str())?;
+    update_bad_debt(deps.storage, &borrower_raw, loan_amount, env.block.height)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "record_bad_debt"),
+        attr("borrower", borrower),
+        attr("bad_debt", loan_amount),
+    ]))
+}
+
+/// Sets the bad debt of a borrower to its current loan
+/// and moves the total bad debt by the difference
+fn update_bad_debt(
+    storage: &mut dyn Storage,
+    borrower_raw: &CanonicalAddr,
+    loan_amount: Uint256,
+    recorded_height: u64,
+) -> StdResult<()> {
+    let prev_bad_debt: BadDebtInfo = read_bad_debt(storage, borrower_raw);
+    let total_bad_debt = read_total_bad_debt(storage) + loan_amount - prev_bad_debt.amount;
+
+    store_bad_debt(
+        storage,
+        borrower_raw,
+        &BadDebtInfo {
+            amount: loan_amount,
+            recorded_height,
+        },
+    )?;
+    store_total_bad_debt(storage, &total_bad_debt)
+}
+
+/// Write off the loan of an insolvent borrower with the interest buffer
+/// Executor: owner or bad debt handler
 pub fn repay_stable_from_yield_reserve(
     deps: DepsMut,
     env: Env,
-    _info: MessageInfo,
+    info: MessageInfo,
     borrower: Addr,
 ) -> Result<Response, ContractError> {
     let config: Config = read_config(deps.storage)?;
+    let write_off_config: WriteOffConfig = read_write_off_config(deps.storage)?;
+
+    let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+    if sender_raw != config.owner_addr && Some(sender_raw) != write_off_config.bad_debt_handler {
+        return Err(ContractError::Unauthorized {});
+    }
+
     let market = deps.api.addr_humanize(&config.market_contract)?;
     let borrow_amount_res: BorrowerInfoResponse = query_borrower_info(
         deps.as_ref(),
         market.clone(),
         borrower.clone(),
-        env.block.height,
+        env.block.time.seconds(),
     )?;
-    let borrow_amount = borrow_amount_res.loan_amount;
+    let borrow_amount = borrow_amount_res.total_loan_amount();
+    if borrow_amount.is_zero() {
+        return Err(ContractError::NoLoanToWriteOff {});
+    }
+
+    // Only insolvent loans can be written off; either the borrower
+    // has no collaterals left or their value does not cover the loan
+    let borrower_raw = deps.api.addr_canonicalize(borrower.as_str())?;
+    let collaterals: Tokens = read_collaterals(deps.storage, &borrower_raw);
+    if !collaterals.is_empty() {
+        let (_, collateral_prices) = compute_borrow_limit(
+            deps.as_ref(),
+            &collaterals,
+            env.block.time.seconds(),
+            Some(env.block.time.seconds()),
+        )?;
+        let collaterals_value = compute_collaterals_value(&collaterals, &collateral_prices);
+
+        if collaterals_value >= borrow_amount {
+            return Err(ContractError::CannotWriteOffSolventLoan {});
+        }
+    }
+
+    // Cap the write-off with the allowance left in the current epoch
+    let mut write_off_state: WriteOffState = read_write_off_state(deps.storage)?;
+    if env.block.time.seconds() >= write_off_state.epoch_start_time + config.epoch_period {
+        write_off_state.epoch_start_time = env.block.time.seconds();
+        write_off_state.epoch_written_off = Uint256::zero();
+    }
+
+    if write_off_state.epoch_written_off >= write_off_config.max_write_off_per_epoch {
+        return Err(ContractError::WriteOffCapReached(
+            write_off_state.epoch_start_time + config.epoch_period,
+        ));
+    }
+
+    let write_off_amount = min(
+        borrow_amount,
+        write_off_config.max_write_off_per_epoch - write_off_state.epoch_written_off,
+    );
+
+    write_off_state.epoch_written_off += write_off_amount;
+    write_off_state.total_written_off += write_off_amount;
+    store_write_off_state(deps.storage, &write_off_state)?;
+
+    let mut write_off_info: WriteOffInfo = read_write_off_info(deps.storage, &borrower_raw);
+    write_off_info.amount += write_off_amount;
+    write_off_info.last_written_off_height = env.block.height;
+    store_write_off_info(deps.storage, &borrower_raw, &write_off_info)?;
+
+    // Written off loans are no longer outstanding bad debt
+    let mut bad_debt: BadDebtInfo = read_bad_debt(deps.storage, &borrower_raw);
+    if !bad_debt.amount.is_zero() {
+        let repaid_bad_debt = min(bad_debt.amount, write_off_amount);
+        bad_debt.amount = bad_debt.amount - repaid_bad_debt;
+        store_bad_debt(deps.storage, &borrower_raw, &bad_debt)?;
+        store_total_bad_debt(
+            deps.storage,
+            &(read_total_bad_debt(deps.storage) - repaid_bad_debt),
+        )?;
+    }
+
+    // The interest buffer shrinks by the written off amount,
+    // so the epoch and dynrate baselines must follow
+    let mut epoch_state: EpochState = read_epoch_state(deps.storage)?;
+    epoch_state.prev_interest_buffer = if epoch_state.prev_interest_buffer > write_off_amount {
+        epoch_state.prev_interest_buffer - write_off_amount
+    } else {
+        Uint256::zero()
+    };
+    store_epoch_state(deps.storage, &epoch_state)?;
+
+    let mut dynrate_state: DynrateState = read_dynrate_state(deps.storage)?;
+    let write_off_value = Decimal256::from_uint256(write_off_amount);
+    dynrate_state.prev_yield_reserve = if dynrate_state.prev_yield_reserve > write_off_value {
+        dynrate_state.prev_yield_reserve - write_off_value
+    } else {
+        Decimal256::zero()
+    };
+    store_dynrate_state(deps.storage, &dynrate_state)?;
 
     let prev_balance: Uint256 = query_balance(
         deps.as_ref(),
@@ -232,7 +750,7 @@ pub fn repay_stable_from_yield_reserve(
             to_address: market.to_string(),
             amount: vec![Coin {
                 denom: config.stable_denom,
-                amount: borrow_amount.into(),
+                amount: write_off_amount.into(),
             }],
         }),
         CosmosMsg::Wasm(WasmMsg::Execute {
@@ -245,7 +763,13 @@ pub fn repay_stable_from_yield_reserve(
         }),
     ];
 
-    Ok(Response::new().add_messages(repay_messages))
+    Ok(Response::new()
+        .add_messages(repay_messages)
+        .add_attributes(vec![
+            attr("action", "repay_stable_from_yield_reserve"),
+            attr("borrower", borrower),
+            attr("write_off_amount", write_off_amount),
+        ]))
 }
 
 pub fn query_collaterals(deps: Deps, borrower: Addr) -> StdResult<CollateralsResponse> {
@@ -263,6 +787,165 @@ pub fn query_collaterals(deps: Deps, borrower: Addr) -> StdResult<CollateralsRes
     })
 }
 
+pub fn query_write_offs(
+    deps: Deps,
+    start_after: Option<Addr>,
+    limit: Option<u32>,
+) -> StdResult<WriteOffsResponse> {
+    let start_after = if let Some(start_after) = start_after {
+        Some(deps.api.addr_canonicalize(start_after.as_str())?)
+    } else {
+        None
+    };
+
+    let write_offs: Vec<WriteOffResponse> = read_write_offs(deps, start_after, limit)?;
+
+    Ok(WriteOffsResponse { write_offs })
+}
+
+pub fn query_bad_debt(deps: Deps, borrower: Addr) -> StdResult<BadDebtResponse> {
+    let bad_debt: BadDebtInfo = read_bad_debt(
+        deps.storage,
+        &deps.api.addr_canonicalize(borrower.as_str())?,
+    );
+
+    Ok(BadDebtResponse {
+        borrower: borrower.to_string(),
+        amount: bad_debt.amount,
+        recorded_height: bad_debt.recorded_height,
+    })
+}
+
+pub fn query_all_bad_debts(
+    deps: Deps,
+    start_after: Option<Addr>,
+    limit: Option<u32>,
+) -> StdResult<BadDebtsResponse> {
+    let start_after = if let Some(start_after) = start_after {
+        Some(deps.api.addr_canonicalize(start_after.as_str())?)
+    } else {
+        None
+    };
+
+    let bad_debts: Vec<BadDebtResponse> = read_bad_debts(deps, start_after, limit)?;
+
+    Ok(BadDebtsResponse { bad_debts })
+}
+
+pub fn query_total_bad_debt(deps: Deps) -> StdResult<TotalBadDebtResponse> {
+    Ok(TotalBadDebtResponse {
+        total_bad_debt: read_total_bad_debt(deps.storage),
+    })
+}
+
+pub fn query_margin_call(deps: Deps, borrower: Addr) -> StdResult<MarginCallResponse> {
+    let liquidation_config: LiquidationConfig = read_liquidation_config(deps.storage)?;
+    let flagged_time = read_margin_call(
+        deps.storage,
+        &deps.api.addr_canonicalize(borrower.as_str())?,
+    );
+
+    Ok(MarginCallResponse {
+        borrower: borrower.to_string(),
+        flagged_time,
+        grace_period_end: flagged_time
+            .map(|flagged_time| flagged_time + liquidation_config.margin_call_grace_period),
+    })
+}
+
+pub fn query_liquidatable_borrowers(
+    deps: Deps,
+    env: Env,
+    start_after: Option<Addr>,
+    limit: Option<u32>,
+) -> StdResult<LiquidatableBorrowersResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let market = deps.api.addr_humanize(&config.market_contract)?;
+
+    let start_after = if let Some(start_after) = start_after {
+        Some(deps.api.addr_canonicalize(start_after.as_str())?)
+    } else {
+        None
+    };
+
+    let (borrowers_collaterals, last_scanned_borrower) =
+        read_borrowers_collaterals(deps.storage, start_after, limit)?;
+    let last_scanned_borrower = if let Some(last_scanned_borrower) = last_scanned_borrower {
+        Some(deps.api.addr_humanize(&last_scanned_borrower)?.to_string())
+    } else {
+        None
+    };
+
+    let mut borrowers: Vec<LiquidatableBorrowerResponse> = vec![];
+    for (borrower_raw, collaterals) in borrowers_collaterals.iter() {
+        let borrower = deps.api.addr_humanize(borrower_raw)?;
+        let (liquidation_limit, _) =
+            compute_liquidation_limit(deps, collaterals, Some(env.block.time.seconds()))?;
+        let loan_amount = query_borrower_info(
+            deps,
+            market.clone(),
+            borrower.clone(),
+            env.block.time.seconds(),
+        )?
+        .total_loan_amount();
+
+        if loan_amount > liquidation_limit {
+            borrowers.push(LiquidatableBorrowerResponse {
+                borrower: borrower.to_string(),
+                loan_amount,
+                liquidation_limit,
+                shortfall: loan_amount - liquidation_limit,
+            });
+        }
+    }
+
+    Ok(LiquidatableBorrowersResponse {
+        borrowers,
+        last_scanned_borrower,
+    })
+}
+
+pub fn query_collateral_usage(
+    deps: Deps,
+    block_time: u64,
+    collateral_token: Option<Addr>,
+    start_after: Option<Addr>,
+    limit: Option<u32>,
+) -> StdResult<CollateralUsageResponse> {
+    let collateral_tokens: Vec<CanonicalAddr> = if let Some(collateral_token) = collateral_token {
+        vec![deps.api.addr_canonicalize(collateral_token.as_str())?]
+    } else {
+        let start_after = if let Some(start_after) = start_after {
+            Some(deps.api.addr_canonicalize(start_after.as_str())?)
+        } else {
+            None
+        };
+
+        read_whitelist(deps, start_after, limit)?
+            .iter()
+            .map(|elem| deps.api.addr_canonicalize(&elem.collateral_token))
+            .collect::<StdResult<Vec<CanonicalAddr>>>()?
+    };
+
+    let elems = collateral_tokens
+        .iter()
+        .map(|collateral_token| {
+            let elem: WhitelistElem = read_whitelist_elem(deps.storage, collateral_token)?;
+            let total_amount = read_total_collateral(deps.storage, collateral_token);
+            let price = query_collateral_price(deps, collateral_token, None)?;
+            Ok(CollateralUsageResponseElem {
+                collateral_token: deps.api.addr_humanize(collateral_token)?.to_string(),
+                total_amount,
+                max_collateral_amount: elem.max_collateral_amount,
+                borrow_value: total_amount * price * elem.effective_max_ltv(block_time),
+                max_borrow_value: elem.max_borrow_value,
+            })
+        })
+        .collect::<StdResult<Vec<CollateralUsageResponseElem>>>()?;
+
+    Ok(CollateralUsageResponse { elems })
+}
+
 pub fn query_all_collaterals(
     deps: Deps,
     start_after: Option<Addr>,
@@ -283,53 +966,218 @@ pub fn query_all_collaterals(
 pub(crate) fn compute_borrow_limit(
     deps: Deps,
     collaterals: &Tokens,
+    ramp_time: u64,
     block_time: Option<u64>,
 ) -> StdResult<(Uint256, Vec<Decimal256>)> {
-    let config: Config = read_config(deps.storage)?;
-    let oracle_contract = deps.api.addr_humanize(&config.oracle_contract)?;
-
     let mut borrow_limit: Uint256 = Uint256::zero();
     let mut collateral_prices: Vec<Decimal256> = vec![];
-    for collateral in collaterals.iter() {
-        let collateral_token = collateral.0.clone();
-        let collateral_amount = collateral.1;
-
-        let price: PriceResponse = query_price(
-            deps,
-            oracle_contract.clone(),
-            (deps.api.addr_humanize(&collateral_token)?).to_string(),
-            config.stable_denom.to_string(),
-            block_time.map(|block_time| TimeConstraints {
-                block_time,
-                valid_timeframe: config.price_timeframe,
-            }),
-        )?;
-
-        let elem: WhitelistElem = read_whitelist_elem(deps.storage, &collateral.0)?;
-        let collateral_value = collateral_amount * price.rate;
-        borrow_limit += collateral_value * elem.max_ltv;
-        collateral_prices.push(price.rate);
+    for (collateral, (price, elem)) in
+        collaterals
+            .iter()
+            .zip(query_collateral_prices(deps, collaterals, block_time)?)
+    {
+        borrow_limit += collateral.1 * price * elem.effective_max_ltv(ramp_time);
+        collateral_prices.push(price);
     }
 
     // returns borrow_limit with collaterals value in stable denom
     Ok((borrow_limit, collateral_prices))
 }
 
+/// Same as compute_borrow_limit, but weighs collaterals with their
+/// liquidation_ltv; a loan above this limit can be liquidated
+#[allow(clippy::ptr_arg)]
+pub(crate) fn compute_liquidation_limit(
+    deps: Deps,
+    collaterals: &Tokens,
+    block_time: Option<u64>,
+) -> StdResult<(Uint256, Vec<Decimal256>)> {
+    let mut liquidation_limit: Uint256 = Uint256::zero();
+    let mut collateral_prices: Vec<Decimal256> = vec![];
+    for (collateral, (price, elem)) in
+        collaterals
+            .iter()
+            .zip(query_collateral_prices(deps, collaterals, block_time)?)
+    {
+        let collateral_value = collateral.1 * price;
+        liquidation_limit += collateral_value * elem.liquidation_ltv;
+        collateral_prices.push(price);
+    }
+
+    Ok((liquidation_limit, collateral_prices))
+}
+
+/// Subtracts unlocked or liquidated collaterals from the total locked amounts
+#[allow(clippy::ptr_arg)]
+fn decrease_total_collaterals(storage: &mut dyn Storage, collaterals: &Tokens) -> StdResult<()> {
+    for collateral in collaterals.iter() {
+        let total_amount = read_total_collateral(storage, &collateral.0);
+        store_total_collateral(
+            storage,
+            &collateral.0,
+            &(total_amount - min(total_amount, collateral.1)),
+        )?;
+    }
+
+    Ok(())
+}
+
+#[allow(clippy::ptr_arg)]
+fn compute_collaterals_value(collaterals: &Tokens, collateral_prices: &[Decimal256]) -> Uint256 {
+    collaterals
+        .iter()
+        .zip(collateral_prices.iter())
+        .fold(Uint256::zero(), |value, (collateral, price)| {
+            value + collateral.1 * *price
+        })
+}
+
+/// Returns the oracle price and whitelist elem of each collateral
+#[allow(clippy::ptr_arg)]
+fn query_collateral_prices(
+    deps: Deps,
+    collaterals: &Tokens,
+    block_time: Option<u64>,
+) -> StdResult<Vec<(Decimal256, WhitelistElem)>> {
+    collaterals
+        .iter()
+        .map(|collateral| {
+            let price = query_collateral_price(deps, &collateral.0, block_time)?;
+            let elem: WhitelistElem = read_whitelist_elem(deps.storage, &collateral.0)?;
+            Ok((price, elem))
+        })
+        .collect()
+}
+
+fn query_collateral_price(
+    deps: Deps,
+    collateral_token: &CanonicalAddr,
+    block_time: Option<u64>,
+) -> StdResult<Decimal256> {
+    let config: Config = read_config(deps.storage)?;
+    let oracle_contract = deps.api.addr_humanize(&config.oracle_contract)?;
+
+    let price: PriceResponse = query_price(
+        deps,
+        oracle_contract,
+        (deps.api.addr_humanize(collateral_token)?).to_string(),
+        config.stable_denom.to_string(),
+        block_time.map(|block_time| TimeConstraints {
+            block_time,
+            valid_timeframe: config.price_timeframe,
+        }),
+    )?;
+
+    Ok(price.rate)
+}
+
 pub fn query_borrow_limit(
     deps: Deps,
     borrower: Addr,
+    ramp_time: u64,
     block_time: Option<u64>,
 ) -> StdResult<BorrowLimitResponse> {
-    let collaterals = read_collaterals(
+    // The market checks new borrows against this limit,
+    // so paused collaterals are left out
+    let mut collaterals: Tokens = vec![];
+    for collateral in read_collaterals(
         deps.storage,
         &deps.api.addr_canonicalize(borrower.as_str())?,
-    );
+    ) {
+        let elem: WhitelistElem = read_whitelist_elem(deps.storage, &collateral.0)?;
+        if elem.status != WhitelistStatus::Paused {
+            collaterals.push(collateral);
+        }
+    }
 
     // Compute borrow limit with collaterals
-    let (borrow_limit, _) = compute_borrow_limit(deps, &collaterals, block_time)?;
+    let (borrow_limit, _) = compute_borrow_limit(deps, &collaterals, ramp_time, block_time)?;
 
     Ok(BorrowLimitResponse {
         borrower: borrower.to_string(),
         borrow_limit,
     })
 }
+
+pub fn query_borrower_health(
+    deps: Deps,
+    env: Env,
+    borrower: Addr,
+    block_time: Option<u64>,
+) -> StdResult<BorrowerHealthResponse> {
+    let current_time = block_time.unwrap_or_else(|| env.block.time.seconds());
+    let config: Config = read_config(deps.storage)?;
+    let collaterals = read_collaterals(
+        deps.storage,
+        &deps.api.addr_canonicalize(borrower.as_str())?,
+    );
+
+    let collateral_prices = query_collateral_prices(deps, &collaterals, block_time)?;
+    let collateral_values: Vec<Uint256> = collaterals
+        .iter()
+        .zip(collateral_prices.iter())
+        .map(|(collateral, (price, _))| collateral.1 * *price)
+        .collect();
+
+    let mut collaterals_value = Uint256::zero();
+    let mut borrow_limit = Uint256::zero();
+    let mut liquidation_limit = Uint256::zero();
+    for (value, (_, elem)) in collateral_values.iter().zip(collateral_prices.iter()) {
+        collaterals_value += *value;
+        borrow_limit += *value * elem.effective_max_ltv(current_time);
+        liquidation_limit += *value * elem.liquidation_ltv;
+    }
+
+    let market = deps.api.addr_humanize(&config.market_contract)?;
+    let loan_amount =
+        query_borrower_info(deps, market, borrower.clone(), current_time)?.total_loan_amount();
+
+    let ltv = if collaterals_value.is_zero() {
+        None
+    } else {
+        Some(Decimal256::from_uint256(loan_amount) / Decimal256::from_uint256(collaterals_value))
+    };
+
+    // prices scale the liquidation limit linearly, so the loan becomes
+    // liquidatable once they drop by 1 - loan_amount / liquidation_limit
+    let liquidation_price_drop = if liquidation_limit > loan_amount {
+        Decimal256::one()
+            - Decimal256::from_uint256(loan_amount) / Decimal256::from_uint256(liquidation_limit)
+    } else {
+        Decimal256::zero()
+    };
+
+    let collaterals = collaterals
+        .iter()
+        .zip(collateral_prices.into_iter())
+        .zip(collateral_values.into_iter())
+        .map(|((collateral, (price, elem)), value)| {
+            let weight = if collaterals_value.is_zero() {
+                Decimal256::zero()
+            } else {
+                Decimal256::from_uint256(value) / Decimal256::from_uint256(collaterals_value)
+            };
+
+            Ok(CollateralHealthResponse {
+                collateral_token: deps.api.addr_humanize(&collateral.0)?.to_string(),
+                amount: collateral.1,
+                price,
+                value,
+                weight,
+                max_ltv: elem.effective_max_ltv(current_time),
+                liquidation_ltv: elem.liquidation_ltv,
+            })
+        })
+        .collect::<StdResult<Vec<CollateralHealthResponse>>>()?;
+
+    Ok(BorrowerHealthResponse {
+        borrower: borrower.to_string(),
+        collaterals_value,
+        borrow_limit,
+        liquidation_limit,
+        loan_amount,
+        ltv,
+        liquidation_price_drop,
+        collaterals,
+    })
+}
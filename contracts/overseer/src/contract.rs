@@ -1,37 +1,61 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    attr, to_binary, Addr, BankMsg, Binary, Coin, CosmosMsg, Deps, DepsMut, Env, MessageInfo,
-    Response, StdResult, Uint128, WasmMsg,
+    attr, to_binary, Addr, BankMsg, Binary, CanonicalAddr, Coin, CosmosMsg, Deps, DepsMut, Env,
+    MessageInfo, Reply, ReplyOn, Response, StdResult, Storage, SubMsg, WasmMsg,
 };
 use std::cmp::{max, min};
 
 use crate::collateral::{
-    liquidate_collateral, query_all_collaterals, query_borrow_limit, query_collaterals,
-    repay_stable_from_yield_reserve, unlock_collateral,
+    clear_margin_call, execute_liquidation, flag_borrower, liquidate_collateral,
+    liquidate_collaterals, lock_collateral, query_all_bad_debts, query_all_collaterals,
+    query_bad_debt, query_borrow_limit, query_borrower_health, query_collateral_usage,
+    query_collaterals, query_liquidatable_borrowers, query_margin_call, query_total_bad_debt,
+    query_write_offs, record_bad_debt, repay_stable_from_yield_reserve, unlock_collateral,
 };
 use crate::error::ContractError;
-use crate::querier::query_epoch_state;
+use crate::querier::{query_epoch_state, query_target_deposit_rate};
 
 use crate::state::{
-    read_config, read_dynrate_config, read_dynrate_state, read_epoch_state, read_whitelist,
-    read_whitelist_elem, store_config, store_dynrate_config, store_dynrate_state,
-    store_epoch_state, store_whitelist_elem, Config, DynrateConfig, DynrateState, EpochState,
-    WhitelistElem,
+    migrate_block_based_state, migrate_dynrate_config, migrate_total_collaterals,
+    migrate_whitelist, read_config, read_dynrate_config, read_dynrate_state, read_epoch_history,
+    read_epoch_history_count, read_epoch_state, read_liquidation_config, read_max_liquidation_ltv,
+    read_pending_epoch_operation, read_reserve_state, read_total_collateral, read_whitelist,
+    read_whitelist_elem, read_write_off_config, read_write_off_state,
+    remove_pending_epoch_operation, remove_whitelist_elem, store_config, store_dynrate_config,
+    store_dynrate_state, store_epoch_snapshot, store_epoch_state, store_liquidation_config,
+    store_pending_epoch_operation, store_reserve_state, store_whitelist_elem,
+    store_write_off_config, store_write_off_state, Config, DynrateConfig, DynrateState,
+    EpochSnapshot, EpochState, LiquidationConfig, PendingEpochOperation, ReserveState,
+    WhitelistElem, WriteOffConfig, WriteOffState,
 };
 
 use cosmwasm_bignumber::{Decimal256, Uint256};
+use cw2::set_contract_version;
 use moneymarket::common::optional_addr_validate;
 use moneymarket::custody::ExecuteMsg as CustodyExecuteMsg;
 use moneymarket::market::EpochStateResponse;
 use moneymarket::market::ExecuteMsg as MarketExecuteMsg;
+use moneymarket::migration::{assert_legacy, assert_upgrade};
+use moneymarket::operator::{
+    approve_operator, query_operator_approval, query_operator_approvals, revoke_operator,
+    OperatorPermission,
+};
 use moneymarket::overseer::{
-    ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, WhitelistResponse,
-    WhitelistResponseElem,
+    ConfigResponse, DynrateDecision, EpochHistoryResponse, ExecuteMsg, InstantiateMsg, MaxLtvRamp,
+    MigrateMsg, QueryMsg, ReserveResponse, WhitelistResponse, WhitelistResponseElem,
+    WhitelistStatus,
+};
+use moneymarket::ownership::{
+    claim_ownership, drop_ownership_proposal, propose_new_owner, query_ownership_proposal,
 };
+use moneymarket::pause::{pause, query_pause_info, unpause, update_guardian};
 use moneymarket::querier::{deduct_tax, query_balance};
 
-pub const BLOCKS_PER_YEAR: u128 = 4656810;
+pub const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
+pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+const EPOCH_OPERATIONS_REPLY_ID: u64 = 1;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -39,7 +63,10 @@ pub fn instantiate(
     env: Env,
     _info: MessageInfo,
     msg: InstantiateMsg,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
+    assert_hard_liquidation_ltv(deps.storage, msg.hard_liquidation_ltv)?;
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
     store_config(
         deps.storage,
         &Config {
@@ -62,8 +89,7 @@ pub fn instantiate(
         deps.storage,
         &DynrateConfig {
             dyn_rate_epoch: msg.dyn_rate_epoch,
-            dyn_rate_maxchange: msg.dyn_rate_maxchange,
-            dyn_rate_yr_increase_expectation: msg.dyn_rate_yr_increase_expectation,
+            deposit_rate_model: deps.api.addr_canonicalize(&msg.deposit_rate_model)?,
             dyn_rate_min: msg.dyn_rate_min,
             dyn_rate_max: msg.dyn_rate_max,
         },
@@ -76,15 +102,46 @@ pub fn instantiate(
             prev_aterra_supply: Uint256::zero(),
             prev_interest_buffer: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
-            last_executed_height: env.block.height,
+            last_executed_time: env.block.time.seconds(),
         },
     )?;
 
     store_dynrate_state(
         deps.storage,
         &DynrateState {
-            last_executed_height: env.block.height,
+            last_executed_time: env.block.time.seconds(),
             prev_yield_reserve: Decimal256::zero(),
+            model_state: None,
+        },
+    )?;
+
+    store_write_off_config(
+        deps.storage,
+        &WriteOffConfig {
+            bad_debt_handler: match msg.bad_debt_handler {
+                Some(bad_debt_handler) => Some(deps.api.addr_canonicalize(&bad_debt_handler)?),
+                None => None,
+            },
+            max_write_off_per_epoch: msg.max_write_off_per_epoch,
+        },
+    )?;
+
+    store_write_off_state(
+        deps.storage,
+        &WriteOffState {
+            epoch_start_time: env.block.time.seconds(),
+            epoch_written_off: Uint256::zero(),
+            total_written_off: Uint256::zero(),
+        },
+    )?;
+
+    store_liquidation_config(
+        deps.storage,
+        &LiquidationConfig {
+            close_factor: msg.close_factor,
+            liquidation_interval: msg.liquidation_interval,
+            margin_call_grace_period: msg.margin_call_grace_period,
+            hard_liquidation_ltv: msg.hard_liquidation_ltv,
         },
     )?;
 
@@ -92,7 +149,64 @@ pub fn instantiate(
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
+pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> StdResult<Response> {
+    match msg {
+        MigrateMsg::FromLegacy {
+            deposit_rate_model,
+            bad_debt_handler,
+            max_write_off_per_epoch,
+            close_factor,
+            liquidation_interval,
+            margin_call_grace_period,
+            hard_liquidation_ltv,
+        } => {
+            assert_legacy(deps.storage)?;
+
+            let deposit_rate_model = deps.api.addr_canonicalize(&deposit_rate_model)?;
+            migrate_dynrate_config(deps.storage, deposit_rate_model)?;
+            migrate_block_based_state(deps.storage, env.block.time.seconds())?;
+            migrate_whitelist(deps.storage, env.block.height, env.block.time.seconds())?;
+            migrate_total_collaterals(deps.storage)?;
+
+            if read_write_off_config(deps.storage).is_err() {
+                store_write_off_config(
+                    deps.storage,
+                    &WriteOffConfig {
+                        bad_debt_handler: match bad_debt_handler {
+                            Some(addr) => Some(deps.api.addr_canonicalize(&addr)?),
+                            None => None,
+                        },
+                        max_write_off_per_epoch,
+                    },
+                )?;
+
+                store_write_off_state(
+                    deps.storage,
+                    &WriteOffState {
+                        epoch_start_time: env.block.time.seconds(),
+                        epoch_written_off: Uint256::zero(),
+                        total_written_off: Uint256::zero(),
+                    },
+                )?;
+            }
+
+            if read_liquidation_config(deps.storage).is_err() {
+                store_liquidation_config(
+                    deps.storage,
+                    &LiquidationConfig {
+                        close_factor,
+                        liquidation_interval,
+                        margin_call_grace_period,
+                        hard_liquidation_ltv,
+                    },
+                )?;
+            }
+        }
+        MigrateMsg::Upgrade {} => assert_upgrade(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?,
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
     Ok(Response::default())
 }
 
@@ -105,7 +219,6 @@ pub fn execute(
 ) -> Result<Response, ContractError> {
     match msg {
         ExecuteMsg::UpdateConfig {
-            owner_addr,
             oracle_contract,
             liquidation_contract,
             threshold_deposit_rate,
@@ -115,16 +228,20 @@ pub fn execute(
             epoch_period,
             price_timeframe,
             dyn_rate_epoch,
-            dyn_rate_maxchange,
-            dyn_rate_yr_increase_expectation,
+            deposit_rate_model,
             dyn_rate_min,
             dyn_rate_max,
+            bad_debt_handler,
+            max_write_off_per_epoch,
+            close_factor,
+            liquidation_interval,
+            margin_call_grace_period,
+            hard_liquidation_ltv,
         } => {
             let api = deps.api;
             update_config(
                 deps,
                 info,
-                optional_addr_validate(api, owner_addr)?,
                 optional_addr_validate(api, oracle_contract)?,
                 optional_addr_validate(api, liquidation_contract)?,
                 threshold_deposit_rate,
@@ -134,18 +251,54 @@ pub fn execute(
                 epoch_period,
                 price_timeframe,
                 dyn_rate_epoch,
-                dyn_rate_maxchange,
-                dyn_rate_yr_increase_expectation,
+                optional_addr_validate(api, deposit_rate_model)?,
                 dyn_rate_min,
                 dyn_rate_max,
+                optional_addr_validate(api, bad_debt_handler)?,
+                max_write_off_per_epoch,
+                close_factor,
+                liquidation_interval,
+                margin_call_grace_period,
+                hard_liquidation_ltv,
             )
         }
+        ExecuteMsg::ProposeNewOwner { owner, expires_in } => {
+            let config: Config = read_config(deps.storage)?;
+            Ok(propose_new_owner(
+                deps,
+                env,
+                info,
+                config.owner_addr,
+                owner,
+                expires_in,
+            )?)
+        }
+        ExecuteMsg::DropOwnershipProposal {} => {
+            let config: Config = read_config(deps.storage)?;
+            Ok(drop_ownership_proposal(deps, info, config.owner_addr)?)
+        }
+        ExecuteMsg::ClaimOwnership {} => Ok(claim_ownership(deps, env, info, update_owner)?),
+        ExecuteMsg::UpdateGuardian { guardian } => {
+            let config: Config = read_config(deps.storage)?;
+            Ok(update_guardian(deps, info, config.owner_addr, guardian)?)
+        }
+        ExecuteMsg::Pause { actions } => {
+            let config: Config = read_config(deps.storage)?;
+            Ok(pause(deps, info, config.owner_addr, actions)?)
+        }
+        ExecuteMsg::Unpause { actions } => {
+            let config: Config = read_config(deps.storage)?;
+            Ok(unpause(deps, info, config.owner_addr, actions)?)
+        }
         ExecuteMsg::Whitelist {
             name,
             symbol,
             collateral_token,
             custody_contract,
             max_ltv,
+            liquidation_ltv,
+            max_collateral_amount,
+            max_borrow_value,
         } => {
             let api = deps.api;
             register_whitelist(
@@ -156,35 +309,145 @@ pub fn execute(
                 api.addr_validate(&collateral_token)?,
                 api.addr_validate(&custody_contract)?,
                 max_ltv,
+                liquidation_ltv,
+                max_collateral_amount,
+                max_borrow_value,
             )
         }
         ExecuteMsg::UpdateWhitelist {
             collateral_token,
             custody_contract,
             max_ltv,
+            liquidation_ltv,
+            max_collateral_amount,
+            max_borrow_value,
+            max_ltv_ramp_seconds,
         } => {
             let api = deps.api;
             update_whitelist(
                 deps,
+                env,
                 info,
                 api.addr_validate(&collateral_token)?,
                 optional_addr_validate(api, custody_contract)?,
                 max_ltv,
+                liquidation_ltv,
+                max_collateral_amount,
+                max_borrow_value,
+                max_ltv_ramp_seconds,
             )
         }
-        ExecuteMsg::ExecuteEpochOperations {} => Err(ContractError::Deprecated {}),
-        ExecuteMsg::UpdateEpochState {
-            interest_buffer,
-            distributed_interest,
-        } => update_epoch_state(deps, env, info, interest_buffer, distributed_interest),
-        ExecuteMsg::LockCollateral { collaterals: _ } => Err(ContractError::Deprecated {}),
-        ExecuteMsg::UnlockCollateral { collaterals } => {
-            unlock_collateral(deps, env, info, collaterals)
+        ExecuteMsg::PauseCollateral { collateral_token } => {
+            let api = deps.api;
+            update_whitelist_status(
+                deps,
+                info,
+                api.addr_validate(&collateral_token)?,
+                WhitelistStatus::Paused,
+            )
         }
+        ExecuteMsg::ActivateCollateral { collateral_token } => {
+            let api = deps.api;
+            update_whitelist_status(
+                deps,
+                info,
+                api.addr_validate(&collateral_token)?,
+                WhitelistStatus::Active,
+            )
+        }
+        ExecuteMsg::DeprecateCollateral {
+            collateral_token,
+            ramp_seconds,
+        } => {
+            let api = deps.api;
+            update_whitelist_status(
+                deps,
+                info,
+                api.addr_validate(&collateral_token)?,
+                WhitelistStatus::Deprecated {
+                    start_time: env.block.time.seconds(),
+                    end_time: env.block.time.seconds() + ramp_seconds,
+                },
+            )
+        }
+        ExecuteMsg::Delist { collateral_token } => {
+            let api = deps.api;
+            delist(deps, info, api.addr_validate(&collateral_token)?)
+        }
+        ExecuteMsg::WithdrawReserve { amount, recipient } => {
+            let api = deps.api;
+            withdraw_reserve(deps, env, info, amount, api.addr_validate(&recipient)?)
+        }
+        ExecuteMsg::ExecuteEpochOperations {} => execute_epoch_operations(deps, env),
+        ExecuteMsg::ExecuteLiquidation {
+            liquidator,
+            borrower,
+            collaterals,
+        } => {
+            let api = deps.api;
+            execute_liquidation(
+                deps,
+                env,
+                info,
+                api.addr_validate(&liquidator)?,
+                api.addr_validate(&borrower)?,
+                collaterals,
+            )
+        }
+        ExecuteMsg::RecordBadDebt { borrower } => {
+            let api = deps.api;
+            record_bad_debt(deps, env, info, api.addr_validate(&borrower)?)
+        }
+        ExecuteMsg::ClearMarginCall { borrower } => {
+            let api = deps.api;
+            clear_margin_call(deps, env, info, api.addr_validate(&borrower)?)
+        }
+        ExecuteMsg::LockCollateral { collaterals } => lock_collateral(deps, env, info, collaterals),
+        ExecuteMsg::UnlockCollateral {
+            collaterals,
+            borrower,
+        } => {
+            let api = deps.api;
+            unlock_collateral(
+                deps,
+                env,
+                info,
+                collaterals,
+                optional_addr_validate(api, borrower)?,
+            )
+        }
+        ExecuteMsg::ApproveOperator {
+            operator,
+            permissions,
+            borrow_allowance,
+            expires_at,
+        } => Ok(approve_operator(
+            deps,
+            env,
+            info,
+            &[OperatorPermission::Unlock],
+            operator,
+            permissions,
+            borrow_allowance,
+            expires_at,
+        )?),
+        ExecuteMsg::RevokeOperator { operator } => Ok(revoke_operator(deps, info, operator)?),
         ExecuteMsg::LiquidateCollateral { borrower } => {
             let api = deps.api;
             liquidate_collateral(deps, env, info, api.addr_validate(&borrower)?)
         }
+        ExecuteMsg::FlagBorrower { borrower } => {
+            let api = deps.api;
+            flag_borrower(deps, env, api.addr_validate(&borrower)?)
+        }
+        ExecuteMsg::LiquidateCollaterals { borrowers } => {
+            let api = deps.api;
+            let borrowers = borrowers
+                .iter()
+                .map(|borrower| api.addr_validate(borrower))
+                .collect::<StdResult<Vec<Addr>>>()?;
+            liquidate_collaterals(deps, env, info, borrowers)
+        }
         ExecuteMsg::FundReserve {} => fund_reserve(deps, info),
         ExecuteMsg::RepayStableFromYieldReserve { borrower } => {
             let api = deps.api;
@@ -197,7 +460,6 @@ pub fn execute(
 pub fn update_config(
     deps: DepsMut,
     info: MessageInfo,
-    owner_addr: Option<Addr>,
     oracle_contract: Option<Addr>,
     liquidation_contract: Option<Addr>,
     threshold_deposit_rate: Option<Decimal256>,
@@ -207,22 +469,25 @@ pub fn update_config(
     epoch_period: Option<u64>,
     price_timeframe: Option<u64>,
     dyn_rate_epoch: Option<u64>,
-    dyn_rate_maxchange: Option<Decimal256>,
-    dyn_rate_yr_increase_expectation: Option<Decimal256>,
+    deposit_rate_model: Option<Addr>,
     dyn_rate_min: Option<Decimal256>,
     dyn_rate_max: Option<Decimal256>,
+    bad_debt_handler: Option<Addr>,
+    max_write_off_per_epoch: Option<Uint256>,
+    close_factor: Option<Decimal256>,
+    liquidation_interval: Option<u64>,
+    margin_call_grace_period: Option<u64>,
+    hard_liquidation_ltv: Option<Decimal256>,
 ) -> Result<Response, ContractError> {
     let mut config: Config = read_config(deps.storage)?;
     let mut dynrate_config: DynrateConfig = read_dynrate_config(deps.storage)?;
+    let mut write_off_config: WriteOffConfig = read_write_off_config(deps.storage)?;
+    let mut liquidation_config: LiquidationConfig = read_liquidation_config(deps.storage)?;
 
     if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr {
         return Err(ContractError::Unauthorized {});
     }
 
-    if let Some(owner_addr) = owner_addr {
-        config.owner_addr = deps.api.addr_canonicalize(&owner_addr.to_string())?;
-    }
-
     if let Some(oracle_contract) = oracle_contract {
         config.oracle_contract = deps.api.addr_canonicalize(&oracle_contract.to_string())?;
     }
@@ -261,12 +526,14 @@ pub fn update_config(
         dynrate_config.dyn_rate_epoch = dyn_rate_epoch;
     }
 
-    if let Some(dyn_rate_maxchange) = dyn_rate_maxchange {
-        dynrate_config.dyn_rate_maxchange = dyn_rate_maxchange;
-    }
+    if let Some(deposit_rate_model) = deposit_rate_model {
+        dynrate_config.deposit_rate_model =
+            deps.api.addr_canonicalize(deposit_rate_model.as_str())?;
 
-    if let Some(dyn_rate_yr_increase_expectation) = dyn_rate_yr_increase_expectation {
-        dynrate_config.dyn_rate_yr_increase_expectation = dyn_rate_yr_increase_expectation;
+        // the state of the previous model means nothing to the new one
+        let mut dynrate_state: DynrateState = read_dynrate_state(deps.storage)?;
+        dynrate_state.model_state = None;
+        store_dynrate_state(deps.storage, &dynrate_state)?;
     }
 
     if let Some(dyn_rate_min) = dyn_rate_min {
@@ -277,12 +544,51 @@ pub fn update_config(
         dynrate_config.dyn_rate_max = dyn_rate_max;
     }
 
+    if let Some(bad_debt_handler) = bad_debt_handler {
+        write_off_config.bad_debt_handler =
+            Some(deps.api.addr_canonicalize(bad_debt_handler.as_str())?);
+    }
+
+    if let Some(max_write_off_per_epoch) = max_write_off_per_epoch {
+        write_off_config.max_write_off_per_epoch = max_write_off_per_epoch;
+    }
+
+    if let Some(close_factor) = close_factor {
+        if close_factor.is_zero() || close_factor > Decimal256::one() {
+            return Err(ContractError::InvalidCloseFactor {});
+        }
+
+        liquidation_config.close_factor = close_factor;
+    }
+
+    if let Some(liquidation_interval) = liquidation_interval {
+        liquidation_config.liquidation_interval = liquidation_interval;
+    }
+
+    if let Some(margin_call_grace_period) = margin_call_grace_period {
+        liquidation_config.margin_call_grace_period = margin_call_grace_period;
+    }
+
+    if let Some(hard_liquidation_ltv) = hard_liquidation_ltv {
+        assert_hard_liquidation_ltv(deps.storage, hard_liquidation_ltv)?;
+        liquidation_config.hard_liquidation_ltv = hard_liquidation_ltv;
+    }
+
     store_config(deps.storage, &config)?;
     store_dynrate_config(deps.storage, &dynrate_config)?;
+    store_write_off_config(deps.storage, &write_off_config)?;
+    store_liquidation_config(deps.storage, &liquidation_config)?;
 
     Ok(Response::new().add_attributes(vec![attr("action", "update_config")]))
 }
 
+pub fn update_owner(deps: DepsMut, owner_addr: CanonicalAddr) -> StdResult<()> {
+    let mut config: Config = read_config(deps.storage)?;
+    config.owner_addr = owner_addr;
+    store_config(deps.storage, &config)
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn register_whitelist(
     deps: DepsMut,
     info: MessageInfo,
@@ -291,12 +597,22 @@ pub fn register_whitelist(
     collateral_token: Addr,
     custody_contract: Addr,
     max_ltv: Decimal256,
+    liquidation_ltv: Decimal256,
+    max_collateral_amount: Option<Uint256>,
+    max_borrow_value: Option<Uint256>,
 ) -> Result<Response, ContractError> {
     let config: Config = read_config(deps.storage)?;
     if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr {
         return Err(ContractError::Unauthorized {});
     }
 
+    let liquidation_config: LiquidationConfig = read_liquidation_config(deps.storage)?;
+    assert_liquidation_ltv(
+        max_ltv,
+        liquidation_ltv,
+        liquidation_config.hard_liquidation_ltv,
+    )?;
+
     let collateral_token_raw = deps.api.addr_canonicalize(collateral_token.as_str())?;
     if read_whitelist_elem(deps.storage, &collateral_token_raw).is_ok() {
         return Err(ContractError::TokenAlreadyRegistered {});
@@ -310,6 +626,12 @@ pub fn register_whitelist(
             symbol: symbol.to_string(),
             custody_contract: deps.api.addr_canonicalize(custody_contract.as_str())?,
             max_ltv,
+            liquidation_ltv,
+            // a zero cap means no cap
+            max_collateral_amount: max_collateral_amount.filter(|cap| !cap.is_zero()),
+            max_borrow_value: max_borrow_value.filter(|cap| !cap.is_zero()),
+            status: WhitelistStatus::Active,
+            max_ltv_ramp: None,
         },
     )?;
 
@@ -320,15 +642,22 @@ pub fn register_whitelist(
         attr("collateral_token", collateral_token),
         attr("custody_contract", custody_contract),
         attr("LTV", max_ltv.to_string()),
+        attr("liquidation_LTV", liquidation_ltv.to_string()),
     ]))
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn update_whitelist(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     collateral_token: Addr,
     custody_contract: Option<Addr>,
     max_ltv: Option<Decimal256>,
+    liquidation_ltv: Option<Decimal256>,
+    max_collateral_amount: Option<Uint256>,
+    max_borrow_value: Option<Uint256>,
+    max_ltv_ramp_seconds: Option<u64>,
 ) -> Result<Response, ContractError> {
     let config: Config = read_config(deps.storage)?;
     if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr {
@@ -344,9 +673,48 @@ pub fn update_whitelist(
     }
 
     if let Some(max_ltv) = max_ltv {
+        // the ramp starts from the value in effect now
+        let ramp_seconds = max_ltv_ramp_seconds.unwrap_or(0);
+        whitelist_elem.max_ltv_ramp = if ramp_seconds > 0 {
+            Some(MaxLtvRamp {
+                start_ltv: whitelist_elem.ramped_max_ltv(env.block.time.seconds()),
+                start_time: env.block.time.seconds(),
+                end_time: env.block.time.seconds() + ramp_seconds,
+            })
+        } else {
+            None
+        };
         whitelist_elem.max_ltv = max_ltv;
     }
 
+    if let Some(liquidation_ltv) = liquidation_ltv {
+        whitelist_elem.liquidation_ltv = liquidation_ltv;
+    }
+
+    // a zero cap removes the cap
+    if let Some(max_collateral_amount) = max_collateral_amount {
+        whitelist_elem.max_collateral_amount =
+            Some(max_collateral_amount).filter(|cap| !cap.is_zero());
+    }
+
+    if let Some(max_borrow_value) = max_borrow_value {
+        whitelist_elem.max_borrow_value = Some(max_borrow_value).filter(|cap| !cap.is_zero());
+    }
+
+    let liquidation_config: LiquidationConfig = read_liquidation_config(deps.storage)?;
+    assert_liquidation_ltv(
+        whitelist_elem.max_ltv,
+        whitelist_elem.liquidation_ltv,
+        liquidation_config.hard_liquidation_ltv,
+    )?;
+    if let Some(max_ltv_ramp) = &whitelist_elem.max_ltv_ramp {
+        assert_liquidation_ltv(
+            max_ltv_ramp.start_ltv,
+            whitelist_elem.liquidation_ltv,
+            liquidation_config.hard_liquidation_ltv,
+        )?;
+    }
+
     store_whitelist_elem(deps.storage, &collateral_token_raw, &whitelist_elem)?;
 
     Ok(Response::new().add_attributes(vec![
@@ -357,77 +725,128 @@ pub fn update_whitelist(
             deps.api.addr_humanize(&whitelist_elem.custody_contract)?,
         ),
         attr("LTV", whitelist_elem.max_ltv.to_string()),
+        attr(
+            "liquidation_LTV",
+            whitelist_elem.liquidation_ltv.to_string(),
+        ),
+    ]))
+}
+
+pub fn update_whitelist_status(
+    deps: DepsMut,
+    info: MessageInfo,
+    collateral_token: Addr,
+    status: WhitelistStatus,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let collateral_token_raw = deps.api.addr_canonicalize(collateral_token.as_str())?;
+    let mut whitelist_elem: WhitelistElem =
+        read_whitelist_elem(deps.storage, &collateral_token_raw)?;
+    whitelist_elem.status = status;
+    store_whitelist_elem(deps.storage, &collateral_token_raw, &whitelist_elem)?;
+
+    let status = match whitelist_elem.status {
+        WhitelistStatus::Active => "active".to_string(),
+        WhitelistStatus::Paused => "paused".to_string(),
+        WhitelistStatus::Deprecated { end_time, .. } => {
+            format!("deprecated_until_{}", end_time)
+        }
+    };
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "update_whitelist_status"),
+        attr("collateral_token", collateral_token),
+        attr("status", status),
+    ]))
+}
+
+pub fn delist(
+    deps: DepsMut,
+    info: MessageInfo,
+    collateral_token: Addr,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let collateral_token_raw = deps.api.addr_canonicalize(collateral_token.as_str())?;
+    read_whitelist_elem(deps.storage, &collateral_token_raw)?;
+    if !read_total_collateral(deps.storage, &collateral_token_raw).is_zero() {
+        return Err(ContractError::CannotDelistLockedCollateral {});
+    }
+
+    remove_whitelist_elem(deps.storage, &collateral_token_raw);
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "delist"),
+        attr("collateral_token", collateral_token),
     ]))
 }
 
-fn update_deposit_rate(deps: DepsMut, env: Env) -> StdResult<()> {
+fn assert_liquidation_ltv(
+    max_ltv: Decimal256,
+    liquidation_ltv: Decimal256,
+    hard_liquidation_ltv: Decimal256,
+) -> Result<(), ContractError> {
+    if liquidation_ltv < max_ltv || liquidation_ltv > hard_liquidation_ltv {
+        return Err(ContractError::InvalidLiquidationLtv {});
+    }
+
+    Ok(())
+}
+
+fn assert_hard_liquidation_ltv(
+    storage: &dyn Storage,
+    hard_liquidation_ltv: Decimal256,
+) -> Result<(), ContractError> {
+    if hard_liquidation_ltv < read_max_liquidation_ltv(storage)?
+        || hard_liquidation_ltv > Decimal256::one()
+    {
+        return Err(ContractError::InvalidHardLiquidationLtv {});
+    }
+
+    Ok(())
+}
+
+fn update_deposit_rate(deps: DepsMut, env: Env) -> StdResult<Option<DynrateDecision>> {
     let dynrate_config: DynrateConfig = read_dynrate_config(deps.storage)?;
     let dynrate_state: DynrateState = read_dynrate_state(deps.storage)?;
     let mut config: Config = read_config(deps.storage)?;
 
     // check whether its time to re-evaluate rate
-    if env.block.height >= dynrate_state.last_executed_height + dynrate_config.dyn_rate_epoch {
+    if env.block.time.seconds() >= dynrate_state.last_executed_time + dynrate_config.dyn_rate_epoch
+    {
         // retrieve interest buffer
         let interest_buffer = query_balance(
             deps.as_ref(),
             env.contract.address.clone(),
             config.stable_denom.to_string(),
         )?;
-        // convert block rate into yearly rate
-        let blocks_per_year = Decimal256::from_ratio(Uint256::from(BLOCKS_PER_YEAR), 1);
-        let current_rate = config.threshold_deposit_rate * blocks_per_year;
-
         let yield_reserve = Decimal256::from_uint256(interest_buffer);
-        let mut yr_went_up = yield_reserve > dynrate_state.prev_yield_reserve;
-
-        // amount yield reserve changed in notional terms
-        let yield_reserve_delta = if yr_went_up {
-            yield_reserve - dynrate_state.prev_yield_reserve
-        } else {
-            dynrate_state.prev_yield_reserve - yield_reserve
-        };
 
-        // amount yield reserve changed in percentage terms
-        // if the prev yield reserve was zero; assume either a 100% decrease
-        // or a 100% increase, but this should be very rare
-        let mut yield_reserve_change = if dynrate_state.prev_yield_reserve.is_zero() {
-            Decimal256::one()
-        } else {
-            yield_reserve_delta / dynrate_state.prev_yield_reserve
-        };
-
-        // decreases the yield reserve change by dyn_rate_yr_increase_expectation
-        // (assume (yr_went_up, yield_reserve_change) is one signed integer, this just subtracts
-        // that integer by dynrate_config.dyn_rate_yr_increase_expectation)
-        let increase_expectation = dynrate_config.dyn_rate_yr_increase_expectation;
-        yield_reserve_change = if !yr_went_up {
-            yield_reserve_change + increase_expectation
-        } else if yield_reserve_change > increase_expectation {
-            yield_reserve_change - increase_expectation
-        } else {
-            yr_went_up = !yr_went_up;
-            increase_expectation - yield_reserve_change
-        };
-
-        yield_reserve_change = min(yield_reserve_change, dynrate_config.dyn_rate_maxchange);
-
-        let mut new_rate = if yr_went_up {
-            current_rate + yield_reserve_change
-        } else if current_rate > yield_reserve_change {
-            current_rate - yield_reserve_change
-        } else {
-            Decimal256::zero()
-        };
-
-        // convert from yearly rate to block rate
-        new_rate = new_rate / blocks_per_year;
+        // compute new rate with the deposit rate model
+        let target_deposit_rate = query_target_deposit_rate(
+            deps.as_ref(),
+            deps.api.addr_humanize(&dynrate_config.deposit_rate_model)?,
+            yield_reserve,
+            dynrate_state.prev_yield_reserve,
+            config.threshold_deposit_rate,
+            env.block.time.seconds() - dynrate_state.last_executed_time,
+            dynrate_state.model_state,
+        )?;
 
         // clamp new rate
-        new_rate = max(
-            min(new_rate, dynrate_config.dyn_rate_max),
+        let new_rate = max(
+            min(target_deposit_rate.rate, dynrate_config.dyn_rate_max),
             dynrate_config.dyn_rate_min,
         );
 
+        let prev_deposit_rate = config.threshold_deposit_rate;
         config.target_deposit_rate = new_rate;
         config.threshold_deposit_rate = new_rate;
         store_config(deps.storage, &config)?;
@@ -436,40 +855,52 @@ fn update_deposit_rate(deps: DepsMut, env: Env) -> StdResult<()> {
         store_dynrate_state(
             deps.storage,
             &DynrateState {
-                last_executed_height: env.block.height,
+                last_executed_time: env.block.time.seconds(),
                 prev_yield_reserve: yield_reserve,
+                model_state: target_deposit_rate.model_state,
             },
         )?;
+
+        return Ok(Some(DynrateDecision {
+            yield_reserve,
+            prev_deposit_rate,
+            threshold_deposit_rate: config.threshold_deposit_rate,
+            target_deposit_rate: config.target_deposit_rate,
+        }));
     };
-    Ok(())
+    Ok(None)
 }
 
 pub fn execute_epoch_operations(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    if read_pending_epoch_operation(deps.storage)?.is_some() {
+        return Err(ContractError::EpochOperationInProgress {});
+    }
+
     let config: Config = read_config(deps.storage)?;
     let state: EpochState = read_epoch_state(deps.storage)?;
-    if env.block.height < state.last_executed_height + config.epoch_period {
-        return Err(ContractError::EpochNotPassed(state.last_executed_height));
+    if env.block.time.seconds() < state.last_executed_time + config.epoch_period {
+        return Err(ContractError::EpochNotPassed(state.last_executed_time));
     }
 
-    // # of blocks from the last executed height
-    let blocks = Uint256::from(env.block.height - state.last_executed_height);
+    // # of seconds from the last executed time
+    let seconds = Uint256::from(env.block.time.seconds() - state.last_executed_time);
 
     // Compute next epoch state
     let market_contract = deps.api.addr_humanize(&config.market_contract)?;
     let epoch_state: EpochStateResponse = query_epoch_state(
         deps.as_ref(),
         market_contract.clone(),
-        env.block.height,
+        env.block.time.seconds(),
         None,
     )?;
 
     // effective_deposit_rate = cur_exchange_rate / prev_exchange_rate
-    // deposit_rate = (effective_deposit_rate - 1) / blocks
+    // deposit_rate = (effective_deposit_rate - 1) / seconds
     let effective_deposit_rate = epoch_state.exchange_rate / state.prev_exchange_rate;
     let deposit_rate =
-        (effective_deposit_rate - Decimal256::one()) / Decimal256::from_uint256(blocks);
+        (effective_deposit_rate - Decimal256::one()) / Decimal256::from_uint256(seconds);
 
-    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut messages: Vec<SubMsg> = vec![];
     let mut interest_buffer = query_balance(
         deps.as_ref(),
         env.contract.address.clone(),
@@ -480,7 +911,7 @@ pub fn execute_epoch_operations(deps: DepsMut, env: Env) -> Result<Response, Con
     let accrued_buffer = interest_buffer - state.prev_interest_buffer;
     let anc_purchase_amount = accrued_buffer * config.anc_purchase_factor;
     if !anc_purchase_amount.is_zero() {
-        messages.push(CosmosMsg::Bank(BankMsg::Send {
+        messages.push(SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
             to_address: deps
                 .api
                 .addr_humanize(&config.collector_contract)?
@@ -492,7 +923,7 @@ pub fn execute_epoch_operations(deps: DepsMut, env: Env) -> Result<Response, Con
                     amount: anc_purchase_amount.into(),
                 },
             )?],
-        }));
+        })));
     }
 
     // Deduct anc_purchase_amount from the interest_buffer
@@ -502,12 +933,12 @@ pub fn execute_epoch_operations(deps: DepsMut, env: Env) -> Result<Response, Con
     // Only executed when deposit rate < threshold_deposit_rate
     let mut distributed_interest: Uint256 = Uint256::zero();
     if deposit_rate < config.threshold_deposit_rate {
-        // missing_deposit_rate(_per_block)
+        // missing_deposit_rate(_per_second)
         let missing_deposit_rate = config.threshold_deposit_rate - deposit_rate;
         let prev_deposits = state.prev_aterra_supply * state.prev_exchange_rate;
 
-        // missing_deposits = prev_deposits * missing_deposit_rate(_per_block) * blocks
-        let missing_deposits = prev_deposits * blocks * missing_deposit_rate;
+        // missing_deposits = prev_deposits * missing_deposit_rate(_per_second) * seconds
+        let missing_deposits = prev_deposits * seconds * missing_deposit_rate;
         let distribution_buffer = interest_buffer * config.buffer_distribution_factor;
 
         // When there was not enough deposits happens,
@@ -529,86 +960,124 @@ pub fn execute_epoch_operations(deps: DepsMut, env: Env) -> Result<Response, Con
             );
 
             // Send some portion of interest buffer to Market contract
-            messages.push(CosmosMsg::Bank(BankMsg::Send {
+            messages.push(SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
                 to_address: market_contract.to_string(),
                 amount: vec![Coin {
                     denom: config.stable_denom,
                     amount: distributed_interest.into(),
                 }],
-            }));
+            })));
         }
     }
 
+    let mut reserve_state: ReserveState = read_reserve_state(deps.storage);
+    reserve_state.total_accrued += accrued_buffer;
+    reserve_state.total_anc_purchased += anc_purchase_amount;
+    reserve_state.total_distributed += distributed_interest;
+    store_reserve_state(deps.storage, &reserve_state)?;
+
     // Execute DistributeRewards
     let whitelist: Vec<WhitelistResponseElem> = read_whitelist(deps.as_ref(), None, None)?;
     for elem in whitelist.iter() {
-        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        messages.push(SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
             contract_addr: elem.custody_contract.clone(),
             funds: vec![],
             msg: to_binary(&CustodyExecuteMsg::DistributeRewards {})?,
-        }));
+        })));
     }
 
-    // TODO: Should this become a reply? If so which SubMsg to make reply_on?
-    // Execute store epoch state operation
-    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: env.contract.address.to_string(),
-        funds: vec![],
-        msg: to_binary(&ExecuteMsg::UpdateEpochState {
-            interest_buffer,
-            distributed_interest,
-        })?,
-    }));
-
-    Ok(Response::new().add_messages(messages).add_attributes(vec![
+    let attributes = vec![
         attr("action", "epoch_operations"),
         attr("deposit_rate", deposit_rate.to_string()),
         attr("exchange_rate", epoch_state.exchange_rate.to_string()),
         attr("aterra_supply", epoch_state.aterra_supply),
         attr("distributed_interest", distributed_interest),
         attr("anc_purchase_amount", anc_purchase_amount),
-    ]))
+    ];
+
+    // Nothing to wait for, update the epoch state right away
+    let last = match messages.last_mut() {
+        Some(last) => last,
+        None => {
+            return Ok(
+                apply_epoch_state(deps, env, interest_buffer, distributed_interest)?
+                    .add_attributes(attributes),
+            )
+        }
+    };
+
+    // Update the epoch state in the reply of the last message, once all
+    // rewards are distributed. The pending operation blocks re-entrance
+    // until then and is reverted together with the messages on failure.
+    last.id = EPOCH_OPERATIONS_REPLY_ID;
+    last.reply_on = ReplyOn::Success;
+    store_pending_epoch_operation(
+        deps.storage,
+        &PendingEpochOperation {
+            interest_buffer,
+            distributed_interest,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attributes(attributes))
 }
 
-pub fn update_epoch_state(
-    deps: DepsMut,
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.id {
+        EPOCH_OPERATIONS_REPLY_ID => {
+            let pending = match read_pending_epoch_operation(deps.storage)? {
+                Some(pending) => pending,
+                None => return Err(ContractError::InvalidReplyId {}),
+            };
+            remove_pending_epoch_operation(deps.storage);
+
+            apply_epoch_state(
+                deps,
+                env,
+                pending.interest_buffer,
+                pending.distributed_interest,
+            )
+        }
+        _ => Err(ContractError::InvalidReplyId {}),
+    }
+}
+
+fn apply_epoch_state(
+    mut deps: DepsMut,
     env: Env,
-    info: MessageInfo,
-    // To store interest buffer before receiving epoch staking rewards,
-    // pass interest_buffer from execute_epoch_operations
     interest_buffer: Uint256,
     distributed_interest: Uint256,
 ) -> Result<Response, ContractError> {
     let config: Config = read_config(deps.storage)?;
     let overseer_epoch_state: EpochState = read_epoch_state(deps.storage)?;
-    if info.sender != env.contract.address {
-        return Err(ContractError::Unauthorized {});
-    }
 
-    // # of blocks from the last executed height
-    let blocks = Uint256::from(env.block.height - overseer_epoch_state.last_executed_height);
+    // # of seconds from the last executed time
+    let seconds = Uint256::from(env.block.time.seconds() - overseer_epoch_state.last_executed_time);
 
     // Compute next epoch state
     let market_contract = deps.api.addr_humanize(&config.market_contract)?;
     let market_epoch_state: EpochStateResponse = query_epoch_state(
         deps.as_ref(),
         market_contract.clone(),
-        env.block.height,
+        env.block.time.seconds(),
         Some(distributed_interest),
     )?;
 
     // effective_deposit_rate = cur_exchange_rate / prev_exchange_rate
-    // deposit_rate = (effective_deposit_rate - 1) / blocks
+    // deposit_rate = (effective_deposit_rate - 1) / seconds
     let effective_deposit_rate =
         market_epoch_state.exchange_rate / overseer_epoch_state.prev_exchange_rate;
     let deposit_rate =
-        (effective_deposit_rate - Decimal256::one()) / Decimal256::from_uint256(blocks);
+        (effective_deposit_rate - Decimal256::one()) / Decimal256::from_uint256(seconds);
 
     // store updated epoch state
     store_epoch_state(
         deps.storage,
         &EpochState {
-            last_executed_height: env.block.height,
+            last_executed_time: env.block.time.seconds(),
             prev_aterra_supply: market_epoch_state.aterra_supply,
             prev_exchange_rate: market_epoch_state.exchange_rate,
             prev_interest_buffer: interest_buffer,
@@ -625,7 +1094,23 @@ pub fn update_epoch_state(
     })?;
 
     // proceed with deposit rate update
-    update_deposit_rate(deps, env)?;
+    let dynrate = update_deposit_rate(deps.branch(), env.clone())?;
+
+    // record the epoch for the history query
+    store_epoch_snapshot(
+        deps.storage,
+        &EpochSnapshot {
+            epoch: read_epoch_history_count(deps.storage),
+            height: env.block.height,
+            time: env.block.time.seconds(),
+            deposit_rate,
+            exchange_rate: market_epoch_state.exchange_rate,
+            aterra_supply: market_epoch_state.aterra_supply,
+            interest_buffer,
+            distributed_interest,
+            dynrate,
+        },
+    )?;
 
     Ok(Response::new()
         .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
@@ -646,29 +1131,106 @@ pub fn update_epoch_state(
 }
 
 pub fn fund_reserve(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
-    let sent_uusd = match info.funds.iter().find(|x| x.denom == "uusd") {
-        Some(coin) => coin.amount,
-        None => Uint128::zero(),
-    };
+    let config: Config = read_config(deps.storage)?;
+    if let Some(coin) = info.funds.iter().find(|x| x.denom != config.stable_denom) {
+        return Err(ContractError::InvalidReserveDenom(coin.denom.clone()));
+    }
+
+    let funded_amount: Uint256 = info
+        .funds
+        .iter()
+        .map(|x| Uint256::from(x.amount))
+        .fold(Uint256::zero(), |acc, x| acc + x);
 
     let mut overseer_epoch_state: EpochState = read_epoch_state(deps.storage)?;
-    overseer_epoch_state.prev_interest_buffer += Uint256::from(sent_uusd);
+    overseer_epoch_state.prev_interest_buffer += funded_amount;
     store_epoch_state(deps.storage, &overseer_epoch_state)?;
 
     let mut dyn_rate_state: DynrateState = read_dynrate_state(deps.storage)?;
-    dyn_rate_state.prev_yield_reserve += Decimal256::from_ratio(Uint256::from(sent_uusd), 1);
+    dyn_rate_state.prev_yield_reserve += Decimal256::from_uint256(funded_amount);
     store_dynrate_state(deps.storage, &dyn_rate_state)?;
 
+    let mut reserve_state: ReserveState = read_reserve_state(deps.storage);
+    reserve_state.total_funded += funded_amount;
+    store_reserve_state(deps.storage, &reserve_state)?;
+
     Ok(Response::new().add_attributes(vec![
         attr("action", "fund_reserve"),
-        attr("funded_amount", sent_uusd.to_string()),
+        attr("funded_amount", funded_amount),
+    ]))
+}
+
+pub fn withdraw_reserve(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Uint256,
+    recipient: Addr,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let balance: Uint256 = query_balance(
+        deps.as_ref(),
+        env.contract.address,
+        config.stable_denom.to_string(),
+    )?;
+    if amount > balance {
+        return Err(ContractError::WithdrawExceedsReserve(balance.into()));
+    }
+
+    // Lower the baselines so the withdrawal is neither seen as
+    // negative accrual nor as a yield reserve drop in the next epoch
+    let mut epoch_state: EpochState = read_epoch_state(deps.storage)?;
+    epoch_state.prev_interest_buffer = if epoch_state.prev_interest_buffer > amount {
+        epoch_state.prev_interest_buffer - amount
+    } else {
+        Uint256::zero()
+    };
+    store_epoch_state(deps.storage, &epoch_state)?;
+
+    let mut dyn_rate_state: DynrateState = read_dynrate_state(deps.storage)?;
+    let withdraw_value = Decimal256::from_uint256(amount);
+    dyn_rate_state.prev_yield_reserve = if dyn_rate_state.prev_yield_reserve > withdraw_value {
+        dyn_rate_state.prev_yield_reserve - withdraw_value
+    } else {
+        Decimal256::zero()
+    };
+    store_dynrate_state(deps.storage, &dyn_rate_state)?;
+
+    let mut reserve_state: ReserveState = read_reserve_state(deps.storage);
+    reserve_state.total_withdrawn += amount;
+    store_reserve_state(deps.storage, &reserve_state)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if !amount.is_zero() {
+        messages.push(CosmosMsg::Bank(BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: vec![deduct_tax(
+                deps.as_ref(),
+                Coin {
+                    denom: config.stable_denom,
+                    amount: amount.into(),
+                },
+            )?],
+        }));
+    }
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "withdraw_reserve"),
+        attr("recipient", recipient),
+        attr("amount", amount),
     ]))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::OwnershipProposal {} => to_binary(&query_ownership_proposal(deps)?),
+        QueryMsg::PauseInfo {} => to_binary(&query_pause_info(deps)?),
         QueryMsg::EpochState {} => to_binary(&query_state(deps)?),
         QueryMsg::Whitelist {
             collateral_token,
@@ -680,6 +1242,17 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
             optional_addr_validate(deps.api, start_after)?,
             limit,
         )?),
+        QueryMsg::CollateralUsage {
+            collateral_token,
+            start_after,
+            limit,
+        } => to_binary(&query_collateral_usage(
+            deps,
+            env.block.time.seconds(),
+            optional_addr_validate(deps.api, collateral_token)?,
+            optional_addr_validate(deps.api, start_after)?,
+            limit,
+        )?),
         QueryMsg::Collaterals { borrower } => to_binary(&query_collaterals(
             deps,
             deps.api.addr_validate(&borrower)?,
@@ -695,15 +1268,66 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         } => to_binary(&query_borrow_limit(
             deps,
             deps.api.addr_validate(&borrower)?,
+            block_time.unwrap_or_else(|| env.block.time.seconds()),
+            block_time,
+        )?),
+        QueryMsg::BorrowerHealth {
+            borrower,
+            block_time,
+        } => to_binary(&query_borrower_health(
+            deps,
+            env,
+            deps.api.addr_validate(&borrower)?,
             block_time,
         )?),
+        QueryMsg::MarginCall { borrower } => to_binary(&query_margin_call(
+            deps,
+            deps.api.addr_validate(&borrower)?,
+        )?),
+        QueryMsg::LiquidatableBorrowers { start_after, limit } => {
+            to_binary(&query_liquidatable_borrowers(
+                deps,
+                env,
+                optional_addr_validate(deps.api, start_after)?,
+                limit,
+            )?)
+        }
         QueryMsg::DynrateState {} => to_binary(&query_dynrate_state(deps)?),
+        QueryMsg::WriteOffState {} => to_binary(&query_write_off_state(deps)?),
+        QueryMsg::WriteOffs { start_after, limit } => to_binary(&query_write_offs(
+            deps,
+            optional_addr_validate(deps.api, start_after)?,
+            limit,
+        )?),
+        QueryMsg::BadDebt { borrower } => {
+            to_binary(&query_bad_debt(deps, deps.api.addr_validate(&borrower)?)?)
+        }
+        QueryMsg::AllBadDebts { start_after, limit } => to_binary(&query_all_bad_debts(
+            deps,
+            optional_addr_validate(deps.api, start_after)?,
+            limit,
+        )?),
+        QueryMsg::TotalBadDebt {} => to_binary(&query_total_bad_debt(deps)?),
+        QueryMsg::EpochHistory { start_after, limit } => {
+            to_binary(&query_epoch_history(deps, start_after, limit)?)
+        }
+        QueryMsg::Reserve {} => to_binary(&query_reserve(deps, env)?),
+        QueryMsg::OperatorApproval { owner, operator } => {
+            to_binary(&query_operator_approval(deps, owner, operator)?)
+        }
+        QueryMsg::OperatorApprovals {
+            owner,
+            start_after,
+            limit,
+        } => to_binary(&query_operator_approvals(deps, owner, start_after, limit)?),
     }
 }
 
 pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
     let config: Config = read_config(deps.storage)?;
     let dynrate_config: DynrateConfig = read_dynrate_config(deps.storage)?;
+    let write_off_config: WriteOffConfig = read_write_off_config(deps.storage)?;
+    let liquidation_config: LiquidationConfig = read_liquidation_config(deps.storage)?;
     Ok(ConfigResponse {
         owner_addr: deps.api.addr_humanize(&config.owner_addr)?.to_string(),
         oracle_contract: deps.api.addr_humanize(&config.oracle_contract)?.to_string(),
@@ -724,10 +1348,21 @@ pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
         anc_purchase_factor: config.anc_purchase_factor,
         price_timeframe: config.price_timeframe,
         dyn_rate_epoch: dynrate_config.dyn_rate_epoch,
-        dyn_rate_maxchange: dynrate_config.dyn_rate_maxchange,
-        dyn_rate_yr_increase_expectation: dynrate_config.dyn_rate_yr_increase_expectation,
+        deposit_rate_model: deps
+            .api
+            .addr_humanize(&dynrate_config.deposit_rate_model)?
+            .to_string(),
         dyn_rate_min: dynrate_config.dyn_rate_min,
         dyn_rate_max: dynrate_config.dyn_rate_max,
+        bad_debt_handler: match write_off_config.bad_debt_handler {
+            Some(bad_debt_handler) => Some(deps.api.addr_humanize(&bad_debt_handler)?.to_string()),
+            None => None,
+        },
+        max_write_off_per_epoch: write_off_config.max_write_off_per_epoch,
+        close_factor: liquidation_config.close_factor,
+        liquidation_interval: liquidation_config.liquidation_interval,
+        margin_call_grace_period: liquidation_config.margin_call_grace_period,
+        hard_liquidation_ltv: liquidation_config.hard_liquidation_ltv,
     })
 }
 
@@ -735,10 +1370,40 @@ pub fn query_state(deps: Deps) -> StdResult<EpochState> {
     read_epoch_state(deps.storage)
 }
 
+pub fn query_epoch_history(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<EpochHistoryResponse> {
+    Ok(EpochHistoryResponse {
+        epochs: read_epoch_history(deps.storage, start_after, limit)?,
+    })
+}
+
 pub fn query_dynrate_state(deps: Deps) -> StdResult<DynrateState> {
     read_dynrate_state(deps.storage)
 }
 
+pub fn query_write_off_state(deps: Deps) -> StdResult<WriteOffState> {
+    read_write_off_state(deps.storage)
+}
+
+pub fn query_reserve(deps: Deps, env: Env) -> StdResult<ReserveResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let reserve_state: ReserveState = read_reserve_state(deps.storage);
+    let write_off_state: WriteOffState = read_write_off_state(deps.storage)?;
+
+    Ok(ReserveResponse {
+        balance: query_balance(deps, env.contract.address, config.stable_denom)?,
+        total_funded: reserve_state.total_funded,
+        total_accrued: reserve_state.total_accrued,
+        total_distributed: reserve_state.total_distributed,
+        total_anc_purchased: reserve_state.total_anc_purchased,
+        total_written_off: write_off_state.total_written_off,
+        total_withdrawn: reserve_state.total_withdrawn,
+    })
+}
+
 pub fn query_whitelist(
     deps: Deps,
     collateral_token: Option<Addr>,
@@ -755,6 +1420,11 @@ pub fn query_whitelist(
                 name: whitelist_elem.name,
                 symbol: whitelist_elem.symbol,
                 max_ltv: whitelist_elem.max_ltv,
+                liquidation_ltv: whitelist_elem.liquidation_ltv,
+                max_collateral_amount: whitelist_elem.max_collateral_amount,
+                max_borrow_value: whitelist_elem.max_borrow_value,
+                status: whitelist_elem.status,
+                max_ltv_ramp: whitelist_elem.max_ltv_ramp,
                 custody_contract: deps
                     .api
                     .addr_humanize(&whitelist_elem.custody_contract)?
@@ -15,7 +15,7 @@ pub enum ContractError {
     #[error("Cannot liquidate safely collateralized loan")]
     CannotLiquidateSafeLoan {},
 
-    #[error("An epoch has not passed yet; last executed height: {0}")]
+    #[error("An epoch has not passed yet; last executed time: {0}")]
     EpochNotPassed(u64),
 
     #[error("Token is already registered as collateral")]
@@ -27,6 +27,63 @@ pub enum ContractError {
     #[error("Unlock amount too high; Loan liability becomes greater than borrow limit: {0}")]
     UnlockTooLarge(u128),
 
+    #[error("Lock amount must be greater than 0")]
+    ZeroLockAmount {},
+
+    #[error("Cannot write off a loan which is still covered by its collaterals")]
+    CannotWriteOffSolventLoan {},
+
+    #[error("Borrower has no loan to write off")]
+    NoLoanToWriteOff {},
+
+    #[error("Write-off cap of the current epoch reached; next epoch starts at: {0}")]
+    WriteOffCapReached(u64),
+
+    #[error("Liquidation LTV must be between max LTV and the hard liquidation LTV")]
+    InvalidLiquidationLtv {},
+
+    #[error("Hard liquidation LTV must be between the highest liquidation LTV and 1")]
+    InvalidHardLiquidationLtv {},
+
+    #[error("Close factor must be greater than 0 and not exceed 1")]
+    InvalidCloseFactor {},
+
+    #[error("Borrower was liquidated recently; next liquidation allowed at height: {0}")]
+    LiquidationTooSoon(u64),
+
+    #[error("Borrower must be flagged before it can be liquidated")]
+    BorrowerNotFlagged {},
+
+    #[error("Cannot flag safely collateralized loan")]
+    CannotFlagSafeLoan {},
+
+    #[error("Borrower is already flagged")]
+    BorrowerAlreadyFlagged {},
+
+    #[error("Grace period of the borrower has not passed yet; ends at: {0}")]
+    GracePeriodNotPassed(u64),
+
+    #[error("Lock amount exceeds the collateral cap; max collateral amount: {0}")]
+    MaxCollateralAmountExceeded(u128),
+
+    #[error("Collateral is paused")]
+    CollateralPaused {},
+
+    #[error("Cannot delist a collateral which is still locked by borrowers")]
+    CannotDelistLockedCollateral {},
+
+    #[error("Epoch operations are already in progress")]
+    EpochOperationInProgress {},
+
+    #[error("Invalid reply ID")]
+    InvalidReplyId {},
+
+    #[error("Reserve can only be funded with {0}")]
+    InvalidReserveDenom(String),
+
+    #[error("Withdraw amount exceeds the reserve balance: {0}")]
+    WithdrawExceedsReserve(u128),
+
     #[error("Functionality deprecated")]
     Deprecated {},
 }
@@ -1,6 +1,9 @@
 use cosmwasm_bignumber::{Decimal256, Uint256};
-use cosmwasm_std::{to_binary, Addr, Deps, QueryRequest, StdResult, WasmQuery};
+use cosmwasm_std::{to_binary, Addr, Binary, Deps, QueryRequest, StdResult, WasmQuery};
 
+use moneymarket::deposit_rate_model::{
+    QueryMsg as DepositRateModelQueryMsg, TargetDepositRateResponse,
+};
 use moneymarket::liquidation::{LiquidationAmountResponse, QueryMsg as LiquidationQueryMsg};
 use moneymarket::market::{
     BorrowerInfoResponse, EpochStateResponse, QueryMsg as MarketQueryMsg, StateResponse,
@@ -10,12 +13,12 @@ use moneymarket::tokens::TokensHuman;
 pub fn query_market_state(
     deps: Deps,
     market_addr: Addr,
-    block_height: u64,
+    block_time: u64,
 ) -> StdResult<StateResponse> {
     let epoch_state: StateResponse = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
         contract_addr: market_addr.to_string(),
         msg: to_binary(&MarketQueryMsg::State {
-            block_height: Some(block_height),
+            block_time: Some(block_time),
         })?,
     }))?;
 
@@ -25,14 +28,14 @@ pub fn query_market_state(
 pub fn query_epoch_state(
     deps: Deps,
     market_addr: Addr,
-    block_height: u64,
+    block_time: u64,
     distributed_interest: Option<Uint256>,
 ) -> StdResult<EpochStateResponse> {
     let epoch_state: EpochStateResponse =
         deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
             contract_addr: market_addr.to_string(),
             msg: to_binary(&MarketQueryMsg::EpochState {
-                block_height: Some(block_height),
+                block_time: Some(block_time),
                 distributed_interest,
             })?,
         }))?;
@@ -45,14 +48,14 @@ pub fn query_borrower_info(
     deps: Deps,
     market_addr: Addr,
     borrower: Addr,
-    block_height: u64,
+    block_time: u64,
 ) -> StdResult<BorrowerInfoResponse> {
     let borrower_amount: BorrowerInfoResponse =
         deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
             contract_addr: market_addr.to_string(),
             msg: to_binary(&MarketQueryMsg::BorrowerInfo {
                 borrower: borrower.to_string(),
-                block_height: Some(block_height),
+                block_time: Some(block_time),
             })?,
         }))?;
 
@@ -81,3 +84,28 @@ pub fn query_liquidation_amount(
 
     Ok(liquidation_amount_res)
 }
+
+/// Query the new deposit rate from the deposit rate model contract
+pub fn query_target_deposit_rate(
+    deps: Deps,
+    deposit_rate_model: Addr,
+    yield_reserve: Decimal256,
+    prev_yield_reserve: Decimal256,
+    current_rate: Decimal256,
+    seconds: u64,
+    model_state: Option<Binary>,
+) -> StdResult<TargetDepositRateResponse> {
+    let target_deposit_rate: TargetDepositRateResponse =
+        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: deposit_rate_model.to_string(),
+            msg: to_binary(&DepositRateModelQueryMsg::TargetDepositRate {
+                yield_reserve,
+                prev_yield_reserve,
+                current_rate,
+                seconds,
+                model_state,
+            })?,
+        }))?;
+
+    Ok(target_deposit_rate)
+}
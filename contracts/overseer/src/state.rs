@@ -2,19 +2,40 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use cosmwasm_bignumber::{Decimal256, Uint256};
-use cosmwasm_std::{CanonicalAddr, Deps, Order, StdError, StdResult, Storage};
+use cosmwasm_std::{Binary, CanonicalAddr, Deps, Order, StdError, StdResult, Storage};
 use cosmwasm_storage::{Bucket, ReadonlyBucket, ReadonlySingleton, Singleton};
+use std::cmp::{max, min};
 
-use moneymarket::overseer::{CollateralsResponse, WhitelistResponseElem};
-use moneymarket::tokens::Tokens;
+use moneymarket::common::{legacy_blocks_to_seconds, legacy_rate_to_rate_per_second};
+use moneymarket::overseer::{
+    BadDebtResponse, CollateralsResponse, DynrateDecision, EpochHistoryResponseElem, MaxLtvRamp,
+    WhitelistResponseElem, WhitelistStatus, WriteOffResponse,
+};
+use moneymarket::tokens::{Tokens, TokensMath};
 
 const KEY_CONFIG: &[u8] = b"config";
 const KEY_DYNRATE_CONFIG: &[u8] = b"dynrate_config";
 const KEY_EPOCH_STATE: &[u8] = b"epoch_state";
 const KEY_DYNRATE_STATE: &[u8] = b"dynrate_state";
+const KEY_WRITE_OFF_CONFIG: &[u8] = b"write_off_config";
+const KEY_WRITE_OFF_STATE: &[u8] = b"write_off_state";
+const KEY_TOTAL_BAD_DEBT: &[u8] = b"total_bad_debt";
+const KEY_LIQUIDATION_CONFIG: &[u8] = b"liquidation_config";
+const KEY_PENDING_EPOCH_OPERATION: &[u8] = b"pending_epoch_operation";
+const KEY_EPOCH_HISTORY_COUNT: &[u8] = b"epoch_history_count";
+const KEY_RESERVE_STATE: &[u8] = b"reserve_state";
 
 const PREFIX_WHITELIST: &[u8] = b"whitelist";
 const PREFIX_COLLATERALS: &[u8] = b"collateral";
+const PREFIX_WRITE_OFF: &[u8] = b"write_off";
+const PREFIX_BAD_DEBT: &[u8] = b"bad_debt";
+const PREFIX_LAST_LIQUIDATION: &[u8] = b"last_liquidation";
+const PREFIX_MARGIN_CALL: &[u8] = b"margin_call";
+const PREFIX_TOTAL_COLLATERAL: &[u8] = b"total_collateral";
+const PREFIX_EPOCH_HISTORY: &[u8] = b"epoch_history";
+
+/// Number of epoch snapshots kept; older ones get overwritten
+pub const EPOCH_HISTORY_SIZE: u64 = 100;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
@@ -35,26 +56,94 @@ pub struct Config {
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct DynrateConfig {
     pub dyn_rate_epoch: u64,
-    pub dyn_rate_maxchange: Decimal256,
-    pub dyn_rate_yr_increase_expectation: Decimal256,
-    // clamps the deposit rate (in blocks)
+    pub deposit_rate_model: CanonicalAddr,
+    // clamps the deposit rate (per second)
     pub dyn_rate_min: Decimal256,
     pub dyn_rate_max: Decimal256,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LiquidationConfig {
+    pub close_factor: Decimal256,
+    pub liquidation_interval: u64,
+    pub margin_call_grace_period: u64,
+    pub hard_liquidation_ltv: Decimal256,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct EpochState {
     pub deposit_rate: Decimal256,
     pub prev_aterra_supply: Uint256,
     pub prev_exchange_rate: Decimal256,
     pub prev_interest_buffer: Uint256,
-    pub last_executed_height: u64,
+    pub last_executed_time: u64,
+}
+
+/// Interest buffer snapshot taken by execute_epoch_operations, applied to the
+/// epoch state once the reply of the last DistributeRewards call comes back
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingEpochOperation {
+    pub interest_buffer: Uint256,
+    pub distributed_interest: Uint256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EpochSnapshot {
+    pub epoch: u64,
+    pub height: u64,
+    pub time: u64,
+    pub deposit_rate: Decimal256,
+    pub exchange_rate: Decimal256,
+    pub aterra_supply: Uint256,
+    pub interest_buffer: Uint256,
+    pub distributed_interest: Uint256,
+    pub dynrate: Option<DynrateDecision>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct DynrateState {
-    pub last_executed_height: u64,
+    pub last_executed_time: u64,
     pub prev_yield_reserve: Decimal256,
+    /// Returned by the deposit rate model with the last target rate
+    pub model_state: Option<Binary>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WriteOffConfig {
+    pub bad_debt_handler: Option<CanonicalAddr>,
+    pub max_write_off_per_epoch: Uint256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WriteOffState {
+    pub epoch_start_time: u64,
+    // amount written off since epoch_start_time
+    pub epoch_written_off: Uint256,
+    pub total_written_off: Uint256,
+}
+
+/// Cumulative flows of the interest buffer
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReserveState {
+    pub total_funded: Uint256,
+    pub total_accrued: Uint256,
+    // sent to the market to cover the threshold deposit rate
+    pub total_distributed: Uint256,
+    // sent to the collector for ANC purchases
+    pub total_anc_purchased: Uint256,
+    pub total_withdrawn: Uint256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WriteOffInfo {
+    pub amount: Uint256,
+    pub last_written_off_height: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BadDebtInfo {
+    pub amount: Uint256,
+    pub recorded_height: u64,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -62,7 +151,121 @@ pub struct WhitelistElem {
     pub name: String,
     pub symbol: String,
     pub max_ltv: Decimal256,
+    pub liquidation_ltv: Decimal256,
     pub custody_contract: CanonicalAddr,
+    pub max_collateral_amount: Option<Uint256>,
+    pub max_borrow_value: Option<Uint256>,
+    pub status: WhitelistStatus,
+    pub max_ltv_ramp: Option<MaxLtvRamp>,
+}
+
+impl WhitelistElem {
+    /// Returns max_ltv, interpolated while a change is ramping
+    pub fn ramped_max_ltv(&self, block_time: u64) -> Decimal256 {
+        match &self.max_ltv_ramp {
+            Some(ramp) if block_time < ramp.end_time => {
+                if block_time <= ramp.start_time {
+                    return ramp.start_ltv;
+                }
+
+                let progress = Decimal256::from_ratio(
+                    block_time - ramp.start_time,
+                    ramp.end_time - ramp.start_time,
+                );
+                if self.max_ltv > ramp.start_ltv {
+                    ramp.start_ltv + (self.max_ltv - ramp.start_ltv) * progress
+                } else {
+                    ramp.start_ltv - (ramp.start_ltv - self.max_ltv) * progress
+                }
+            }
+            _ => self.max_ltv,
+        }
+    }
+
+    /// Returns the max_ltv backing loans at the given height; paused
+    /// collaterals keep theirs, only new locks and borrows are refused
+    pub fn effective_max_ltv(&self, block_time: u64) -> Decimal256 {
+        let max_ltv = self.ramped_max_ltv(block_time);
+        match self.status {
+            WhitelistStatus::Active | WhitelistStatus::Paused => max_ltv,
+            WhitelistStatus::Deprecated {
+                start_time,
+                end_time,
+            } => {
+                if block_time >= end_time {
+                    Decimal256::zero()
+                } else if block_time <= start_time {
+                    max_ltv
+                } else {
+                    max_ltv * Decimal256::from_ratio(end_time - block_time, end_time - start_time)
+                }
+            }
+        }
+    }
+}
+
+/// Whitelist elem as stored before liquidation_ltv was introduced
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyWhitelistElem {
+    pub name: String,
+    pub symbol: String,
+    pub max_ltv: Decimal256,
+    pub liquidation_ltv: Option<Decimal256>,
+    pub custody_contract: CanonicalAddr,
+    pub max_collateral_amount: Option<Uint256>,
+    pub max_borrow_value: Option<Uint256>,
+    pub status: Option<LegacyWhitelistStatus>,
+    pub max_ltv_ramp: Option<LegacyMaxLtvRamp>,
+}
+
+/// Max_ltv ramp as scheduled in block heights
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyMaxLtvRamp {
+    pub start_ltv: Decimal256,
+    pub start_height: u64,
+    pub end_height: u64,
+}
+
+/// Whitelist status as scheduled in block heights
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LegacyWhitelistStatus {
+    Active,
+    Paused,
+    Deprecated { start_height: u64, end_height: u64 },
+}
+
+/// Epoch state as stored by the block based accounting
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyEpochState {
+    pub deposit_rate: Decimal256,
+    pub prev_aterra_supply: Uint256,
+    pub prev_exchange_rate: Decimal256,
+    pub prev_interest_buffer: Uint256,
+    pub last_executed_height: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyDynrateState {
+    pub last_executed_height: u64,
+    pub prev_yield_reserve: Decimal256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyWriteOffState {
+    pub epoch_start_height: u64,
+    pub epoch_written_off: Uint256,
+    pub total_written_off: Uint256,
+}
+
+/// Dynrate config as stored before the deposit rate model was split out
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyDynrateConfig {
+    pub dyn_rate_epoch: u64,
+    pub dyn_rate_maxchange: Decimal256,
+    pub dyn_rate_yr_increase_expectation: Decimal256,
+    pub dyn_rate_min: Decimal256,
+    pub dyn_rate_max: Decimal256,
 }
 
 pub fn store_config(storage: &mut dyn Storage, data: &Config) -> StdResult<()> {
@@ -97,6 +300,135 @@ pub fn read_dynrate_state(storage: &dyn Storage) -> StdResult<DynrateState> {
     ReadonlySingleton::new(storage, KEY_DYNRATE_STATE).load()
 }
 
+pub fn store_write_off_config(storage: &mut dyn Storage, data: &WriteOffConfig) -> StdResult<()> {
+    Singleton::new(storage, KEY_WRITE_OFF_CONFIG).save(data)
+}
+
+pub fn read_write_off_config(storage: &dyn Storage) -> StdResult<WriteOffConfig> {
+    ReadonlySingleton::new(storage, KEY_WRITE_OFF_CONFIG).load()
+}
+
+pub fn store_write_off_state(storage: &mut dyn Storage, data: &WriteOffState) -> StdResult<()> {
+    Singleton::new(storage, KEY_WRITE_OFF_STATE).save(data)
+}
+
+pub fn read_write_off_state(storage: &dyn Storage) -> StdResult<WriteOffState> {
+    ReadonlySingleton::new(storage, KEY_WRITE_OFF_STATE).load()
+}
+
+pub fn store_reserve_state(storage: &mut dyn Storage, data: &ReserveState) -> StdResult<()> {
+    Singleton::new(storage, KEY_RESERVE_STATE).save(data)
+}
+
+pub fn read_reserve_state(storage: &dyn Storage) -> ReserveState {
+    match ReadonlySingleton::new(storage, KEY_RESERVE_STATE).load() {
+        Ok(v) => v,
+        _ => ReserveState {
+            total_funded: Uint256::zero(),
+            total_accrued: Uint256::zero(),
+            total_distributed: Uint256::zero(),
+            total_anc_purchased: Uint256::zero(),
+            total_withdrawn: Uint256::zero(),
+        },
+    }
+}
+
+pub fn store_liquidation_config(
+    storage: &mut dyn Storage,
+    data: &LiquidationConfig,
+) -> StdResult<()> {
+    Singleton::new(storage, KEY_LIQUIDATION_CONFIG).save(data)
+}
+
+pub fn read_liquidation_config(storage: &dyn Storage) -> StdResult<LiquidationConfig> {
+    ReadonlySingleton::new(storage, KEY_LIQUIDATION_CONFIG).load()
+}
+
+pub fn store_pending_epoch_operation(
+    storage: &mut dyn Storage,
+    data: &PendingEpochOperation,
+) -> StdResult<()> {
+    Singleton::new(storage, KEY_PENDING_EPOCH_OPERATION).save(data)
+}
+
+pub fn read_pending_epoch_operation(
+    storage: &dyn Storage,
+) -> StdResult<Option<PendingEpochOperation>> {
+    ReadonlySingleton::new(storage, KEY_PENDING_EPOCH_OPERATION).may_load()
+}
+
+pub fn remove_pending_epoch_operation(storage: &mut dyn Storage) {
+    Singleton::<PendingEpochOperation>::new(storage, KEY_PENDING_EPOCH_OPERATION).remove()
+}
+
+/// Stores the snapshot in the ring buffer slot of its epoch,
+/// overwriting the snapshot taken EPOCH_HISTORY_SIZE epochs earlier
+pub fn store_epoch_snapshot(storage: &mut dyn Storage, snapshot: &EpochSnapshot) -> StdResult<()> {
+    let mut history_bucket: Bucket<EpochSnapshot> = Bucket::new(storage, PREFIX_EPOCH_HISTORY);
+    history_bucket.save(
+        &(snapshot.epoch % EPOCH_HISTORY_SIZE).to_be_bytes(),
+        snapshot,
+    )?;
+
+    Singleton::new(storage, KEY_EPOCH_HISTORY_COUNT).save(&(snapshot.epoch + 1))
+}
+
+/// Number of epoch snapshots recorded so far, which is also the next epoch number
+pub fn read_epoch_history_count(storage: &dyn Storage) -> u64 {
+    match ReadonlySingleton::new(storage, KEY_EPOCH_HISTORY_COUNT).load() {
+        Ok(v) => v,
+        _ => 0,
+    }
+}
+
+pub fn read_epoch_history(
+    storage: &dyn Storage,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<EpochHistoryResponseElem>> {
+    let history_bucket: ReadonlyBucket<EpochSnapshot> =
+        ReadonlyBucket::new(storage, PREFIX_EPOCH_HISTORY);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as u64;
+    let count = read_epoch_history_count(storage);
+
+    // only the last EPOCH_HISTORY_SIZE epochs are still in the buffer
+    let oldest = count.saturating_sub(EPOCH_HISTORY_SIZE);
+    let start = match start_after {
+        Some(epoch) => max(oldest, epoch.saturating_add(1)),
+        None => oldest,
+    };
+    let end = min(count, start.saturating_add(limit));
+
+    (start..end)
+        .map(|epoch| {
+            let v = history_bucket.load(&(epoch % EPOCH_HISTORY_SIZE).to_be_bytes())?;
+            Ok(EpochHistoryResponseElem {
+                epoch: v.epoch,
+                height: v.height,
+                time: v.time,
+                deposit_rate: v.deposit_rate,
+                exchange_rate: v.exchange_rate,
+                aterra_supply: v.aterra_supply,
+                interest_buffer: v.interest_buffer,
+                distributed_interest: v.distributed_interest,
+                dynrate: v.dynrate,
+            })
+        })
+        .collect()
+}
+
+pub fn store_total_bad_debt(storage: &mut dyn Storage, data: &Uint256) -> StdResult<()> {
+    Singleton::new(storage, KEY_TOTAL_BAD_DEBT).save(data)
+}
+
+pub fn read_total_bad_debt(storage: &dyn Storage) -> Uint256 {
+    match ReadonlySingleton::new(storage, KEY_TOTAL_BAD_DEBT).load() {
+        Ok(v) => v,
+        _ => Uint256::zero(),
+    }
+}
+
 pub fn store_whitelist_elem(
     storage: &mut dyn Storage,
     collateral_token: &CanonicalAddr,
@@ -108,6 +440,11 @@ pub fn store_whitelist_elem(
     Ok(())
 }
 
+pub fn remove_whitelist_elem(storage: &mut dyn Storage, collateral_token: &CanonicalAddr) {
+    let mut whitelist_bucket: Bucket<WhitelistElem> = Bucket::new(storage, PREFIX_WHITELIST);
+    whitelist_bucket.remove(collateral_token.as_slice());
+}
+
 pub fn read_whitelist_elem(
     storage: &dyn Storage,
     collateral_token: &CanonicalAddr,
@@ -146,11 +483,209 @@ pub fn read_whitelist(
                 collateral_token,
                 custody_contract,
                 max_ltv: v.max_ltv,
+                liquidation_ltv: v.liquidation_ltv,
+                max_collateral_amount: v.max_collateral_amount,
+                max_borrow_value: v.max_borrow_value,
+                status: v.status,
+                max_ltv_ramp: v.max_ltv_ramp,
             })
         })
         .collect()
 }
 
+/// Highest liquidation LTV among the whitelisted collaterals
+pub fn read_max_liquidation_ltv(storage: &dyn Storage) -> StdResult<Decimal256> {
+    let whitelist_bucket: ReadonlyBucket<WhitelistElem> =
+        ReadonlyBucket::new(storage, PREFIX_WHITELIST);
+    whitelist_bucket
+        .range(None, None, Order::Ascending)
+        .try_fold(Decimal256::zero(), |max_liquidation_ltv, elem| {
+            let (_, v) = elem?;
+            Ok(if v.liquidation_ltv > max_liquidation_ltv {
+                v.liquidation_ltv
+            } else {
+                max_liquidation_ltv
+            })
+        })
+}
+
+/// Existing whitelist elems keep liquidating at their max_ltv and stay active;
+/// ramps scheduled in block heights move to the equivalent block times
+pub fn migrate_whitelist(
+    storage: &mut dyn Storage,
+    block_height: u64,
+    block_time: u64,
+) -> StdResult<()> {
+    let to_time = |height: u64| {
+        if height >= block_height {
+            block_time + legacy_blocks_to_seconds(height - block_height)
+        } else {
+            block_time.saturating_sub(legacy_blocks_to_seconds(block_height - height))
+        }
+    };
+
+    let legacy_bucket: ReadonlyBucket<LegacyWhitelistElem> =
+        ReadonlyBucket::new(storage, PREFIX_WHITELIST);
+    let legacy_elems: Vec<(Vec<u8>, LegacyWhitelistElem)> = legacy_bucket
+        .range(None, None, Order::Ascending)
+        .collect::<StdResult<Vec<(Vec<u8>, LegacyWhitelistElem)>>>()?;
+
+    let mut whitelist_bucket: Bucket<WhitelistElem> = Bucket::new(storage, PREFIX_WHITELIST);
+    for (k, v) in legacy_elems.into_iter() {
+        whitelist_bucket.save(
+            &k,
+            &WhitelistElem {
+                name: v.name,
+                symbol: v.symbol,
+                max_ltv: v.max_ltv,
+                liquidation_ltv: v.liquidation_ltv.unwrap_or(v.max_ltv),
+                custody_contract: v.custody_contract,
+                max_collateral_amount: v.max_collateral_amount,
+                max_borrow_value: v.max_borrow_value,
+                status: match v.status {
+                    None | Some(LegacyWhitelistStatus::Active) => WhitelistStatus::Active,
+                    Some(LegacyWhitelistStatus::Paused) => WhitelistStatus::Paused,
+                    Some(LegacyWhitelistStatus::Deprecated {
+                        start_height,
+                        end_height,
+                    }) => WhitelistStatus::Deprecated {
+                        start_time: to_time(start_height),
+                        end_time: to_time(end_height),
+                    },
+                },
+                max_ltv_ramp: v.max_ltv_ramp.map(|ramp| MaxLtvRamp {
+                    start_ltv: ramp.start_ltv,
+                    start_time: to_time(ramp.start_height),
+                    end_time: to_time(ramp.end_height),
+                }),
+            },
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Replaces the built-in dynamic rate parameters with the deposit rate model;
+/// dyn_rate_maxchange and dyn_rate_yr_increase_expectation move to the model
+pub fn migrate_dynrate_config(
+    storage: &mut dyn Storage,
+    deposit_rate_model: CanonicalAddr,
+) -> StdResult<()> {
+    let legacy_dynrate_config: LegacyDynrateConfig =
+        match ReadonlySingleton::new(storage, KEY_DYNRATE_CONFIG).load() {
+            Ok(v) => v,
+            Err(_) => return Ok(()),
+        };
+
+    store_dynrate_config(
+        storage,
+        &DynrateConfig {
+            dyn_rate_epoch: legacy_dynrate_config.dyn_rate_epoch,
+            deposit_rate_model,
+            dyn_rate_min: legacy_dynrate_config.dyn_rate_min,
+            dyn_rate_max: legacy_dynrate_config.dyn_rate_max,
+        },
+    )
+}
+
+/// Converts the per-block rates and periods into per-second ones;
+/// the running epochs restart at the given block time
+pub fn migrate_block_based_state(storage: &mut dyn Storage, block_time: u64) -> StdResult<()> {
+    // already migrated when the epoch state tracks block times
+    let legacy_epoch_state: LegacyEpochState =
+        match ReadonlySingleton::new(storage, KEY_EPOCH_STATE).load() {
+            Ok(v) => v,
+            Err(_) => return Ok(()),
+        };
+    let legacy_dynrate_state: LegacyDynrateState =
+        ReadonlySingleton::new(storage, KEY_DYNRATE_STATE).load()?;
+    let legacy_write_off_state: Option<LegacyWriteOffState> =
+        ReadonlySingleton::new(storage, KEY_WRITE_OFF_STATE).may_load()?;
+
+    let mut config = read_config(storage)?;
+    config.epoch_period = legacy_blocks_to_seconds(config.epoch_period);
+    config.threshold_deposit_rate = legacy_rate_to_rate_per_second(config.threshold_deposit_rate);
+    config.target_deposit_rate = legacy_rate_to_rate_per_second(config.target_deposit_rate);
+    store_config(storage, &config)?;
+
+    let mut dynrate_config = read_dynrate_config(storage)?;
+    dynrate_config.dyn_rate_epoch = legacy_blocks_to_seconds(dynrate_config.dyn_rate_epoch);
+    dynrate_config.dyn_rate_min = legacy_rate_to_rate_per_second(dynrate_config.dyn_rate_min);
+    dynrate_config.dyn_rate_max = legacy_rate_to_rate_per_second(dynrate_config.dyn_rate_max);
+    store_dynrate_config(storage, &dynrate_config)?;
+
+    store_epoch_state(
+        storage,
+        &EpochState {
+            deposit_rate: legacy_rate_to_rate_per_second(legacy_epoch_state.deposit_rate),
+            prev_aterra_supply: legacy_epoch_state.prev_aterra_supply,
+            prev_exchange_rate: legacy_epoch_state.prev_exchange_rate,
+            prev_interest_buffer: legacy_epoch_state.prev_interest_buffer,
+            last_executed_time: block_time,
+        },
+    )?;
+
+    store_dynrate_state(
+        storage,
+        &DynrateState {
+            last_executed_time: block_time,
+            prev_yield_reserve: legacy_dynrate_state.prev_yield_reserve,
+            model_state: None,
+        },
+    )?;
+
+    if let Some(legacy_write_off_state) = legacy_write_off_state {
+        store_write_off_state(
+            storage,
+            &WriteOffState {
+                epoch_start_time: block_time,
+                epoch_written_off: legacy_write_off_state.epoch_written_off,
+                total_written_off: legacy_write_off_state.total_written_off,
+            },
+        )?;
+    }
+
+    Ok(())
+}
+
+pub fn store_total_collateral(
+    storage: &mut dyn Storage,
+    collateral_token: &CanonicalAddr,
+    amount: &Uint256,
+) -> StdResult<()> {
+    let mut total_collateral_bucket: Bucket<Uint256> =
+        Bucket::new(storage, PREFIX_TOTAL_COLLATERAL);
+    total_collateral_bucket.save(collateral_token.as_slice(), amount)
+}
+
+pub fn read_total_collateral(storage: &dyn Storage, collateral_token: &CanonicalAddr) -> Uint256 {
+    let total_collateral_bucket: ReadonlyBucket<Uint256> =
+        ReadonlyBucket::new(storage, PREFIX_TOTAL_COLLATERAL);
+    match total_collateral_bucket.load(collateral_token.as_slice()) {
+        Ok(v) => v,
+        _ => Uint256::zero(),
+    }
+}
+
+/// Recomputes the total locked amount of each collateral from the borrowers collaterals
+pub fn migrate_total_collaterals(storage: &mut dyn Storage) -> StdResult<()> {
+    let collateral_bucket: ReadonlyBucket<Tokens> =
+        ReadonlyBucket::new(storage, PREFIX_COLLATERALS);
+    let mut totals: Tokens = vec![];
+    for elem in collateral_bucket.range(None, None, Order::Ascending) {
+        let (_, collaterals) = elem?;
+        totals.add(collaterals);
+    }
+
+    let mut total_collateral_bucket: Bucket<Uint256> =
+        Bucket::new(storage, PREFIX_TOTAL_COLLATERAL);
+    for (collateral_token, amount) in totals.iter() {
+        total_collateral_bucket.save(collateral_token.as_slice(), amount)?;
+    }
+
+    Ok(())
+}
+
 #[allow(clippy::ptr_arg)]
 pub fn store_collaterals(
     storage: &mut dyn Storage,
@@ -209,6 +744,174 @@ pub fn read_all_collaterals(
         .collect()
 }
 
+pub fn store_last_liquidation_height(
+    storage: &mut dyn Storage,
+    borrower: &CanonicalAddr,
+    height: u64,
+) -> StdResult<()> {
+    let mut last_liquidation_bucket: Bucket<u64> = Bucket::new(storage, PREFIX_LAST_LIQUIDATION);
+    last_liquidation_bucket.save(borrower.as_slice(), &height)
+}
+
+pub fn read_last_liquidation_height(
+    storage: &dyn Storage,
+    borrower: &CanonicalAddr,
+) -> Option<u64> {
+    let last_liquidation_bucket: ReadonlyBucket<u64> =
+        ReadonlyBucket::new(storage, PREFIX_LAST_LIQUIDATION);
+    last_liquidation_bucket.load(borrower.as_slice()).ok()
+}
+
+pub fn store_margin_call(
+    storage: &mut dyn Storage,
+    borrower: &CanonicalAddr,
+    flagged_time: u64,
+) -> StdResult<()> {
+    let mut margin_call_bucket: Bucket<u64> = Bucket::new(storage, PREFIX_MARGIN_CALL);
+    margin_call_bucket.save(borrower.as_slice(), &flagged_time)
+}
+
+pub fn remove_margin_call(storage: &mut dyn Storage, borrower: &CanonicalAddr) {
+    let mut margin_call_bucket: Bucket<u64> = Bucket::new(storage, PREFIX_MARGIN_CALL);
+    margin_call_bucket.remove(borrower.as_slice())
+}
+
+pub fn read_margin_call(storage: &dyn Storage, borrower: &CanonicalAddr) -> Option<u64> {
+    let margin_call_bucket: ReadonlyBucket<u64> = ReadonlyBucket::new(storage, PREFIX_MARGIN_CALL);
+    margin_call_bucket.load(borrower.as_slice()).ok()
+}
+
+/// Returns a page of borrowers with their collaterals, and the last
+/// borrower of the page if there may be more borrowers left
+pub fn read_borrowers_collaterals(
+    storage: &dyn Storage,
+    start_after: Option<CanonicalAddr>,
+    limit: Option<u32>,
+) -> StdResult<(Vec<(CanonicalAddr, Tokens)>, Option<CanonicalAddr>)> {
+    let collaterals_bucket: ReadonlyBucket<Tokens> =
+        ReadonlyBucket::new(storage, PREFIX_COLLATERALS);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = calc_range_start(start_after);
+
+    let borrowers_collaterals = collaterals_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (k, v) = elem?;
+            Ok((CanonicalAddr::from(k), v))
+        })
+        .collect::<StdResult<Vec<(CanonicalAddr, Tokens)>>>()?;
+
+    let last_borrower = if borrowers_collaterals.len() == limit {
+        borrowers_collaterals.last().map(|(k, _)| k.clone())
+    } else {
+        None
+    };
+
+    Ok((borrowers_collaterals, last_borrower))
+}
+
+pub fn store_write_off_info(
+    storage: &mut dyn Storage,
+    borrower: &CanonicalAddr,
+    write_off_info: &WriteOffInfo,
+) -> StdResult<()> {
+    let mut write_off_bucket: Bucket<WriteOffInfo> = Bucket::new(storage, PREFIX_WRITE_OFF);
+    write_off_bucket.save(borrower.as_slice(), write_off_info)
+}
+
+pub fn read_write_off_info(storage: &dyn Storage, borrower: &CanonicalAddr) -> WriteOffInfo {
+    let write_off_bucket: ReadonlyBucket<WriteOffInfo> =
+        ReadonlyBucket::new(storage, PREFIX_WRITE_OFF);
+    match write_off_bucket.load(borrower.as_slice()) {
+        Ok(v) => v,
+        _ => WriteOffInfo {
+            amount: Uint256::zero(),
+            last_written_off_height: 0,
+        },
+    }
+}
+
+pub fn read_write_offs(
+    deps: Deps,
+    start_after: Option<CanonicalAddr>,
+    limit: Option<u32>,
+) -> StdResult<Vec<WriteOffResponse>> {
+    let write_off_bucket: ReadonlyBucket<WriteOffInfo> =
+        ReadonlyBucket::new(deps.storage, PREFIX_WRITE_OFF);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = calc_range_start(start_after);
+
+    write_off_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (k, v) = elem?;
+            let borrower = deps.api.addr_humanize(&CanonicalAddr::from(k))?.to_string();
+            Ok(WriteOffResponse {
+                borrower,
+                amount: v.amount,
+                last_written_off_height: v.last_written_off_height,
+            })
+        })
+        .collect()
+}
+
+pub fn store_bad_debt(
+    storage: &mut dyn Storage,
+    borrower: &CanonicalAddr,
+    bad_debt: &BadDebtInfo,
+) -> StdResult<()> {
+    let mut bad_debt_bucket: Bucket<BadDebtInfo> = Bucket::new(storage, PREFIX_BAD_DEBT);
+    if bad_debt.amount.is_zero() {
+        bad_debt_bucket.remove(borrower.as_slice());
+    } else {
+        bad_debt_bucket.save(borrower.as_slice(), bad_debt)?;
+    }
+
+    Ok(())
+}
+
+pub fn read_bad_debt(storage: &dyn Storage, borrower: &CanonicalAddr) -> BadDebtInfo {
+    let bad_debt_bucket: ReadonlyBucket<BadDebtInfo> =
+        ReadonlyBucket::new(storage, PREFIX_BAD_DEBT);
+    match bad_debt_bucket.load(borrower.as_slice()) {
+        Ok(v) => v,
+        _ => BadDebtInfo {
+            amount: Uint256::zero(),
+            recorded_height: 0,
+        },
+    }
+}
+
+pub fn read_bad_debts(
+    deps: Deps,
+    start_after: Option<CanonicalAddr>,
+    limit: Option<u32>,
+) -> StdResult<Vec<BadDebtResponse>> {
+    let bad_debt_bucket: ReadonlyBucket<BadDebtInfo> =
+        ReadonlyBucket::new(deps.storage, PREFIX_BAD_DEBT);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = calc_range_start(start_after);
+
+    bad_debt_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (k, v) = elem?;
+            let borrower = deps.api.addr_humanize(&CanonicalAddr::from(k))?.to_string();
+            Ok(BadDebtResponse {
+                borrower,
+                amount: v.amount,
+                recorded_height: v.recorded_height,
+            })
+        })
+        .collect()
+}
+
 // this will set the first key after the provided key, by appending a 1 byte
 fn calc_range_start(start_after: Option<CanonicalAddr>) -> Option<Vec<u8>> {
     start_after.map(|addr| {
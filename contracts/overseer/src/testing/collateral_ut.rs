@@ -31,10 +31,15 @@ fn proper_compute_borrow_limit() {
         anc_purchase_factor: Decimal256::percent(20),
         price_timeframe: 60u64,
         dyn_rate_epoch: 86400u64,
-        dyn_rate_maxchange: Decimal256::from_str("0.03").unwrap(),
-        dyn_rate_yr_increase_expectation: Decimal256::from_str("0.01").unwrap(),
+        deposit_rate_model: "deposit_rate_model".to_string(),
         dyn_rate_min: Decimal256::zero(),
         dyn_rate_max: Decimal256::one(),
+        bad_debt_handler: None,
+        max_write_off_per_epoch: Uint256::zero(),
+        close_factor: Decimal256::one(),
+        liquidation_interval: 0,
+        margin_call_grace_period: 0,
+        hard_liquidation_ltv: Decimal256::one(),
     };
 
     // we can just call .unwrap() to assert this was a success
@@ -47,6 +52,9 @@ fn proper_compute_borrow_limit() {
         collateral_token: "bluna".to_string(),
         custody_contract: "custody_bluna".to_string(),
         max_ltv: Decimal256::percent(60),
+        liquidation_ltv: Decimal256::percent(60),
+        max_collateral_amount: None,
+        max_borrow_value: None,
     };
 
     let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
@@ -57,6 +65,9 @@ fn proper_compute_borrow_limit() {
         collateral_token: "batom".to_string(),
         custody_contract: "custody_batom".to_string(),
         max_ltv: Decimal256::percent(60),
+        liquidation_ltv: Decimal256::percent(60),
+        max_collateral_amount: None,
+        max_borrow_value: None,
     };
 
     let _res = execute(deps.as_mut(), env.clone(), info, msg);
@@ -92,7 +103,8 @@ fn proper_compute_borrow_limit() {
     );
     collaterals.push(token2);
 
-    let res = compute_borrow_limit(deps.as_ref(), &collaterals, None).unwrap();
+    let res =
+        compute_borrow_limit(deps.as_ref(), &collaterals, mock_env().block.height, None).unwrap();
     let vec: Vec<Decimal256> = vec![
         Decimal256::from_uint256(1000u128),
         Decimal256::from_uint256(2000u128),
@@ -1,3 +1,4 @@
+use crate::state::EpochState;
 use astroport::token::InstantiateMsg as TokenInstantiateMsg;
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::testing::{mock_env, MockApi, MockStorage};
@@ -8,6 +9,7 @@ use moneymarket::custody::{
     BAssetInfo, ExecuteMsg as CustodyExecuteMsg, InstantiateMsg as CustodyInstantiateMsg,
     QueryMsg as CustodyQueryMsg,
 };
+use moneymarket::deposit_rate_model::InstantiateMsg as DepositRateModelInstantiateMsg;
 use moneymarket::distribution_model::InstantiateMsg as DistributionModelInstantiateMsg;
 use moneymarket::interest_model::InstantiateMsg as InterestModelInstantiateMsg;
 use moneymarket::market::{
@@ -16,9 +18,11 @@ use moneymarket::market::{
 };
 use moneymarket::oracle::{ExecuteMsg as OracleExecuteMsg, InstantiateMsg as OracleInstantiateMsg};
 use moneymarket::overseer::{
-    ExecuteMsg as OverseerExecuteMsg, InstantiateMsg as OverseerInstantiateMsg,
-    MigrateMsg as OverseerMigrateMsg,
+    CollateralUsageResponse, CollateralUsageResponseElem, CollateralsResponse, ConfigResponse,
+    ExecuteMsg as OverseerExecuteMsg, MigrateMsg as OverseerMigrateMsg,
+    QueryMsg as OverseerQueryMsg, WhitelistResponse, WhitelistStatus,
 };
+use serde::Serialize;
 use std::str::FromStr;
 use terra_multi_test::{AppBuilder, BankKeeper, ContractWrapper, Executor, TerraApp, TerraMock};
 
@@ -26,6 +30,28 @@ const OWNER: &str = "owner";
 const USER: &str = "user";
 const ADMIN: &str = "admin";
 
+/// Instantiate msg of the overseer before the deposit rate model was split out
+#[derive(Serialize)]
+struct OverseerInstantiateMsgOld {
+    owner_addr: String,
+    oracle_contract: String,
+    market_contract: String,
+    liquidation_contract: String,
+    collector_contract: String,
+    stable_denom: String,
+    epoch_period: u64,
+    threshold_deposit_rate: Decimal256,
+    target_deposit_rate: Decimal256,
+    buffer_distribution_factor: Decimal256,
+    anc_purchase_factor: Decimal256,
+    price_timeframe: u64,
+    dyn_rate_epoch: u64,
+    dyn_rate_maxchange: Decimal256,
+    dyn_rate_yr_increase_expectation: Decimal256,
+    dyn_rate_min: Decimal256,
+    dyn_rate_max: Decimal256,
+}
+
 fn mock_app() -> TerraApp {
     let env = mock_env();
     let api = MockApi::default();
@@ -127,6 +153,7 @@ fn store_overseer_contract_code(app: &mut TerraApp) -> u64 {
             moneymarket_overseer::contract::instantiate,
             moneymarket_overseer::contract::query,
         )
+        .with_reply_empty(moneymarket_overseer::contract::reply)
         .with_migrate_empty(moneymarket_overseer::contract::migrate),
     );
 
@@ -163,6 +190,16 @@ fn store_distribution_model_code(app: &mut TerraApp) -> u64 {
     app.store_code(distribution_model_contract)
 }
 
+fn store_deposit_rate_model_code(app: &mut TerraApp) -> u64 {
+    let deposit_rate_model_contract = Box::new(ContractWrapper::new_with_empty(
+        moneymarket_deposit_rate_model::contract::execute,
+        moneymarket_deposit_rate_model::contract::instantiate,
+        moneymarket_deposit_rate_model::contract::query,
+    ));
+
+    app.store_code(deposit_rate_model_contract)
+}
+
 fn create_contracts() -> (TerraApp, Addr, Addr, Addr, Addr, Addr) {
     let mut app = mock_app();
     let owner = Addr::unchecked(OWNER);
@@ -258,7 +295,7 @@ fn create_contracts() -> (TerraApp, Addr, Addr, Addr, Addr, Addr) {
         .unwrap();
 
     // instantiate overseer contract
-    let msg = OverseerInstantiateMsg {
+    let msg = OverseerInstantiateMsgOld {
         owner_addr: owner.to_string(),
         oracle_contract: oracle_addr.to_string(),
         market_contract: market_addr.to_string(),
@@ -350,28 +387,69 @@ fn create_contracts() -> (TerraApp, Addr, Addr, Addr, Addr, Addr) {
     )
 }
 
-fn migrate_contracts(app: &mut TerraApp, market_addr: &Addr, overseer_addr: &Addr) {
+fn migrate_contracts(app: &mut TerraApp, market_addr: &Addr, overseer_addr: &Addr) -> Addr {
+    let owner = Addr::unchecked(OWNER);
     let admin = Addr::unchecked(ADMIN);
 
     // store new contract code
     let market_code_id = store_market_contract_code(app);
     let overseer_code_id = store_overseer_contract_code(app);
+    let deposit_rate_model_code_id = store_deposit_rate_model_code(app);
+
+    // instantiate deposit rate model contract with the old dynrate parameters
+    let msg = DepositRateModelInstantiateMsg {
+        owner: owner.to_string(),
+        max_change: Decimal256::permille(5),
+        yr_increase_expectation: Decimal256::permille(1),
+    };
+    let deposit_rate_model_addr = app
+        .instantiate_contract(
+            deposit_rate_model_code_id,
+            owner,
+            &msg,
+            &[],
+            String::from("DEPOSIT RATE MODEL"),
+            None,
+        )
+        .unwrap();
 
-    // migrate market contract
-    let msg = MarketMigrateMsg {};
+    // migrate market contract with the rates of the legacy interest model and overseer
+    let msg = MarketMigrateMsg::FromLegacy {
+        borrow_rate: Decimal256::percent(10),
+        target_deposit_rate: Decimal256::permille(5),
+    };
     app.migrate_contract(admin.clone(), market_addr.clone(), &msg, market_code_id)
         .unwrap();
 
     // migrate overseer contract
-    let msg = OverseerMigrateMsg {};
+    let msg = OverseerMigrateMsg::FromLegacy {
+        deposit_rate_model: deposit_rate_model_addr.to_string(),
+        bad_debt_handler: None,
+        max_write_off_per_epoch: Uint256::zero(),
+        close_factor: Decimal256::one(),
+        liquidation_interval: 0,
+        margin_call_grace_period: 0,
+        hard_liquidation_ltv: Decimal256::one(),
+    };
     app.migrate_contract(admin, overseer_addr.clone(), &msg, overseer_code_id)
         .unwrap();
+
+    deposit_rate_model_addr
 }
 
 #[test]
 fn test_migration() {
     let (mut app, market_addr, overseer_addr, _, _, _) = create_contracts();
-    migrate_contracts(&mut app, &market_addr, &overseer_addr);
+    let deposit_rate_model_addr = migrate_contracts(&mut app, &market_addr, &overseer_addr);
+
+    let config: ConfigResponse = app
+        .wrap()
+        .query_wasm_smart(overseer_addr, &OverseerQueryMsg::Config {})
+        .unwrap();
+    assert_eq!(
+        config.deposit_rate_model,
+        deposit_rate_model_addr.to_string()
+    );
 }
 
 #[test]
@@ -393,6 +471,9 @@ fn test_successfully_repay_stable_from_yield_reserve() {
         collateral_token: bluna_token_addr.to_string(),
         custody_contract: custody_contract_addr.to_string(),
         max_ltv: Decimal256::percent(60),
+        liquidation_ltv: Decimal256::percent(60),
+        max_collateral_amount: None,
+        max_borrow_value: None,
     };
 
     app.execute_contract(owner.clone(), overseer_addr.clone(), &msg, &[])
@@ -425,7 +506,8 @@ fn test_successfully_repay_stable_from_yield_reserve() {
         )],
     };
 
-    app.execute_contract(owner, oracle_addr, &msg, &[]).unwrap();
+    app.execute_contract(owner.clone(), oracle_addr.clone(), &msg, &[])
+        .unwrap();
 
     // borrow UST agaist bluna
     let msg = MarketExecuteMsg::BorrowStable {
@@ -438,12 +520,59 @@ fn test_successfully_repay_stable_from_yield_reserve() {
 
     migrate_contracts(&mut app, &market_addr, &overseer_addr);
 
-    // repay stable from yield reserve
+    // enable write-offs
+    let msg = OverseerExecuteMsg::UpdateConfig {
+        oracle_contract: None,
+        liquidation_contract: None,
+        threshold_deposit_rate: None,
+        target_deposit_rate: None,
+        buffer_distribution_factor: None,
+        anc_purchase_factor: None,
+        epoch_period: None,
+        price_timeframe: None,
+        dyn_rate_epoch: None,
+        deposit_rate_model: None,
+        dyn_rate_min: None,
+        dyn_rate_max: None,
+        bad_debt_handler: None,
+        max_write_off_per_epoch: Some(Uint256::from(1_000_000_000u64)),
+        close_factor: None,
+        liquidation_interval: None,
+        margin_call_grace_period: None,
+        hard_liquidation_ltv: None,
+    };
+
+    app.execute_contract(owner.clone(), overseer_addr.clone(), &msg, &[])
+        .unwrap();
+
+    // the loan is still covered by its collaterals
+    let msg = OverseerExecuteMsg::RepayStableFromYieldReserve {
+        borrower: user.to_string(),
+    };
+
+    app.execute_contract(owner.clone(), overseer_addr.clone(), &msg, &[])
+        .unwrap_err();
+
+    // bluna price crashes, the loan is under water
+    let msg = OracleExecuteMsg::FeedPrice {
+        prices: vec![(
+            bluna_token_addr.to_string(),
+            Decimal256::from_str("0.5").unwrap(),
+        )],
+    };
+
+    app.execute_contract(owner.clone(), oracle_addr, &msg, &[])
+        .unwrap();
+
+    // only the owner or the bad debt handler can write off
     let msg = OverseerExecuteMsg::RepayStableFromYieldReserve {
         borrower: user.to_string(),
     };
 
     app.execute_contract(user.clone(), overseer_addr.clone(), &msg, &[])
+        .unwrap_err();
+
+    app.execute_contract(owner, overseer_addr.clone(), &msg, &[])
         .unwrap();
 
     // check remain loan amount of the user
@@ -453,10 +582,256 @@ fn test_successfully_repay_stable_from_yield_reserve() {
             market_addr.clone(),
             &MarketQueryMsg::BorrowerInfo {
                 borrower: user.to_string(),
-                block_height: None,
+                block_time: None,
             },
         )
         .unwrap();
 
     assert_eq!(res.loan_amount, Uint256::zero());
 }
+
+#[test]
+fn test_lock_collateral_after_migration() {
+    let owner = Addr::unchecked(OWNER);
+    let user = Addr::unchecked(USER);
+
+    let (mut app, market_addr, overseer_addr, bluna_token_addr, custody_contract_addr, oracle_addr) =
+        create_contracts();
+
+    // register whitelist
+    let msg = OverseerExecuteMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: bluna_token_addr.to_string(),
+        custody_contract: custody_contract_addr.to_string(),
+        max_ltv: Decimal256::percent(60),
+        liquidation_ltv: Decimal256::percent(60),
+        max_collateral_amount: None,
+        max_borrow_value: None,
+    };
+
+    app.execute_contract(owner.clone(), overseer_addr.clone(), &msg, &[])
+        .unwrap();
+
+    // lock some bluna with the old overseer
+    let msg = OverseerExecuteMsg::LockCollateral {
+        collaterals: vec![(bluna_token_addr.to_string(), Uint256::from(1_000_000u64))],
+    };
+
+    app.execute_contract(user.clone(), overseer_addr.clone(), &msg, &[])
+        .unwrap();
+
+    migrate_contracts(&mut app, &market_addr, &overseer_addr);
+
+    // lock more bluna with the migrated overseer
+    let msg = OverseerExecuteMsg::LockCollateral {
+        collaterals: vec![(bluna_token_addr.to_string(), Uint256::from(2_000_000u64))],
+    };
+
+    app.execute_contract(user.clone(), overseer_addr.clone(), &msg, &[])
+        .unwrap();
+
+    // tokens which are not whitelisted cannot be locked
+    let msg = OverseerExecuteMsg::LockCollateral {
+        collaterals: vec![(market_addr.to_string(), Uint256::from(1_000_000u64))],
+    };
+
+    app.execute_contract(user.clone(), overseer_addr.clone(), &msg, &[])
+        .unwrap_err();
+
+    let res: CollateralsResponse = app
+        .wrap()
+        .query_wasm_smart(
+            overseer_addr.clone(),
+            &OverseerQueryMsg::Collaterals {
+                borrower: user.to_string(),
+            },
+        )
+        .unwrap();
+
+    assert_eq!(
+        res,
+        CollateralsResponse {
+            borrower: user.to_string(),
+            collaterals: vec![(bluna_token_addr.to_string(), Uint256::from(3_000_000u64))],
+        }
+    );
+
+    // the total locked amount includes the collaterals locked before the migration
+    let msg = OracleExecuteMsg::RegisterFeeder {
+        asset: bluna_token_addr.to_string(),
+        feeder: owner.to_string(),
+    };
+
+    app.execute_contract(owner.clone(), oracle_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let msg = OracleExecuteMsg::FeedPrice {
+        prices: vec![(
+            bluna_token_addr.to_string(),
+            Decimal256::from_str("10").unwrap(),
+        )],
+    };
+
+    app.execute_contract(owner, oracle_addr, &msg, &[]).unwrap();
+
+    let res: CollateralUsageResponse = app
+        .wrap()
+        .query_wasm_smart(
+            overseer_addr,
+            &OverseerQueryMsg::CollateralUsage {
+                collateral_token: None,
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+
+    assert_eq!(
+        res,
+        CollateralUsageResponse {
+            elems: vec![CollateralUsageResponseElem {
+                collateral_token: bluna_token_addr.to_string(),
+                total_amount: Uint256::from(3_000_000u64),
+                max_collateral_amount: None,
+                borrow_value: Uint256::from(18_000_000u64),
+                max_borrow_value: None,
+            }],
+        }
+    );
+}
+
+#[test]
+fn test_whitelist_liquidation_ltv_after_migration() {
+    let owner = Addr::unchecked(OWNER);
+
+    let (mut app, market_addr, overseer_addr, bluna_token_addr, custody_contract_addr, _) =
+        create_contracts();
+
+    // register whitelist with the old overseer
+    let msg = OverseerExecuteMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: bluna_token_addr.to_string(),
+        custody_contract: custody_contract_addr.to_string(),
+        max_ltv: Decimal256::percent(60),
+        liquidation_ltv: Decimal256::percent(60),
+        max_collateral_amount: None,
+        max_borrow_value: None,
+    };
+
+    app.execute_contract(owner.clone(), overseer_addr.clone(), &msg, &[])
+        .unwrap();
+
+    migrate_contracts(&mut app, &market_addr, &overseer_addr);
+
+    // existing collaterals are liquidated at their max LTV
+    let res: WhitelistResponse = app
+        .wrap()
+        .query_wasm_smart(
+            overseer_addr.clone(),
+            &OverseerQueryMsg::Whitelist {
+                collateral_token: Some(bluna_token_addr.to_string()),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(res.elems[0].max_ltv, Decimal256::percent(60));
+    assert_eq!(res.elems[0].liquidation_ltv, Decimal256::percent(60));
+    assert_eq!(res.elems[0].status, WhitelistStatus::Active);
+
+    let msg = OverseerExecuteMsg::UpdateWhitelist {
+        collateral_token: bluna_token_addr.to_string(),
+        custody_contract: None,
+        max_ltv: None,
+        liquidation_ltv: Some(Decimal256::percent(75)),
+        max_collateral_amount: None,
+        max_borrow_value: None,
+        max_ltv_ramp_seconds: None,
+    };
+
+    app.execute_contract(owner, overseer_addr.clone(), &msg, &[])
+        .unwrap();
+
+    let res: WhitelistResponse = app
+        .wrap()
+        .query_wasm_smart(
+            overseer_addr,
+            &OverseerQueryMsg::Whitelist {
+                collateral_token: Some(bluna_token_addr.to_string()),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(res.elems[0].max_ltv, Decimal256::percent(60));
+    assert_eq!(res.elems[0].liquidation_ltv, Decimal256::percent(75));
+}
+
+#[test]
+fn test_execute_epoch_operations_after_migration() {
+    let owner = Addr::unchecked(OWNER);
+    let user = Addr::unchecked(USER);
+
+    let (mut app, market_addr, overseer_addr, bluna_token_addr, custody_contract_addr, _) =
+        create_contracts();
+
+    migrate_contracts(&mut app, &market_addr, &overseer_addr);
+
+    // register whitelist
+    let msg = OverseerExecuteMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: bluna_token_addr.to_string(),
+        custody_contract: custody_contract_addr.to_string(),
+        max_ltv: Decimal256::percent(60),
+        liquidation_ltv: Decimal256::percent(60),
+        max_collateral_amount: None,
+        max_borrow_value: None,
+    };
+
+    app.execute_contract(owner, overseer_addr.clone(), &msg, &[])
+        .unwrap();
+
+    // epoch has not passed yet
+    let msg = OverseerExecuteMsg::ExecuteEpochOperations {};
+    app.execute_contract(user.clone(), overseer_addr.clone(), &msg, &[])
+        .unwrap_err();
+
+    // the epoch period of 86400 blocks was converted into seconds
+    let config: ConfigResponse = app
+        .wrap()
+        .query_wasm_smart(overseer_addr.clone(), &OverseerQueryMsg::Config {})
+        .unwrap();
+    assert_eq!(config.epoch_period, 585_102);
+
+    app.update_block(|block| {
+        block.height += 1;
+        block.time = block.time.plus_seconds(config.epoch_period);
+    });
+
+    // anyone can execute epoch operations once the epoch has passed
+    let res = app
+        .execute_contract(user.clone(), overseer_addr.clone(), &msg, &[])
+        .unwrap();
+    assert!(res.events.iter().any(|event| event
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "action" && attr.value == "update_epoch_state")));
+    assert!(res.events.iter().any(|event| event
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "action" && attr.value == "execute_epoch_operations")));
+
+    // the epoch state was updated by the reply of the custody call
+    let state: EpochState = app
+        .wrap()
+        .query_wasm_smart(overseer_addr.clone(), &OverseerQueryMsg::EpochState {})
+        .unwrap();
+    assert_eq!(state.last_executed_time, app.block_info().time.seconds());
+
+    // the next epoch cannot be executed right away
+    app.execute_contract(user, overseer_addr, &msg, &[])
+        .unwrap_err();
+}
@@ -4,11 +4,14 @@ use serde::{Deserialize, Serialize};
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    from_binary, from_slice, to_binary, Coin, ContractResult, Decimal, OwnedDeps, Querier,
+    from_binary, from_slice, to_binary, Binary, Coin, ContractResult, Decimal, OwnedDeps, Querier,
     QuerierResult, QueryRequest, SystemError, SystemResult, Uint128, WasmQuery,
 };
 use std::collections::HashMap;
 
+use moneymarket_deposit_rate_model::contract::compute_target_deposit_rate;
+
+use moneymarket::deposit_rate_model::TargetDepositRateResponse;
 use moneymarket::liquidation::LiquidationAmountResponse;
 use moneymarket::market::{BorrowerInfoResponse, EpochStateResponse, StateResponse};
 use moneymarket::oracle::PriceResponse;
@@ -22,16 +25,16 @@ use std::str::FromStr;
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     /// Market state to market contract
-    State { block_height: Option<u64> },
+    State { block_time: Option<u64> },
     /// Query epoch state to market contract
     EpochState {
-        block_height: Option<u64>,
+        block_time: Option<u64>,
         distributed_interest: Option<Uint256>,
     },
     /// Query loan amount to market contract
     BorrowerInfo {
         borrower: String,
-        block_height: Option<u64>,
+        block_time: Option<u64>,
     },
     /// Query oracle price to oracle contract
     Price { base: String, quote: String },
@@ -42,6 +45,14 @@ pub enum QueryMsg {
         collaterals: TokensHuman,
         collateral_prices: Vec<Decimal256>,
     },
+    /// Query new deposit rate to deposit rate model contract
+    TargetDepositRate {
+        yield_reserve: Decimal256,
+        prev_yield_reserve: Decimal256,
+        current_rate: Decimal256,
+        seconds: u64,
+        model_state: Option<Binary>,
+    },
 }
 
 /// mock_dependencies is a drop-in replacement for cosmwasm_std::testing::mock_dependencies
@@ -66,6 +77,7 @@ pub struct WasmMockQuerier {
     oracle_price_querier: OraclePriceQuerier,
     loan_amount_querier: LoanAmountQuerier,
     liquidation_percent_querier: LiquidationPercentQuerier,
+    deposit_rate_model_querier: DepositRateModelQuerier,
 }
 
 #[derive(Clone, Default)]
@@ -207,6 +219,23 @@ pub(crate) fn liquidation_percent_to_map(
     liquidation_percent_map
 }
 
+#[derive(Clone, Default)]
+pub struct DepositRateModelQuerier {
+    // (max_change, yr_increase_expectation) per deposit rate model contract
+    models: HashMap<String, (Decimal256, Decimal256)>,
+}
+
+impl DepositRateModelQuerier {
+    pub fn new(models: &[(&String, &(Decimal256, Decimal256))]) -> Self {
+        let mut models_map: HashMap<String, (Decimal256, Decimal256)> = HashMap::new();
+        for (deposit_rate_model, model) in models.iter() {
+            models_map.insert((*deposit_rate_model).clone(), **model);
+        }
+
+        DepositRateModelQuerier { models: models_map }
+    }
+}
+
 impl WasmMockQuerier {
     pub fn handle_query(&self, request: &QueryRequest<TerraQueryWrapper>) -> QuerierResult {
         match &request {
@@ -237,7 +266,7 @@ impl WasmMockQuerier {
             }
             QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg }) => {
                 match from_binary(msg).unwrap() {
-                    QueryMsg::State { block_height: _ } => {
+                    QueryMsg::State { block_time: _ } => {
                         match self.epoch_state_querier.epoch_state.get(contract_addr) {
                             // TODO:
                             Some(_v) => {
@@ -250,6 +279,7 @@ impl WasmMockQuerier {
                                         .unwrap(),
                                     global_reward_index: Decimal256::zero(),
                                     anc_emission_rate: Decimal256::zero(),
+                                    aterra_supply: Uint256::zero(),
                                     prev_aterra_supply: Uint256::zero(),
                                     prev_exchange_rate: Decimal256::zero(),
                                 })))
@@ -261,7 +291,7 @@ impl WasmMockQuerier {
                         }
                     }
                     QueryMsg::EpochState {
-                        block_height: _,
+                        block_time: _,
                         distributed_interest: _,
                     } => match self.epoch_state_querier.epoch_state.get(contract_addr) {
                         Some(v) => {
@@ -277,7 +307,7 @@ impl WasmMockQuerier {
                     },
                     QueryMsg::BorrowerInfo {
                         borrower,
-                        block_height: _,
+                        block_time: _,
                     } => match self.loan_amount_querier.borrower_amount.get(&borrower) {
                         Some(v) => SystemResult::Ok(ContractResult::from(to_binary(
                             &BorrowerInfoResponse {
@@ -285,6 +315,7 @@ impl WasmMockQuerier {
                                 interest_index: Decimal256::one(),
                                 reward_index: Decimal256::zero(),
                                 loan_amount: *v,
+                                fixed_loan_amount: Uint256::zero(),
                                 pending_rewards: Decimal256::zero(),
                             },
                         ))),
@@ -344,6 +375,30 @@ impl WasmMockQuerier {
                             }),
                         }
                     }
+                    QueryMsg::TargetDepositRate {
+                        yield_reserve,
+                        prev_yield_reserve,
+                        current_rate,
+                        seconds: _,
+                        model_state: _,
+                    } => match self.deposit_rate_model_querier.models.get(contract_addr) {
+                        Some(v) => SystemResult::Ok(ContractResult::from(to_binary(
+                            &TargetDepositRateResponse {
+                                rate: compute_target_deposit_rate(
+                                    v.0,
+                                    v.1,
+                                    yield_reserve,
+                                    prev_yield_reserve,
+                                    current_rate,
+                                ),
+                                model_state: None,
+                            },
+                        ))),
+                        None => SystemResult::Err(SystemError::InvalidRequest {
+                            error: "No deposit rate model exists".to_string(),
+                            request: msg.as_slice().into(),
+                        }),
+                    },
                 }
             }
             _ => self.base.handle_query(request),
@@ -360,6 +415,7 @@ impl WasmMockQuerier {
             oracle_price_querier: OraclePriceQuerier::default(),
             loan_amount_querier: LoanAmountQuerier::default(),
             liquidation_percent_querier: LiquidationPercentQuerier::default(),
+            deposit_rate_model_querier: DepositRateModelQuerier::default(),
         }
     }
 
@@ -387,4 +443,8 @@ impl WasmMockQuerier {
     pub fn with_liquidation_percent(&mut self, liquidation_percent: &[(&String, &Decimal256)]) {
         self.liquidation_percent_querier = LiquidationPercentQuerier::new(liquidation_percent);
     }
+
+    pub fn with_deposit_rate_model(&mut self, models: &[(&String, &(Decimal256, Decimal256))]) {
+        self.deposit_rate_model_querier = DepositRateModelQuerier::new(models);
+    }
 }
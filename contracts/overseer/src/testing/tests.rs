@@ -1,24 +1,37 @@
 use crate::collateral::lock_collateral as _lock_collateral;
-use crate::contract::{execute, instantiate, query};
+use crate::contract::{execute, instantiate, migrate, query, reply};
 use crate::error::ContractError;
 use crate::querier::query_epoch_state;
 use crate::state::{
-    read_epoch_state, store_dynrate_state, store_epoch_state, DynrateState, EpochState,
+    read_dynrate_config, read_epoch_state, read_liquidation_config, read_whitelist_elem,
+    read_write_off_config, store_dynrate_state, store_epoch_state, store_pending_epoch_operation,
+    DynrateState, EpochState, LegacyDynrateConfig, LegacyDynrateState, LegacyEpochState,
+    LegacyMaxLtvRamp, LegacyWhitelistElem, LegacyWhitelistStatus, LiquidationConfig,
+    PendingEpochOperation, WriteOffConfig, WriteOffState, EPOCH_HISTORY_SIZE,
 };
 use crate::testing::mock_querier::mock_dependencies;
 
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    attr, from_binary, to_binary, Addr, Api, BankMsg, CanonicalAddr, Coin, CosmosMsg, Decimal,
-    DepsMut, SubMsg, Uint128, WasmMsg,
+    attr, from_binary, to_binary, Addr, Api, BankMsg, Binary, CanonicalAddr, Coin, ContractResult,
+    CosmosMsg, Decimal, DepsMut, Env, Reply, Response, StdError, SubMsg, SubMsgExecutionResponse,
+    Uint128, WasmMsg,
 };
+use cosmwasm_storage::{Bucket, Singleton};
+use moneymarket::common::{legacy_blocks_to_seconds, legacy_rate_to_rate_per_second};
 use moneymarket::custody::ExecuteMsg as CustodyExecuteMsg;
 use moneymarket::market::ExecuteMsg as MarketExecuteMsg;
+use moneymarket::operator::OperatorPermission;
 use moneymarket::overseer::{
-    AllCollateralsResponse, BorrowLimitResponse, CollateralsResponse, ConfigResponse, ExecuteMsg,
-    InstantiateMsg, QueryMsg, WhitelistResponse, WhitelistResponseElem,
+    AllCollateralsResponse, BadDebtResponse, BadDebtsResponse, BorrowLimitResponse,
+    BorrowerHealthResponse, CollateralHealthResponse, CollateralUsageResponse,
+    CollateralUsageResponseElem, CollateralsResponse, ConfigResponse, DynrateDecision,
+    EpochHistoryResponse, EpochHistoryResponseElem, ExecuteMsg, InstantiateMsg, MarginCallResponse,
+    MaxLtvRamp, MigrateMsg, QueryMsg, ReserveResponse, TotalBadDebtResponse, WhitelistResponse,
+    WhitelistResponseElem, WhitelistStatus, WriteOffResponse, WriteOffsResponse,
 };
+use moneymarket::pause::{PauseAction, PauseInfoResponse};
 use moneymarket::querier::deduct_tax;
 
 use std::str::FromStr;
@@ -41,10 +54,15 @@ fn proper_initialization() {
         anc_purchase_factor: Decimal256::percent(20),
         price_timeframe: 60u64,
         dyn_rate_epoch: 8600u64,
-        dyn_rate_maxchange: Decimal256::permille(5),
-        dyn_rate_yr_increase_expectation: Decimal256::permille(1),
+        deposit_rate_model: "deposit_rate_model".to_string(),
         dyn_rate_min: Decimal256::from_ratio(1000000000000u64, 1000000000000000000u64),
         dyn_rate_max: Decimal256::from_ratio(1200000000000u64, 1000000000000000000u64),
+        bad_debt_handler: None,
+        max_write_off_per_epoch: Uint256::zero(),
+        close_factor: Decimal256::one(),
+        liquidation_interval: 0,
+        margin_call_grace_period: 0,
+        hard_liquidation_ltv: Decimal256::one(),
     };
 
     let info = mock_info("addr0000", &[]);
@@ -70,10 +88,15 @@ fn proper_initialization() {
             anc_purchase_factor: Decimal256::percent(20),
             price_timeframe: 60u64,
             dyn_rate_epoch: 8600u64,
-            dyn_rate_maxchange: Decimal256::permille(5),
-            dyn_rate_yr_increase_expectation: Decimal256::permille(1),
+            deposit_rate_model: "deposit_rate_model".to_string(),
             dyn_rate_min: Decimal256::from_ratio(1000000000000u64, 1000000000000000000u64),
             dyn_rate_max: Decimal256::from_ratio(1200000000000u64, 1000000000000000000u64),
+            bad_debt_handler: None,
+            max_write_off_per_epoch: Uint256::zero(),
+            close_factor: Decimal256::one(),
+            liquidation_interval: 0,
+            margin_call_grace_period: 0,
+            hard_liquidation_ltv: Decimal256::one(),
         }
     );
 
@@ -83,7 +106,7 @@ fn proper_initialization() {
         epoch_state,
         EpochState {
             deposit_rate: Decimal256::zero(),
-            last_executed_height: mock_env().block.height,
+            last_executed_time: mock_env().block.time.seconds(),
             prev_aterra_supply: Uint256::zero(),
             prev_exchange_rate: Decimal256::one(),
             prev_interest_buffer: Uint256::zero(),
@@ -110,34 +133,32 @@ fn update_config() {
         anc_purchase_factor: Decimal256::percent(20),
         price_timeframe: 60u64,
         dyn_rate_epoch: 86400u64,
-        dyn_rate_maxchange: Decimal256::from_str("0.03").unwrap(),
-        dyn_rate_yr_increase_expectation: Decimal256::from_str("0.01").unwrap(),
+        deposit_rate_model: "deposit_rate_model".to_string(),
         dyn_rate_min: Decimal256::zero(),
         dyn_rate_max: Decimal256::one(),
+        bad_debt_handler: None,
+        max_write_off_per_epoch: Uint256::zero(),
+        close_factor: Decimal256::one(),
+        liquidation_interval: 0,
+        margin_call_grace_period: 0,
+        hard_liquidation_ltv: Decimal256::one(),
     };
 
     // we can just call .unwrap() to assert this was a success
     let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
 
-    // update owner
+    // transfer the ownership
     let info = mock_info("owner", &[]);
-    let msg = ExecuteMsg::UpdateConfig {
-        owner_addr: Some("owner1".to_string()),
-        oracle_contract: None,
-        liquidation_contract: None,
-        threshold_deposit_rate: None,
-        target_deposit_rate: None,
-        buffer_distribution_factor: None,
-        anc_purchase_factor: None,
-        epoch_period: None,
-        price_timeframe: None,
-        dyn_rate_epoch: None,
-        dyn_rate_maxchange: None,
-        dyn_rate_yr_increase_expectation: None,
-        dyn_rate_min: None,
-        dyn_rate_max: None,
+    let msg = ExecuteMsg::ProposeNewOwner {
+        owner: "owner1".to_string(),
+        expires_in: 86400u64,
     };
 
+    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let info = mock_info("owner1", &[]);
+    let msg = ExecuteMsg::ClaimOwnership {};
+
     let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
     assert_eq!(0, res.messages.len());
 
@@ -146,10 +167,19 @@ fn update_config() {
     let config_res: ConfigResponse = from_binary(&res).unwrap();
     assert_eq!("owner1".to_string(), config_res.owner_addr);
 
+    store_dynrate_state(
+        deps.as_mut().storage,
+        &DynrateState {
+            last_executed_time: mock_env().block.time.seconds(),
+            prev_yield_reserve: Decimal256::zero(),
+            model_state: Some(Binary::from(b"model_state".to_vec())),
+        },
+    )
+    .unwrap();
+
     // update left items
     let info = mock_info("owner1", &[]);
     let msg = ExecuteMsg::UpdateConfig {
-        owner_addr: None,
         oracle_contract: Some("oracle1".to_string()),
         liquidation_contract: Some("liquidation1".to_string()),
         threshold_deposit_rate: Some(Decimal256::permille(1)),
@@ -159,8 +189,7 @@ fn update_config() {
         epoch_period: Some(100000u64),
         price_timeframe: Some(120u64),
         dyn_rate_epoch: Some(8600u64),
-        dyn_rate_maxchange: Some(Decimal256::permille(5)),
-        dyn_rate_yr_increase_expectation: Some(Decimal256::permille(1)),
+        deposit_rate_model: Some("deposit_rate_model1".to_string()),
         dyn_rate_min: Some(Decimal256::from_ratio(
             1000000000000u64,
             1000000000000000000u64,
@@ -169,6 +198,12 @@ fn update_config() {
             1200000000000u64,
             1000000000000000000u64,
         )),
+        bad_debt_handler: None,
+        max_write_off_per_epoch: None,
+        close_factor: None,
+        liquidation_interval: None,
+        margin_call_grace_period: None,
+        hard_liquidation_ltv: None,
     };
 
     let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -189,11 +224,19 @@ fn update_config() {
     assert_eq!(Decimal256::percent(10), config_res.anc_purchase_factor);
     assert_eq!(100000u64, config_res.epoch_period);
     assert_eq!(120u64, config_res.price_timeframe);
+    assert_eq!(
+        "deposit_rate_model1".to_string(),
+        config_res.deposit_rate_model
+    );
+
+    // the state of the previous deposit rate model is dropped
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::DynrateState {}).unwrap();
+    let dynrate_state: DynrateState = from_binary(&res).unwrap();
+    assert_eq!(dynrate_state.model_state, None);
 
     // Unauthorized err
     let info = mock_info("owner", &[]);
     let msg = ExecuteMsg::UpdateConfig {
-        owner_addr: None,
         oracle_contract: None,
         liquidation_contract: None,
         threshold_deposit_rate: None,
@@ -203,10 +246,15 @@ fn update_config() {
         epoch_period: None,
         price_timeframe: None,
         dyn_rate_epoch: None,
-        dyn_rate_maxchange: None,
-        dyn_rate_yr_increase_expectation: None,
+        deposit_rate_model: None,
         dyn_rate_min: None,
         dyn_rate_max: None,
+        bad_debt_handler: None,
+        max_write_off_per_epoch: None,
+        close_factor: None,
+        liquidation_interval: None,
+        margin_call_grace_period: None,
+        hard_liquidation_ltv: None,
     };
 
     let res = execute(deps.as_mut(), mock_env(), info, msg);
@@ -235,10 +283,15 @@ fn whitelist() {
         anc_purchase_factor: Decimal256::percent(20),
         price_timeframe: 60u64,
         dyn_rate_epoch: 86400u64,
-        dyn_rate_maxchange: Decimal256::from_str("0.03").unwrap(),
-        dyn_rate_yr_increase_expectation: Decimal256::from_str("0.01").unwrap(),
+        deposit_rate_model: "deposit_rate_model".to_string(),
         dyn_rate_min: Decimal256::zero(),
         dyn_rate_max: Decimal256::one(),
+        bad_debt_handler: None,
+        max_write_off_per_epoch: Uint256::zero(),
+        close_factor: Decimal256::one(),
+        liquidation_interval: 0,
+        margin_call_grace_period: 0,
+        hard_liquidation_ltv: Decimal256::one(),
     };
 
     // we can just call .unwrap() to assert this was a success
@@ -250,6 +303,9 @@ fn whitelist() {
         collateral_token: "bluna".to_string(),
         custody_contract: "custody".to_string(),
         max_ltv: Decimal256::percent(60),
+        liquidation_ltv: Decimal256::percent(70),
+        max_collateral_amount: None,
+        max_borrow_value: None,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -270,6 +326,7 @@ fn whitelist() {
             attr("collateral_token", "bluna"),
             attr("custody_contract", "custody"),
             attr("LTV", "0.6"),
+            attr("liquidation_LTV", "0.7"),
         ]
     );
 
@@ -293,10 +350,34 @@ fn whitelist() {
                 collateral_token: "bluna".to_string(),
                 custody_contract: "custody".to_string(),
                 max_ltv: Decimal256::percent(60),
+                liquidation_ltv: Decimal256::percent(70),
+                max_collateral_amount: None,
+                max_borrow_value: None,
+                status: WhitelistStatus::Active,
+                max_ltv_ramp: None,
             }]
         }
     );
 
+    // liquidation LTV must not be lower than max LTV
+    let msg = ExecuteMsg::Whitelist {
+        name: "batom".to_string(),
+        symbol: "batom".to_string(),
+        collateral_token: "batom".to_string(),
+        custody_contract: "custody".to_string(),
+        max_ltv: Decimal256::percent(60),
+        liquidation_ltv: Decimal256::percent(50),
+        max_collateral_amount: None,
+        max_borrow_value: None,
+    };
+
+    let info = mock_info("owner", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    match res {
+        Err(ContractError::InvalidLiquidationLtv {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
     //Attempting to whitelist already whitelisted collaterals
     let msg = ExecuteMsg::Whitelist {
         name: "bluna".to_string(),
@@ -304,6 +385,9 @@ fn whitelist() {
         collateral_token: "bluna".to_string(),
         custody_contract: "custody".to_string(),
         max_ltv: Decimal256::percent(60),
+        liquidation_ltv: Decimal256::percent(60),
+        max_collateral_amount: None,
+        max_borrow_value: None,
     };
 
     let info = mock_info("owner", &[]);
@@ -317,6 +401,10 @@ fn whitelist() {
         collateral_token: "bluna".to_string(),
         custody_contract: Some("custody2".to_string()),
         max_ltv: Some(Decimal256::percent(30)),
+        liquidation_ltv: None,
+        max_collateral_amount: None,
+        max_borrow_value: None,
+        max_ltv_ramp_seconds: None,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -335,6 +423,7 @@ fn whitelist() {
             attr("collateral_token", "bluna"),
             attr("custody_contract", "custody2"),
             attr("LTV", "0.3"),
+            attr("liquidation_LTV", "0.7"),
         ]
     );
 
@@ -358,13 +447,34 @@ fn whitelist() {
                 collateral_token: "bluna".to_string(),
                 custody_contract: "custody2".to_string(),
                 max_ltv: Decimal256::percent(30),
+                liquidation_ltv: Decimal256::percent(70),
+                max_collateral_amount: None,
+                max_borrow_value: None,
+                status: WhitelistStatus::Active,
+                max_ltv_ramp: None,
             }]
         }
     );
+
+    let msg = ExecuteMsg::UpdateWhitelist {
+        collateral_token: "bluna".to_string(),
+        custody_contract: None,
+        max_ltv: Some(Decimal256::percent(80)),
+        liquidation_ltv: None,
+        max_collateral_amount: None,
+        max_borrow_value: None,
+        max_ltv_ramp_seconds: None,
+    };
+
+    let info = mock_info("owner", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    match res {
+        Err(ContractError::InvalidLiquidationLtv {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
 }
 
 #[test]
-#[ignore = "deprecated functionality"]
 fn execute_epoch_operations() {
     let mut deps = mock_dependencies(&[Coin {
         denom: "uusd".to_string(),
@@ -387,15 +497,28 @@ fn execute_epoch_operations() {
         anc_purchase_factor: Decimal256::percent(20),
         price_timeframe: 60u64,
         dyn_rate_epoch: 86400u64,
-        dyn_rate_maxchange: Decimal256::from_str("0.03").unwrap(),
-        dyn_rate_yr_increase_expectation: Decimal256::from_str("0.01").unwrap(),
+        deposit_rate_model: "deposit_rate_model".to_string(),
         dyn_rate_min: Decimal256::zero(),
         dyn_rate_max: Decimal256::one(),
+        bad_debt_handler: None,
+        max_write_off_per_epoch: Uint256::zero(),
+        close_factor: Decimal256::one(),
+        liquidation_interval: 0,
+        margin_call_grace_period: 0,
+        hard_liquidation_ltv: Decimal256::one(),
     };
 
     // we can just call .unwrap() to assert this was a success
     let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
+    deps.querier.with_deposit_rate_model(&[(
+        &"deposit_rate_model".to_string(),
+        &(
+            Decimal256::from_str("0.03").unwrap(),
+            Decimal256::from_str("0.01").unwrap(),
+        ),
+    )]);
+
     let batom_collat_token = deps
         .api
         .addr_humanize(&CanonicalAddr::from(vec![
@@ -421,6 +544,9 @@ fn execute_epoch_operations() {
         collateral_token: bluna_collat_token,
         custody_contract: "custody_bluna".to_string(),
         max_ltv: Decimal256::percent(60),
+        liquidation_ltv: Decimal256::percent(60),
+        max_collateral_amount: None,
+        max_borrow_value: None,
     };
 
     let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
@@ -431,6 +557,9 @@ fn execute_epoch_operations() {
         collateral_token: batom_collat_token,
         custody_contract: "custody_batom".to_string(),
         max_ltv: Decimal256::percent(60),
+        liquidation_ltv: Decimal256::percent(60),
+        max_collateral_amount: None,
+        max_borrow_value: None,
     };
 
     let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
@@ -438,11 +567,11 @@ fn execute_epoch_operations() {
     let msg = ExecuteMsg::ExecuteEpochOperations {};
     let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone());
     match res {
-        Err(ContractError::EpochNotPassed(12345)) => (),
+        Err(ContractError::EpochNotPassed(1571797419)) => (),
         _ => panic!("DO NOT ENTER HERE"),
     }
 
-    env.block.height += 86400u64;
+    env.block.time = env.block.time.plus_seconds(86400u64);
 
     // If deposit_rate is bigger than threshold_deposit_rate
     deps.querier.with_epoch_state(&[(
@@ -474,20 +603,14 @@ fn execute_epoch_operations() {
                 funds: vec![],
                 msg: to_binary(&CustodyExecuteMsg::DistributeRewards {}).unwrap(),
             })),
-            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: "custody_bluna".to_string(),
-                funds: vec![],
-                msg: to_binary(&CustodyExecuteMsg::DistributeRewards {}).unwrap(),
-            })),
-            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
-                funds: vec![],
-                msg: to_binary(&ExecuteMsg::UpdateEpochState {
-                    interest_buffer: Uint256::from(8_000_000_000u128),
-                    distributed_interest: Uint256::zero(),
-                })
-                .unwrap(),
-            }))
+            SubMsg::reply_on_success(
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: "custody_bluna".to_string(),
+                    funds: vec![],
+                    msg: to_binary(&CustodyExecuteMsg::DistributeRewards {}).unwrap(),
+                }),
+                1
+            ),
         ]
     );
 
@@ -503,11 +626,88 @@ fn execute_epoch_operations() {
         ]
     );
 
+    // cannot re-enter before the reply is received
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+    match res {
+        Err(ContractError::EpochOperationInProgress {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let reply_msg = Reply {
+        id: 2,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: None,
+        }),
+    };
+    let res = reply(deps.as_mut(), env.clone(), reply_msg);
+    match res {
+        Err(ContractError::InvalidReplyId {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // store dynrate state for test purpose; keep the deposit rates unchanged
+    store_dynrate_state(
+        deps.as_mut().storage,
+        &DynrateState {
+            last_executed_time: env.block.time.seconds(),
+            prev_yield_reserve: Decimal256::zero(),
+            model_state: None,
+        },
+    )
+    .unwrap();
+
+    // epoch state is updated once the rewards are distributed
+    let reply_msg = Reply {
+        id: 1,
+        result: ContractResult::Ok(SubMsgExecutionResponse {
+            events: vec![],
+            data: None,
+        }),
+    };
+    let res = reply(deps.as_mut(), env.clone(), reply_msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "market".to_string(),
+            funds: vec![],
+            msg: to_binary(&MarketExecuteMsg::ExecuteEpochOperations {
+                deposit_rate: Decimal256::from_str("0.000002314814814814").unwrap(),
+                target_deposit_rate: Decimal256::from_ratio(1u64, 1000000u64),
+                threshold_deposit_rate: Decimal256::from_ratio(1u64, 1000000u64),
+                distributed_interest: Uint256::zero(),
+            })
+            .unwrap(),
+        }))]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "update_epoch_state"),
+            attr("deposit_rate", "0.000002314814814814"),
+            attr("aterra_supply", "1000000"),
+            attr("exchange_rate", "1.2"),
+            attr("interest_buffer", "8000000000"),
+        ]
+    );
+    assert_eq!(
+        read_epoch_state(deps.as_ref().storage)
+            .unwrap()
+            .last_executed_time,
+        env.block.time.seconds()
+    );
+
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+    match res {
+        Err(ContractError::EpochNotPassed(_)) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
     // store epoch state for test purpose
     store_epoch_state(
         deps.as_mut().storage,
         &EpochState {
-            last_executed_height: env.block.height,
+            last_executed_time: env.block.time.seconds(),
             prev_exchange_rate: Decimal256::from_str("1.2").unwrap(),
             prev_aterra_supply: Uint256::from_str("1000000").unwrap(),
             prev_interest_buffer: Uint256::from_str("9999000000").unwrap(),
@@ -527,7 +727,7 @@ fn execute_epoch_operations() {
         &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
     );
 
-    env.block.height += 86400u64;
+    env.block.time = env.block.time.plus_seconds(86400u64);
 
     // accrued_buffer = 1,000,000
     // interest_buffer = 9,999,000,000
@@ -564,20 +764,14 @@ fn execute_epoch_operations() {
                 funds: vec![],
                 msg: to_binary(&CustodyExecuteMsg::DistributeRewards {}).unwrap(),
             })),
-            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: "custody_bluna".to_string(),
-                funds: vec![],
-                msg: to_binary(&CustodyExecuteMsg::DistributeRewards {}).unwrap(),
-            })),
-            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
-                funds: vec![],
-                msg: to_binary(&ExecuteMsg::UpdateEpochState {
-                    interest_buffer: Uint256::from(9999746320u128),
-                    distributed_interest: Uint256::from(53148u128),
-                })
-                .unwrap(),
-            }))
+            SubMsg::reply_on_success(
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: "custody_bluna".to_string(),
+                    funds: vec![],
+                    msg: to_binary(&CustodyExecuteMsg::DistributeRewards {}).unwrap(),
+                }),
+                1
+            ),
         ]
     );
 
@@ -616,15 +810,28 @@ fn update_epoch_state() {
         anc_purchase_factor: Decimal256::percent(20),
         price_timeframe: 60u64,
         dyn_rate_epoch: 86400u64,
-        dyn_rate_maxchange: Decimal256::from_str("0.03").unwrap(),
-        dyn_rate_yr_increase_expectation: Decimal256::from_str("0.01").unwrap(),
+        deposit_rate_model: "deposit_rate_model".to_string(),
         dyn_rate_min: Decimal256::zero(),
         dyn_rate_max: Decimal256::one(),
+        bad_debt_handler: None,
+        max_write_off_per_epoch: Uint256::zero(),
+        close_factor: Decimal256::one(),
+        liquidation_interval: 0,
+        margin_call_grace_period: 0,
+        hard_liquidation_ltv: Decimal256::one(),
     };
 
     // we can just call .unwrap() to assert this was a success
     let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
 
+    deps.querier.with_deposit_rate_model(&[(
+        &"deposit_rate_model".to_string(),
+        &(
+            Decimal256::from_str("0.03").unwrap(),
+            Decimal256::from_str("0.01").unwrap(),
+        ),
+    )]);
+
     // store whitelist elems
     let msg = ExecuteMsg::Whitelist {
         name: "bluna".to_string(),
@@ -632,6 +839,9 @@ fn update_epoch_state() {
         collateral_token: "bluna".to_string(),
         custody_contract: "custody_bluna".to_string(),
         max_ltv: Decimal256::percent(60),
+        liquidation_ltv: Decimal256::percent(60),
+        max_collateral_amount: None,
+        max_borrow_value: None,
     };
 
     let _res = execute(deps.as_mut(), mock_env(), info.clone(), msg);
@@ -642,32 +852,28 @@ fn update_epoch_state() {
         collateral_token: "batom".to_string(),
         custody_contract: "custody_batom".to_string(),
         max_ltv: Decimal256::percent(60),
+        liquidation_ltv: Decimal256::percent(60),
+        max_collateral_amount: None,
+        max_borrow_value: None,
     };
 
-    let _res = execute(deps.as_mut(), mock_env(), info.clone(), msg);
+    let _res = execute(deps.as_mut(), mock_env(), info, msg);
 
-    // only contract itself can execute update_epoch_state
-    let msg = ExecuteMsg::UpdateEpochState {
+    let pending = PendingEpochOperation {
         interest_buffer: Uint256::from(10000000000u128),
         distributed_interest: Uint256::from(1000000u128),
     };
-    let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
-    match res {
-        Err(ContractError::Unauthorized {}) => (),
-        _ => panic!("DO NOT ENTER HERE"),
-    }
 
     // Assume execute epoch operation is executed
     let mut env = mock_env();
-    let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
-    env.block.height += 86400u64;
+    env.block.time = env.block.time.plus_seconds(86400u64);
 
     deps.querier.with_epoch_state(&[(
         &"market".to_string(),
         &(Uint256::from(1000000u64), Decimal256::percent(120)),
     )]);
 
-    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+    let res = epoch_operations_reply(deps.as_mut(), env.clone(), &pending).unwrap();
     assert_eq!(
         res.messages,
         vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
@@ -699,8 +905,8 @@ fn update_epoch_state() {
         &(Uint256::from(1000000u64), Decimal256::percent(125)),
     )]);
 
-    env.block.height += 86400u64;
-    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    env.block.time = env.block.time.plus_seconds(86400u64);
+    let res = epoch_operations_reply(deps.as_mut(), env.clone(), &pending).unwrap();
     assert_eq!(
         res.messages,
         vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
@@ -708,8 +914,8 @@ fn update_epoch_state() {
             funds: vec![],
             msg: to_binary(&MarketExecuteMsg::ExecuteEpochOperations {
                 deposit_rate: Decimal256::from_str("0.000000482253086419").unwrap(),
-                target_deposit_rate: Decimal256::from_str("0.000001006442178229").unwrap(),
-                threshold_deposit_rate: Decimal256::from_str("0.000001006442178229").unwrap(),
+                target_deposit_rate: Decimal256::from_str("0.000001000951293759").unwrap(),
+                threshold_deposit_rate: Decimal256::from_str("0.000001000951293759").unwrap(),
                 distributed_interest: Uint256::from(1000000u128),
             })
             .unwrap(),
@@ -729,7 +935,7 @@ fn update_epoch_state() {
     let epoch_state_response = query_epoch_state(
         deps.as_ref(),
         Addr::unchecked("market"),
-        env.block.height,
+        env.block.time.seconds(),
         None,
     )
     .unwrap();
@@ -743,13 +949,179 @@ fn update_epoch_state() {
             prev_aterra_supply: epoch_state_response.aterra_supply,
             prev_exchange_rate: epoch_state_response.exchange_rate,
             prev_interest_buffer: Uint256::from(10000000000u128),
-            last_executed_height: env.block.height,
+            last_executed_time: env.block.time.seconds(),
         }
     )
 }
 
 #[test]
-#[ignore = "deprecated functionality"]
+fn epoch_history() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(10000000000u128),
+    }]);
+
+    let info = mock_info("owner", &[]);
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        oracle_contract: "oracle".to_string(),
+        market_contract: "market".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        collector_contract: "collector".to_string(),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::from_ratio(1u64, 1000000u64),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+        dyn_rate_epoch: 172800u64,
+        deposit_rate_model: "deposit_rate_model".to_string(),
+        dyn_rate_min: Decimal256::zero(),
+        dyn_rate_max: Decimal256::one(),
+        bad_debt_handler: None,
+        max_write_off_per_epoch: Uint256::zero(),
+        close_factor: Decimal256::one(),
+        liquidation_interval: 0,
+        margin_call_grace_period: 0,
+        hard_liquidation_ltv: Decimal256::one(),
+    };
+
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    deps.querier.with_deposit_rate_model(&[(
+        &"deposit_rate_model".to_string(),
+        &(
+            Decimal256::from_str("0.03").unwrap(),
+            Decimal256::from_str("0.01").unwrap(),
+        ),
+    )]);
+
+    // no epoch has been recorded yet
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::EpochHistory {
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let history: EpochHistoryResponse = from_binary(&res).unwrap();
+    assert_eq!(history.epochs, vec![]);
+
+    deps.querier.with_epoch_state(&[(
+        &"market".to_string(),
+        &(Uint256::from(1000000u64), Decimal256::percent(120)),
+    )]);
+
+    let mut env = mock_env();
+    let pending = PendingEpochOperation {
+        interest_buffer: Uint256::from(10000000000u128),
+        distributed_interest: Uint256::from(1000000u128),
+    };
+
+    env.block.time = env.block.time.plus_seconds(86400u64);
+    epoch_operations_reply(deps.as_mut(), env.clone(), &pending).unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::EpochHistory {
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let history: EpochHistoryResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        history.epochs,
+        vec![EpochHistoryResponseElem {
+            epoch: 0,
+            height: env.block.height,
+            time: env.block.time.seconds(),
+            deposit_rate: Decimal256::from_str("0.000002314814814814").unwrap(),
+            exchange_rate: Decimal256::percent(120),
+            aterra_supply: Uint256::from(1000000u64),
+            interest_buffer: Uint256::from(10000000000u128),
+            distributed_interest: Uint256::from(1000000u128),
+            dynrate: None,
+        }]
+    );
+
+    // fill the ring buffer past its capacity; dynrate runs every other epoch
+    for _ in 1..(EPOCH_HISTORY_SIZE + 2) {
+        env.block.time = env.block.time.plus_seconds(86400u64);
+        epoch_operations_reply(deps.as_mut(), env.clone(), &pending).unwrap();
+    }
+
+    // the two oldest epochs got overwritten
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::EpochHistory {
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let history: EpochHistoryResponse = from_binary(&res).unwrap();
+    assert_eq!(history.epochs.len(), 10);
+    assert_eq!(history.epochs[0].epoch, 2);
+    assert_eq!(history.epochs[0].deposit_rate, Decimal256::zero());
+    assert_eq!(history.epochs[0].dynrate, None);
+    assert!(history.epochs[1].dynrate.is_some());
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::EpochHistory {
+            start_after: Some(0),
+            limit: Some(100),
+        },
+    )
+    .unwrap();
+    let history: EpochHistoryResponse = from_binary(&res).unwrap();
+    assert_eq!(history.epochs.len(), 30);
+    assert_eq!(history.epochs[0].epoch, 2);
+    assert_eq!(history.epochs[29].epoch, 31);
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::EpochHistory {
+            start_after: Some(97),
+            limit: None,
+        },
+    )
+    .unwrap();
+    let history: EpochHistoryResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        history
+            .epochs
+            .iter()
+            .map(|elem| elem.epoch)
+            .collect::<Vec<u64>>(),
+        vec![98, 99, 100, 101]
+    );
+
+    // the latest decision matches the stored config
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config_res: ConfigResponse = from_binary(&res).unwrap();
+    let prev_decision = history.epochs[1].dynrate.clone().unwrap();
+    let decision = history.epochs[3].dynrate.clone().unwrap();
+    assert_eq!(
+        decision,
+        DynrateDecision {
+            yield_reserve: Decimal256::from_uint256(Uint256::from(10000000000u128)),
+            prev_deposit_rate: prev_decision.threshold_deposit_rate,
+            threshold_deposit_rate: config_res.threshold_deposit_rate,
+            target_deposit_rate: config_res.target_deposit_rate,
+        }
+    );
+}
+
+#[test]
 fn lock_collateral() {
     let mut deps = mock_dependencies(&[]);
 
@@ -768,10 +1140,15 @@ fn lock_collateral() {
         anc_purchase_factor: Decimal256::percent(20),
         price_timeframe: 60u64,
         dyn_rate_epoch: 86400u64,
-        dyn_rate_maxchange: Decimal256::from_str("0.03").unwrap(),
-        dyn_rate_yr_increase_expectation: Decimal256::from_str("0.01").unwrap(),
+        deposit_rate_model: "deposit_rate_model".to_string(),
         dyn_rate_min: Decimal256::zero(),
         dyn_rate_max: Decimal256::one(),
+        bad_debt_handler: None,
+        max_write_off_per_epoch: Uint256::zero(),
+        close_factor: Decimal256::one(),
+        liquidation_interval: 0,
+        margin_call_grace_period: 0,
+        hard_liquidation_ltv: Decimal256::one(),
     };
 
     // we can just call .unwrap() to assert this was a success
@@ -802,6 +1179,9 @@ fn lock_collateral() {
         collateral_token: bluna_collat_token.clone(),
         custody_contract: "custody_bluna".to_string(),
         max_ltv: Decimal256::percent(60),
+        liquidation_ltv: Decimal256::percent(60),
+        max_collateral_amount: None,
+        max_borrow_value: None,
     };
 
     let _res = execute(deps.as_mut(), mock_env(), info.clone(), msg);
@@ -812,10 +1192,40 @@ fn lock_collateral() {
         collateral_token: batom_collat_token.clone(),
         custody_contract: "custody_batom".to_string(),
         max_ltv: Decimal256::percent(60),
+        liquidation_ltv: Decimal256::percent(60),
+        max_collateral_amount: None,
+        max_borrow_value: None,
     };
 
     let _res = execute(deps.as_mut(), mock_env(), info, msg);
 
+    // cannot lock a token which is not whitelisted
+    let msg = ExecuteMsg::LockCollateral {
+        collaterals: vec![
+            (bluna_collat_token.clone(), Uint256::from(1000000u64)),
+            ("bust".to_string(), Uint256::from(1000000u64)),
+        ],
+    };
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    match res {
+        Err(ContractError::Std(StdError::GenericErr { msg, .. })) => {
+            assert_eq!(msg, "Token is not registered as collateral")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // cannot lock zero amount
+    let msg = ExecuteMsg::LockCollateral {
+        collaterals: vec![(bluna_collat_token.clone(), Uint256::zero())],
+    };
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    match res {
+        Err(ContractError::ZeroLockAmount {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
     let msg = ExecuteMsg::LockCollateral {
         collaterals: vec![
             (bluna_collat_token.clone(), Uint256::from(1000000u64)),
@@ -927,10 +1337,15 @@ fn unlock_collateral() {
         anc_purchase_factor: Decimal256::percent(20),
         price_timeframe: 60u64,
         dyn_rate_epoch: 86400u64,
-        dyn_rate_maxchange: Decimal256::from_str("0.03").unwrap(),
-        dyn_rate_yr_increase_expectation: Decimal256::from_str("0.01").unwrap(),
+        deposit_rate_model: "deposit_rate_model".to_string(),
         dyn_rate_min: Decimal256::zero(),
         dyn_rate_max: Decimal256::one(),
+        bad_debt_handler: None,
+        max_write_off_per_epoch: Uint256::zero(),
+        close_factor: Decimal256::one(),
+        liquidation_interval: 0,
+        margin_call_grace_period: 0,
+        hard_liquidation_ltv: Decimal256::one(),
     };
 
     // we can just call .unwrap() to assert this was a success
@@ -943,6 +1358,9 @@ fn unlock_collateral() {
         collateral_token: "bluna".to_string(),
         custody_contract: "custody_bluna".to_string(),
         max_ltv: Decimal256::percent(60),
+        liquidation_ltv: Decimal256::percent(60),
+        max_collateral_amount: None,
+        max_borrow_value: None,
     };
 
     let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
@@ -953,6 +1371,9 @@ fn unlock_collateral() {
         collateral_token: "batom".to_string(),
         custody_contract: "custody_batom".to_string(),
         max_ltv: Decimal256::percent(60),
+        liquidation_ltv: Decimal256::percent(60),
+        max_collateral_amount: None,
+        max_borrow_value: None,
     };
 
     let _res = execute(deps.as_mut(), env.clone(), info, msg);
@@ -965,7 +1386,7 @@ fn unlock_collateral() {
     let info = mock_info("addr0000", &[]);
 
     // simulate lock collateral
-    _lock_collateral(deps.as_mut(), info.clone(), collaterals).unwrap();
+    _lock_collateral(deps.as_mut(), env.clone(), info.clone(), collaterals).unwrap();
 
     // Failed to unlock more than locked amount
     let msg = ExecuteMsg::UnlockCollateral {
@@ -973,6 +1394,7 @@ fn unlock_collateral() {
             ("bluna".to_string(), Uint256::from(1000001u64)),
             ("batom".to_string(), Uint256::from(10000001u64)),
         ],
+        borrower: None,
     };
     let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
     match res {
@@ -1008,6 +1430,7 @@ fn unlock_collateral() {
     // Failed to unlock more than locked amount
     let msg = ExecuteMsg::UnlockCollateral {
         collaterals: vec![("bluna".to_string(), Uint256::one())],
+        borrower: None,
     };
     let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
     match res {
@@ -1017,6 +1440,7 @@ fn unlock_collateral() {
 
     let msg = ExecuteMsg::UnlockCollateral {
         collaterals: vec![("batom".to_string(), Uint256::one())],
+        borrower: None,
     };
     let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
     match res {
@@ -1043,6 +1467,7 @@ fn unlock_collateral() {
     // Cannot unlock 2bluna
     let msg = ExecuteMsg::UnlockCollateral {
         collaterals: vec![("bluna".to_string(), Uint256::from(2u64))],
+        borrower: None,
     };
     let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
     match res {
@@ -1053,6 +1478,7 @@ fn unlock_collateral() {
     // Can unlock 1bluna
     let msg = ExecuteMsg::UnlockCollateral {
         collaterals: vec![("bluna".to_string(), Uint256::one())],
+        borrower: None,
     };
     let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
     assert_eq!(
@@ -1086,8 +1512,9 @@ fn unlock_collateral() {
             ("bluna".to_string(), Uint256::from(1u128)),
             ("batom".to_string(), Uint256::from(1u128)),
         ],
+        borrower: None,
     };
-    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
     assert_eq!(
         res.messages,
         vec![
@@ -1119,6 +1546,71 @@ fn unlock_collateral() {
             attr("collaterals", "1bluna,1batom"),
         ]
     );
+
+    // operators need an unlock approval from the borrower
+    let msg = ExecuteMsg::UnlockCollateral {
+        collaterals: vec![("bluna".to_string(), Uint256::one())],
+        borrower: Some("addr0000".to_string()),
+    };
+    let operator_info = mock_info("operator0000", &[]);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        operator_info.clone(),
+        msg.clone(),
+    );
+    match res {
+        Err(ContractError::Std(StdError::GenericErr { msg, .. })) => {
+            assert_eq!(msg, "unauthorized")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        ExecuteMsg::ApproveOperator {
+            operator: "operator0000".to_string(),
+            permissions: vec![OperatorPermission::Borrow],
+            borrow_allowance: None,
+            expires_at: None,
+        },
+    );
+    match res {
+        Err(ContractError::Std(StdError::GenericErr { msg, .. })) => {
+            assert_eq!(msg, "Unsupported operator permission: borrow")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let _res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        ExecuteMsg::ApproveOperator {
+            operator: "operator0000".to_string(),
+            permissions: vec![OperatorPermission::Unlock],
+            borrow_allowance: None,
+            expires_at: None,
+        },
+    )
+    .unwrap();
+
+    // the collaterals are released to the borrower
+    let res = execute(deps.as_mut(), env, operator_info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "custody_bluna".to_string(),
+            funds: vec![],
+            msg: to_binary(&CustodyExecuteMsg::UnlockCollateral {
+                borrower: "addr0000".to_string(),
+                amount: Uint256::one(),
+            })
+            .unwrap(),
+        }))]
+    );
 }
 
 #[test]
@@ -1143,10 +1635,15 @@ fn liquidate_collateral() {
         anc_purchase_factor: Decimal256::percent(20),
         price_timeframe: 60u64,
         dyn_rate_epoch: 86400u64,
-        dyn_rate_maxchange: Decimal256::from_str("0.03").unwrap(),
-        dyn_rate_yr_increase_expectation: Decimal256::from_str("0.01").unwrap(),
+        deposit_rate_model: "deposit_rate_model".to_string(),
         dyn_rate_min: Decimal256::zero(),
         dyn_rate_max: Decimal256::one(),
+        bad_debt_handler: None,
+        max_write_off_per_epoch: Uint256::zero(),
+        close_factor: Decimal256::one(),
+        liquidation_interval: 0,
+        margin_call_grace_period: 0,
+        hard_liquidation_ltv: Decimal256::one(),
     };
 
     // we can just call .unwrap() to assert this was a success
@@ -1177,6 +1674,9 @@ fn liquidate_collateral() {
         collateral_token: bluna_collat_token.clone(),
         custody_contract: "custody_bluna".to_string(),
         max_ltv: Decimal256::percent(60),
+        liquidation_ltv: Decimal256::percent(60),
+        max_collateral_amount: None,
+        max_borrow_value: None,
     };
 
     let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
@@ -1187,6 +1687,9 @@ fn liquidate_collateral() {
         collateral_token: batom_collat_token.clone(),
         custody_contract: "custody_batom".to_string(),
         max_ltv: Decimal256::percent(60),
+        liquidation_ltv: Decimal256::percent(60),
+        max_collateral_amount: None,
+        max_borrow_value: None,
     };
 
     let _res = execute(deps.as_mut(), env.clone(), info, msg);
@@ -1199,7 +1702,7 @@ fn liquidate_collateral() {
     let info = mock_info("addr0000", &[]);
 
     // simulate lock collateral
-    _lock_collateral(deps.as_mut(), info, collaterals).unwrap();
+    _lock_collateral(deps.as_mut(), env.clone(), info, collaterals).unwrap();
 
     deps.querier.with_oracle_price(&[
         (
@@ -1236,62 +1739,2306 @@ fn liquidate_collateral() {
     }
 
     deps.querier
-        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::from(12600000001u64))]);
-    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::from(12600000001u64))]);
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "custody_batom".to_string(),
+                funds: vec![],
+                msg: to_binary(&CustodyExecuteMsg::LiquidateCollateral {
+                    liquidator: "addr0001".to_string(),
+                    borrower: "addr0000".to_string(),
+                    amount: Uint256::from(100000u64),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "custody_bluna".to_string(),
+                funds: vec![],
+                msg: to_binary(&CustodyExecuteMsg::LiquidateCollateral {
+                    liquidator: "addr0001".to_string(),
+                    borrower: "addr0000".to_string(),
+                    amount: Uint256::from(10000u64),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "market".to_string(),
+                funds: vec![],
+                msg: to_binary(&MarketExecuteMsg::RepayStableFromLiquidation {
+                    borrower: "addr0000".to_string(),
+                    prev_balance: Uint256::zero(),
+                })
+                .unwrap(),
+            }))
+        ]
+    );
+
+    let res = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::Collaterals {
+            borrower: "addr0000".to_string(),
+        },
+    )
+    .unwrap();
+    let collaterals_res: CollateralsResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        collaterals_res,
+        CollateralsResponse {
+            borrower: "addr0000".to_string(),
+            collaterals: vec![
+                (batom_collat_token, Uint256::from(9900000u64)),
+                (bluna_collat_token, Uint256::from(990000u64)),
+            ]
+        }
+    );
+}
+
+#[test]
+fn liquidate_collateral_with_liquidation_ltv() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier
+        .with_liquidation_percent(&[(&"liquidation".to_string(), &Decimal256::percent(1))]);
+
+    let info = mock_info("owner", &[]);
+    let env = mock_env();
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        oracle_contract: "oracle".to_string(),
+        market_contract: "market".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        collector_contract: "collector".to_string(),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+        dyn_rate_epoch: 86400u64,
+        deposit_rate_model: "deposit_rate_model".to_string(),
+        dyn_rate_min: Decimal256::zero(),
+        dyn_rate_max: Decimal256::one(),
+        bad_debt_handler: None,
+        max_write_off_per_epoch: Uint256::zero(),
+        close_factor: Decimal256::one(),
+        liquidation_interval: 0,
+        margin_call_grace_period: 0,
+        hard_liquidation_ltv: Decimal256::one(),
+    };
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    // store whitelist elem
+    let msg = ExecuteMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: "bluna".to_string(),
+        custody_contract: "custody_bluna".to_string(),
+        max_ltv: Decimal256::percent(60),
+        liquidation_ltv: Decimal256::percent(80),
+        max_collateral_amount: None,
+        max_borrow_value: None,
+    };
+
+    let _res = execute(deps.as_mut(), env.clone(), info, msg);
+
+    // simulate lock collateral
+    let info = mock_info("addr0000", &[]);
+    _lock_collateral(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        vec![("bluna".to_string(), Uint256::from(1000u64))],
+    )
+    .unwrap();
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+
+    // borrow_limit = 1000 * 1000 * 0.6 = 600,000 uusd
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::BorrowLimit {
+            borrower: "addr0000".to_string(),
+            block_time: None,
+        },
+    )
+    .unwrap();
+    let borrow_limit_res: BorrowLimitResponse = from_binary(&res).unwrap();
+    assert_eq!(borrow_limit_res.borrow_limit, Uint256::from(600000u64));
+
+    // liquidation_limit = 1000 * 1000 * 0.8 = 800,000 uusd
+    deps.querier
+        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::from(800000u64))]);
+
+    let msg = ExecuteMsg::LiquidateCollateral {
+        borrower: "addr0000".to_string(),
+    };
+    let info = mock_info("addr0001", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+    match res {
+        Err(ContractError::CannotLiquidateSafeLoan {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    deps.querier
+        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::from(800001u64))]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "custody_bluna".to_string(),
+            funds: vec![],
+            msg: to_binary(&CustodyExecuteMsg::LiquidateCollateral {
+                liquidator: "addr0001".to_string(),
+                borrower: "addr0000".to_string(),
+                amount: Uint256::from(10u64),
+            })
+            .unwrap(),
+        }))
+    );
+}
+
+#[test]
+fn borrower_health() {
+    let mut deps = mock_dependencies(&[]);
+
+    let info = mock_info("owner", &[]);
+    let env = mock_env();
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        oracle_contract: "oracle".to_string(),
+        market_contract: "market".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        collector_contract: "collector".to_string(),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+        dyn_rate_epoch: 86400u64,
+        deposit_rate_model: "deposit_rate_model".to_string(),
+        dyn_rate_min: Decimal256::zero(),
+        dyn_rate_max: Decimal256::one(),
+        bad_debt_handler: None,
+        max_write_off_per_epoch: Uint256::zero(),
+        close_factor: Decimal256::one(),
+        liquidation_interval: 0,
+        margin_call_grace_period: 0,
+        hard_liquidation_ltv: Decimal256::one(),
+    };
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    // store whitelist elems
+    let msg = ExecuteMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: "bluna".to_string(),
+        custody_contract: "custody_bluna".to_string(),
+        max_ltv: Decimal256::percent(60),
+        liquidation_ltv: Decimal256::percent(80),
+        max_collateral_amount: None,
+        max_borrow_value: None,
+    };
+
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+
+    let msg = ExecuteMsg::Whitelist {
+        name: "batom".to_string(),
+        symbol: "batom".to_string(),
+        collateral_token: "batom".to_string(),
+        custody_contract: "custody_batom".to_string(),
+        max_ltv: Decimal256::percent(50),
+        liquidation_ltv: Decimal256::percent(60),
+        max_collateral_amount: None,
+        max_borrow_value: None,
+    };
+
+    let _res = execute(deps.as_mut(), env.clone(), info, msg);
+
+    // simulate lock collateral
+    let info = mock_info("addr0000", &[]);
+    _lock_collateral(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        vec![
+            ("bluna".to_string(), Uint256::from(1000u64)),
+            ("batom".to_string(), Uint256::from(1000u64)),
+        ],
+    )
+    .unwrap();
+
+    deps.querier.with_oracle_price(&[
+        (
+            &("bluna".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::from_ratio(1000u64, 1u64),
+                env.block.time.seconds(),
+                env.block.time.seconds(),
+            ),
+        ),
+        (
+            &("batom".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::from_ratio(3000u64, 1u64),
+                env.block.time.seconds(),
+                env.block.time.seconds(),
+            ),
+        ),
+    ]);
+    deps.querier
+        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::from(1300000u64))]);
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::BorrowerHealth {
+            borrower: "addr0000".to_string(),
+            block_time: Some(env.block.time.seconds()),
+        },
+    )
+    .unwrap();
+    let mut health_res: BorrowerHealthResponse = from_binary(&res).unwrap();
+    health_res
+        .collaterals
+        .sort_by(|a, b| a.collateral_token.cmp(&b.collateral_token));
+
+    // collaterals_value = 1000 * 1000 + 1000 * 3000 = 4,000,000 uusd
+    // borrow_limit = 1,000,000 * 0.6 + 3,000,000 * 0.5 = 2,100,000 uusd
+    // liquidation_limit = 1,000,000 * 0.8 + 3,000,000 * 0.6 = 2,600,000 uusd
+    assert_eq!(
+        health_res,
+        BorrowerHealthResponse {
+            borrower: "addr0000".to_string(),
+            collaterals_value: Uint256::from(4000000u64),
+            borrow_limit: Uint256::from(2100000u64),
+            liquidation_limit: Uint256::from(2600000u64),
+            loan_amount: Uint256::from(1300000u64),
+            ltv: Some(Decimal256::from_str("0.325").unwrap()),
+            liquidation_price_drop: Decimal256::percent(50),
+            collaterals: vec![
+                CollateralHealthResponse {
+                    collateral_token: "batom".to_string(),
+                    amount: Uint256::from(1000u64),
+                    price: Decimal256::from_ratio(3000u64, 1u64),
+                    value: Uint256::from(3000000u64),
+                    weight: Decimal256::percent(75),
+                    max_ltv: Decimal256::percent(50),
+                    liquidation_ltv: Decimal256::percent(60),
+                },
+                CollateralHealthResponse {
+                    collateral_token: "bluna".to_string(),
+                    amount: Uint256::from(1000u64),
+                    price: Decimal256::from_ratio(1000u64, 1u64),
+                    value: Uint256::from(1000000u64),
+                    weight: Decimal256::percent(25),
+                    max_ltv: Decimal256::percent(60),
+                    liquidation_ltv: Decimal256::percent(80),
+                },
+            ],
+        }
+    );
+
+    // a liquidatable loan has no room for a price drop
+    deps.querier.with_loan_amount(&[
+        (&"addr0000".to_string(), &Uint256::from(2600001u64)),
+        (&"addr0001".to_string(), &Uint256::zero()),
+    ]);
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::BorrowerHealth {
+            borrower: "addr0000".to_string(),
+            block_time: None,
+        },
+    )
+    .unwrap();
+    let health_res: BorrowerHealthResponse = from_binary(&res).unwrap();
+    assert_eq!(health_res.liquidation_price_drop, Decimal256::zero());
+
+    // a paused collateral keeps backing the existing loan
+    let msg = ExecuteMsg::PauseCollateral {
+        collateral_token: "bluna".to_string(),
+    };
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::BorrowerHealth {
+            borrower: "addr0000".to_string(),
+            block_time: None,
+        },
+    )
+    .unwrap();
+    let health_res: BorrowerHealthResponse = from_binary(&res).unwrap();
+    assert_eq!(health_res.borrow_limit, Uint256::from(2100000u64));
+    assert_eq!(health_res.collaterals[1].max_ltv, Decimal256::percent(60));
+
+    // borrower without collaterals
+    let res = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::BorrowerHealth {
+            borrower: "addr0001".to_string(),
+            block_time: None,
+        },
+    )
+    .unwrap();
+    let health_res: BorrowerHealthResponse = from_binary(&res).unwrap();
+    assert_eq!(health_res.collaterals_value, Uint256::zero());
+    assert_eq!(health_res.ltv, None);
+    assert_eq!(health_res.collaterals, vec![]);
+}
+
+#[test]
+fn liquidatable_borrowers() {
+    let mut deps = mock_dependencies(&[]);
+
+    let info = mock_info("owner", &[]);
+    let env = mock_env();
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        oracle_contract: "oracle".to_string(),
+        market_contract: "market".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        collector_contract: "collector".to_string(),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+        dyn_rate_epoch: 86400u64,
+        deposit_rate_model: "deposit_rate_model".to_string(),
+        dyn_rate_min: Decimal256::zero(),
+        dyn_rate_max: Decimal256::one(),
+        bad_debt_handler: None,
+        max_write_off_per_epoch: Uint256::zero(),
+        close_factor: Decimal256::one(),
+        liquidation_interval: 0,
+        margin_call_grace_period: 0,
+        hard_liquidation_ltv: Decimal256::one(),
+    };
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    // store whitelist elem
+    let msg = ExecuteMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: "bluna".to_string(),
+        custody_contract: "custody_bluna".to_string(),
+        max_ltv: Decimal256::percent(60),
+        liquidation_ltv: Decimal256::percent(80),
+        max_collateral_amount: None,
+        max_borrow_value: None,
+    };
+
+    let _res = execute(deps.as_mut(), env.clone(), info, msg);
+
+    // simulate lock collateral
+    for borrower in ["addr0000", "addr0001", "addr0002"].iter() {
+        _lock_collateral(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(borrower, &[]),
+            vec![("bluna".to_string(), Uint256::from(1000u64))],
+        )
+        .unwrap();
+    }
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+
+    // liquidation_limit = 1000 * 1000 * 0.8 = 800,000 uusd
+    deps.querier.with_loan_amount(&[
+        (&"addr0000".to_string(), &Uint256::from(800000u64)),
+        (&"addr0001".to_string(), &Uint256::from(800100u64)),
+        (&"addr0002".to_string(), &Uint256::from(900000u64)),
+    ]);
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::LiquidatableBorrowers {
+            start_after: None,
+            limit: Some(2u32),
+        },
+    )
+    .unwrap();
+    let first_page: LiquidatableBorrowersResponse = from_binary(&res).unwrap();
+    assert!(first_page.last_scanned_borrower.is_some());
+
+    let res = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::LiquidatableBorrowers {
+            start_after: first_page.last_scanned_borrower,
+            limit: Some(2u32),
+        },
+    )
+    .unwrap();
+    let second_page: LiquidatableBorrowersResponse = from_binary(&res).unwrap();
+    assert_eq!(second_page.last_scanned_borrower, None);
+
+    let mut borrowers: Vec<LiquidatableBorrowerResponse> = first_page
+        .borrowers
+        .into_iter()
+        .chain(second_page.borrowers.into_iter())
+        .collect();
+    borrowers.sort_by(|a, b| a.borrower.cmp(&b.borrower));
+    assert_eq!(
+        borrowers,
+        vec![
+            LiquidatableBorrowerResponse {
+                borrower: "addr0001".to_string(),
+                loan_amount: Uint256::from(800100u64),
+                liquidation_limit: Uint256::from(800000u64),
+                shortfall: Uint256::from(100u64),
+            },
+            LiquidatableBorrowerResponse {
+                borrower: "addr0002".to_string(),
+                loan_amount: Uint256::from(900000u64),
+                liquidation_limit: Uint256::from(800000u64),
+                shortfall: Uint256::from(100000u64),
+            },
+        ]
+    );
+}
+
+#[test]
+fn liquidate_collateral_with_close_factor() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier
+        .with_liquidation_percent(&[(&"liquidation".to_string(), &Decimal256::one())]);
+
+    let info = mock_info("owner", &[]);
+    let mut env = mock_env();
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        oracle_contract: "oracle".to_string(),
+        market_contract: "market".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        collector_contract: "collector".to_string(),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+        dyn_rate_epoch: 86400u64,
+        deposit_rate_model: "deposit_rate_model".to_string(),
+        dyn_rate_min: Decimal256::zero(),
+        dyn_rate_max: Decimal256::one(),
+        bad_debt_handler: None,
+        max_write_off_per_epoch: Uint256::zero(),
+        close_factor: Decimal256::one(),
+        liquidation_interval: 0,
+        margin_call_grace_period: 0,
+        hard_liquidation_ltv: Decimal256::one(),
+    };
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    let msg = ExecuteMsg::UpdateConfig {
+        oracle_contract: None,
+        liquidation_contract: None,
+        threshold_deposit_rate: None,
+        target_deposit_rate: None,
+        buffer_distribution_factor: None,
+        anc_purchase_factor: None,
+        epoch_period: None,
+        price_timeframe: None,
+        dyn_rate_epoch: None,
+        deposit_rate_model: None,
+        dyn_rate_min: None,
+        dyn_rate_max: None,
+        bad_debt_handler: None,
+        max_write_off_per_epoch: None,
+        close_factor: Some(Decimal256::zero()),
+        liquidation_interval: Some(100u64),
+        margin_call_grace_period: None,
+        hard_liquidation_ltv: None,
+    };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+    match res {
+        Err(ContractError::InvalidCloseFactor {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = ExecuteMsg::UpdateConfig {
+        oracle_contract: None,
+        liquidation_contract: None,
+        threshold_deposit_rate: None,
+        target_deposit_rate: None,
+        buffer_distribution_factor: None,
+        anc_purchase_factor: None,
+        epoch_period: None,
+        price_timeframe: None,
+        dyn_rate_epoch: None,
+        deposit_rate_model: None,
+        dyn_rate_min: None,
+        dyn_rate_max: None,
+        bad_debt_handler: None,
+        max_write_off_per_epoch: None,
+        close_factor: Some(Decimal256::percent(50)),
+        liquidation_interval: Some(100u64),
+        margin_call_grace_period: None,
+        hard_liquidation_ltv: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    // store whitelist elem
+    let msg = ExecuteMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: "bluna".to_string(),
+        custody_contract: "custody_bluna".to_string(),
+        max_ltv: Decimal256::percent(60),
+        liquidation_ltv: Decimal256::percent(80),
+        max_collateral_amount: None,
+        max_borrow_value: None,
+    };
+
+    let _res = execute(deps.as_mut(), env.clone(), info, msg);
+
+    // simulate lock collateral
+    let info = mock_info("addr0000", &[]);
+    _lock_collateral(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        vec![("bluna".to_string(), Uint256::from(1000u64))],
+    )
+    .unwrap();
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+    deps.querier
+        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::from(900000u64))]);
+
+    // the whole position is returned by the liquidation contract,
+    // but only 900,000 * 0.5 = 450,000 uusd worth of bluna can be liquidated
+    let msg = ExecuteMsg::LiquidateCollateral {
+        borrower: "addr0000".to_string(),
+    };
+    let info = mock_info("addr0001", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "custody_bluna".to_string(),
+            funds: vec![],
+            msg: to_binary(&CustodyExecuteMsg::LiquidateCollateral {
+                liquidator: "addr0001".to_string(),
+                borrower: "addr0000".to_string(),
+                amount: Uint256::from(450u64),
+            })
+            .unwrap(),
+        }))
+    );
+
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+    match res {
+        Err(ContractError::LiquidationTooSoon(height)) => {
+            assert_eq!(height, env.block.height + 100u64)
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    env.block.height += 100u64;
+    let _res = execute(deps.as_mut(), env, info, msg).unwrap();
+}
+
+#[test]
+fn margin_call() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier
+        .with_liquidation_percent(&[(&"liquidation".to_string(), &Decimal256::percent(1))]);
+
+    let info = mock_info("owner", &[]);
+    let mut env = mock_env();
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        oracle_contract: "oracle".to_string(),
+        market_contract: "market".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        collector_contract: "collector".to_string(),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+        dyn_rate_epoch: 86400u64,
+        deposit_rate_model: "deposit_rate_model".to_string(),
+        dyn_rate_min: Decimal256::zero(),
+        dyn_rate_max: Decimal256::one(),
+        bad_debt_handler: None,
+        max_write_off_per_epoch: Uint256::zero(),
+        close_factor: Decimal256::one(),
+        liquidation_interval: 0,
+        margin_call_grace_period: 3600u64,
+        hard_liquidation_ltv: Decimal256::percent(90),
+    };
+
+    let mut invalid_msg = msg.clone();
+    invalid_msg.hard_liquidation_ltv = Decimal256::percent(101);
+    let res = instantiate(deps.as_mut(), env.clone(), info.clone(), invalid_msg);
+    match res {
+        Err(ContractError::InvalidHardLiquidationLtv {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    // store whitelist elem
+    let msg = ExecuteMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: "bluna".to_string(),
+        custody_contract: "custody_bluna".to_string(),
+        max_ltv: Decimal256::percent(60),
+        liquidation_ltv: Decimal256::percent(80),
+        max_collateral_amount: None,
+        max_borrow_value: None,
+    };
+
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+
+    // the hard liquidation LTV stays between the liquidation LTVs and 1
+    for hard_liquidation_ltv in [Decimal256::percent(79), Decimal256::percent(101)].iter() {
+        let msg = ExecuteMsg::UpdateConfig {
+            oracle_contract: None,
+            liquidation_contract: None,
+            threshold_deposit_rate: None,
+            target_deposit_rate: None,
+            buffer_distribution_factor: None,
+            anc_purchase_factor: None,
+            epoch_period: None,
+            price_timeframe: None,
+            dyn_rate_epoch: None,
+            deposit_rate_model: None,
+            dyn_rate_min: None,
+            dyn_rate_max: None,
+            bad_debt_handler: None,
+            max_write_off_per_epoch: None,
+            close_factor: None,
+            liquidation_interval: None,
+            margin_call_grace_period: None,
+            hard_liquidation_ltv: Some(*hard_liquidation_ltv),
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+        match res {
+            Err(ContractError::InvalidHardLiquidationLtv {}) => (),
+            _ => panic!("DO NOT ENTER HERE"),
+        }
+    }
+
+    let msg = ExecuteMsg::UpdateWhitelist {
+        collateral_token: "bluna".to_string(),
+        custody_contract: None,
+        max_ltv: None,
+        liquidation_ltv: Some(Decimal256::percent(91)),
+        max_collateral_amount: None,
+        max_borrow_value: None,
+        max_ltv_ramp_seconds: None,
+    };
+    let res = execute(deps.as_mut(), env.clone(), info, msg);
+    match res {
+        Err(ContractError::InvalidLiquidationLtv {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // simulate lock collateral
+    let info = mock_info("addr0000", &[]);
+    _lock_collateral(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        vec![("bluna".to_string(), Uint256::from(1000u64))],
+    )
+    .unwrap();
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+
+    // liquidation_limit = 1000 * 1000 * 0.8 = 800,000 uusd
+    deps.querier
+        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::from(700000u64))]);
+
+    let flag_msg = ExecuteMsg::FlagBorrower {
+        borrower: "addr0000".to_string(),
+    };
+    let liquidate_msg = ExecuteMsg::LiquidateCollateral {
+        borrower: "addr0000".to_string(),
+    };
+    let info = mock_info("addr0001", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), flag_msg.clone());
+    match res {
+        Err(ContractError::CannotFlagSafeLoan {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    deps.querier
+        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::from(850000u64))]);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        liquidate_msg.clone(),
+    );
+    match res {
+        Err(ContractError::BorrowerNotFlagged {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), flag_msg.clone()).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "flag_borrower"),
+            attr("borrower", "addr0000"),
+            attr("flagged_time", env.block.time.seconds().to_string()),
+        ]
+    );
+
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), flag_msg.clone());
+    match res {
+        Err(ContractError::BorrowerAlreadyFlagged {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::MarginCall {
+            borrower: "addr0000".to_string(),
+        },
+    )
+    .unwrap();
+    let margin_call_res: MarginCallResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        margin_call_res,
+        MarginCallResponse {
+            borrower: "addr0000".to_string(),
+            flagged_time: Some(env.block.time.seconds()),
+            grace_period_end: Some(env.block.time.seconds() + 3600u64),
+        }
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        liquidate_msg.clone(),
+    );
+    match res {
+        Err(ContractError::GracePeriodNotPassed(grace_period_end)) => {
+            assert_eq!(grace_period_end, env.block.time.seconds() + 3600u64)
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // above the hard liquidation LTV the grace period does not apply,
+    // and the liquidation clears the margin call
+    deps.querier
+        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::from(900001u64))]);
+    let _res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        liquidate_msg.clone(),
+    )
+    .unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::MarginCall {
+            borrower: "addr0000".to_string(),
+        },
+    )
+    .unwrap();
+    let margin_call_res: MarginCallResponse = from_binary(&res).unwrap();
+    assert_eq!(margin_call_res.flagged_time, None);
+
+    // once the grace period passed the flagged borrower can be liquidated
+    // liquidation_limit = 990 * 1000 * 0.8 = 792,000 uusd
+    deps.querier
+        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::from(850000u64))]);
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), flag_msg.clone()).unwrap();
+
+    env.block.time = env.block.time.plus_seconds(3600u64);
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), liquidate_msg).unwrap();
+
+    // a lock which makes the loan safe clears the margin call
+    // liquidation_limit = 981 * 1000 * 0.8 = 784,800 uusd
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), flag_msg.clone()).unwrap();
+    _lock_collateral(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[]),
+        vec![("bluna".to_string(), Uint256::from(100u64))],
+    )
+    .unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::MarginCall {
+            borrower: "addr0000".to_string(),
+        },
+    )
+    .unwrap();
+    let margin_call_res: MarginCallResponse = from_binary(&res).unwrap();
+    assert_eq!(margin_call_res.flagged_time, None);
+
+    // the market clears the margin call after a repayment makes the loan safe
+    // liquidation_limit = 1081 * 1000 * 0.8 = 864,800 uusd
+    deps.querier
+        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::from(900000u64))]);
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), flag_msg.clone()).unwrap();
+
+    let clear_msg = ExecuteMsg::ClearMarginCall {
+        borrower: "addr0000".to_string(),
+    };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), clear_msg.clone());
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let market_info = mock_info("market", &[]);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        market_info.clone(),
+        clear_msg.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "clear_margin_call"),
+            attr("borrower", "addr0000"),
+            attr("cleared", "false"),
+        ]
+    );
+
+    deps.querier
+        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::from(700000u64))]);
+    let res = execute(deps.as_mut(), env.clone(), market_info, clear_msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "clear_margin_call"),
+            attr("borrower", "addr0000"),
+            attr("cleared", "true"),
+        ]
+    );
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::MarginCall {
+            borrower: "addr0000".to_string(),
+        },
+    )
+    .unwrap();
+    let margin_call_res: MarginCallResponse = from_binary(&res).unwrap();
+    assert_eq!(margin_call_res.flagged_time, None);
+
+    // the flag is cleared once the loan is safe again
+    deps.querier
+        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::from(900000u64))]);
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), flag_msg.clone()).unwrap();
+
+    deps.querier
+        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::from(700000u64))]);
+    let res = execute(deps.as_mut(), env.clone(), info, flag_msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "unflag_borrower"),
+            attr("borrower", "addr0000"),
+        ]
+    );
+
+    let res = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::MarginCall {
+            borrower: "addr0000".to_string(),
+        },
+    )
+    .unwrap();
+    let margin_call_res: MarginCallResponse = from_binary(&res).unwrap();
+    assert_eq!(margin_call_res.flagged_time, None);
+}
+
+#[test]
+fn collateral_caps() {
+    let mut deps = mock_dependencies(&[]);
+
+    let info = mock_info("owner", &[]);
+    let env = mock_env();
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        oracle_contract: "oracle".to_string(),
+        market_contract: "market".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        collector_contract: "collector".to_string(),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+        dyn_rate_epoch: 86400u64,
+        deposit_rate_model: "deposit_rate_model".to_string(),
+        dyn_rate_min: Decimal256::zero(),
+        dyn_rate_max: Decimal256::one(),
+        bad_debt_handler: None,
+        max_write_off_per_epoch: Uint256::zero(),
+        close_factor: Decimal256::one(),
+        liquidation_interval: 0,
+        margin_call_grace_period: 0,
+        hard_liquidation_ltv: Decimal256::one(),
+    };
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    // store whitelist elem
+    let msg = ExecuteMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: "bluna".to_string(),
+        custody_contract: "custody_bluna".to_string(),
+        max_ltv: Decimal256::percent(50),
+        liquidation_ltv: Decimal256::percent(50),
+        max_collateral_amount: Some(Uint256::from(1000u64)),
+        max_borrow_value: Some(Uint256::from(300000u64)),
+    };
+
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+    deps.querier.with_loan_amount(&[
+        (&"addr0000".to_string(), &Uint256::zero()),
+        (&"addr0001".to_string(), &Uint256::zero()),
+    ]);
+
+    let msg = ExecuteMsg::LockCollateral {
+        collaterals: vec![("bluna".to_string(), Uint256::from(600u64))],
+    };
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg).unwrap();
+
+    let msg = ExecuteMsg::LockCollateral {
+        collaterals: vec![("bluna".to_string(), Uint256::from(500u64))],
+    };
+    let res = execute(deps.as_mut(), env.clone(), mock_info("addr0001", &[]), msg);
+    match res {
+        Err(ContractError::MaxCollateralAmountExceeded(1000)) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = ExecuteMsg::LockCollateral {
+        collaterals: vec![("bluna".to_string(), Uint256::from(400u64))],
+    };
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("addr0001", &[]), msg).unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::CollateralUsage {
+            collateral_token: None,
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let usage_res: CollateralUsageResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        usage_res,
+        CollateralUsageResponse {
+            elems: vec![CollateralUsageResponseElem {
+                collateral_token: "bluna".to_string(),
+                total_amount: Uint256::from(1000u64),
+                max_collateral_amount: Some(Uint256::from(1000u64)),
+                borrow_value: Uint256::from(500000u64),
+                max_borrow_value: Some(Uint256::from(300000u64)),
+            }],
+        }
+    );
+
+    // the borrow value cap is enforced by the market on new borrows,
+    // borrow limits stay 600 * 1000 * 0.5 = 300,000 uusd
+    let query_msg = QueryMsg::BorrowLimit {
+        borrower: "addr0000".to_string(),
+        block_time: None,
+    };
+    let res = query(deps.as_ref(), env.clone(), query_msg.clone()).unwrap();
+    let borrow_limit_res: BorrowLimitResponse = from_binary(&res).unwrap();
+    assert_eq!(borrow_limit_res.borrow_limit, Uint256::from(300000u64));
+
+    // unlocked collaterals are removed from the total
+    let msg = ExecuteMsg::UnlockCollateral {
+        collaterals: vec![("bluna".to_string(), Uint256::from(400u64))],
+        borrower: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("addr0001", &[]), msg).unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::CollateralUsage {
+            collateral_token: Some("bluna".to_string()),
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let usage_res: CollateralUsageResponse = from_binary(&res).unwrap();
+    assert_eq!(usage_res.elems[0].total_amount, Uint256::from(600u64));
+    assert_eq!(usage_res.elems[0].borrow_value, Uint256::from(300000u64));
+
+    let msg = ExecuteMsg::LockCollateral {
+        collaterals: vec![("bluna".to_string(), Uint256::from(400u64))],
+    };
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("addr0001", &[]), msg).unwrap();
+
+    // zero caps remove the caps
+    let msg = ExecuteMsg::UpdateWhitelist {
+        collateral_token: "bluna".to_string(),
+        custody_contract: None,
+        max_ltv: None,
+        liquidation_ltv: None,
+        max_collateral_amount: Some(Uint256::zero()),
+        max_borrow_value: Some(Uint256::zero()),
+        max_ltv_ramp_seconds: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let res = query(deps.as_ref(), env.clone(), query_msg).unwrap();
+    let borrow_limit_res: BorrowLimitResponse = from_binary(&res).unwrap();
+    assert_eq!(borrow_limit_res.borrow_limit, Uint256::from(300000u64));
+
+    let msg = ExecuteMsg::LockCollateral {
+        collaterals: vec![("bluna".to_string(), Uint256::from(500u64))],
+    };
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("addr0001", &[]), msg).unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::CollateralUsage {
+            collateral_token: Some("bluna".to_string()),
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let usage_res: CollateralUsageResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        usage_res,
+        CollateralUsageResponse {
+            elems: vec![CollateralUsageResponseElem {
+                collateral_token: "bluna".to_string(),
+                total_amount: Uint256::from(1500u64),
+                max_collateral_amount: None,
+                borrow_value: Uint256::from(750000u64),
+                max_borrow_value: None,
+            }],
+        }
+    );
+}
+
+#[test]
+fn whitelist_status() {
+    let mut deps = mock_dependencies(&[]);
+
+    let info = mock_info("owner", &[]);
+    let mut env = mock_env();
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        oracle_contract: "oracle".to_string(),
+        market_contract: "market".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        collector_contract: "collector".to_string(),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+        dyn_rate_epoch: 86400u64,
+        deposit_rate_model: "deposit_rate_model".to_string(),
+        dyn_rate_min: Decimal256::zero(),
+        dyn_rate_max: Decimal256::one(),
+        bad_debt_handler: None,
+        max_write_off_per_epoch: Uint256::zero(),
+        close_factor: Decimal256::one(),
+        liquidation_interval: 0,
+        margin_call_grace_period: 0,
+        hard_liquidation_ltv: Decimal256::one(),
+    };
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    // store whitelist elem
+    let msg = ExecuteMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: "bluna".to_string(),
+        custody_contract: "custody_bluna".to_string(),
+        max_ltv: Decimal256::percent(50),
+        liquidation_ltv: Decimal256::percent(50),
+        max_collateral_amount: None,
+        max_borrow_value: None,
+    };
+
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+    deps.querier
+        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::zero())]);
+
+    let lock_msg = ExecuteMsg::LockCollateral {
+        collaterals: vec![("bluna".to_string(), Uint256::from(1000u64))],
+    };
+    let _res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[]),
+        lock_msg.clone(),
+    )
+    .unwrap();
+
+    let msg = ExecuteMsg::PauseCollateral {
+        collateral_token: "bluna".to_string(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    );
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "update_whitelist_status"),
+            attr("collateral_token", "bluna"),
+            attr("status", "paused"),
+        ]
+    );
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::Whitelist {
+            collateral_token: Some("bluna".to_string()),
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let whitelist_res: WhitelistResponse = from_binary(&res).unwrap();
+    assert_eq!(whitelist_res.elems[0].status, WhitelistStatus::Paused);
+
+    // paused collaterals cannot be locked or borrowed against
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[]),
+        lock_msg.clone(),
+    );
+    match res {
+        Err(ContractError::CollateralPaused {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let borrow_limit_msg = QueryMsg::BorrowLimit {
+        borrower: "addr0000".to_string(),
+        block_time: None,
+    };
+    let res = query(deps.as_ref(), env.clone(), borrow_limit_msg.clone()).unwrap();
+    let borrow_limit_res: BorrowLimitResponse = from_binary(&res).unwrap();
+    assert_eq!(borrow_limit_res.borrow_limit, Uint256::zero());
+
+    // but they keep backing the loan, so unlocks are still allowed
+    deps.querier
+        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::from(200000u64))]);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::UnlockCollateral {
+            collaterals: vec![("bluna".to_string(), Uint256::from(700u64))],
+            borrower: None,
+        },
+    );
+    match res {
+        Err(ContractError::UnlockTooLarge(150000)) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    let _res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::UnlockCollateral {
+            collaterals: vec![("bluna".to_string(), Uint256::from(600u64))],
+            borrower: None,
+        },
+    )
+    .unwrap();
+    deps.querier
+        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::zero())]);
+
+    let msg = ExecuteMsg::ActivateCollateral {
+        collateral_token: "bluna".to_string(),
+    };
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    let _res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[]),
+        ExecuteMsg::LockCollateral {
+            collaterals: vec![("bluna".to_string(), Uint256::from(600u64))],
+        },
+    )
+    .unwrap();
+
+    let res = query(deps.as_ref(), env.clone(), borrow_limit_msg.clone()).unwrap();
+    let borrow_limit_res: BorrowLimitResponse = from_binary(&res).unwrap();
+    assert_eq!(borrow_limit_res.borrow_limit, Uint256::from(500000u64));
+
+    // max_ltv ramps down to zero over 100 seconds
+    let msg = ExecuteMsg::DeprecateCollateral {
+        collateral_token: "bluna".to_string(),
+        ramp_seconds: 100u64,
+    };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "update_whitelist_status"),
+            attr("collateral_token", "bluna"),
+            attr(
+                "status",
+                format!("deprecated_until_{}", env.block.time.seconds() + 100u64)
+            ),
+        ]
+    );
+
+    env.block.time = env.block.time.plus_seconds(50);
+    let res = query(deps.as_ref(), env.clone(), borrow_limit_msg.clone()).unwrap();
+    let borrow_limit_res: BorrowLimitResponse = from_binary(&res).unwrap();
+    assert_eq!(borrow_limit_res.borrow_limit, Uint256::from(250000u64));
+
+    env.block.time = env.block.time.plus_seconds(50);
+    let res = query(deps.as_ref(), env.clone(), borrow_limit_msg).unwrap();
+    let borrow_limit_res: BorrowLimitResponse = from_binary(&res).unwrap();
+    assert_eq!(borrow_limit_res.borrow_limit, Uint256::zero());
+
+    let delist_msg = ExecuteMsg::Delist {
+        collateral_token: "bluna".to_string(),
+    };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), delist_msg.clone());
+    match res {
+        Err(ContractError::CannotDelistLockedCollateral {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = ExecuteMsg::UnlockCollateral {
+        collaterals: vec![("bluna".to_string(), Uint256::from(1000u64))],
+        borrower: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg).unwrap();
+
+    let res = execute(deps.as_mut(), env.clone(), info, delist_msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![attr("action", "delist"), attr("collateral_token", "bluna"),]
+    );
+
+    let res = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::Whitelist {
+            collateral_token: Some("bluna".to_string()),
+            start_after: None,
+            limit: None,
+        },
+    );
+    assert!(res.is_err());
+}
+
+#[test]
+fn max_ltv_ramp() {
+    let mut deps = mock_dependencies(&[]);
+
+    let info = mock_info("owner", &[]);
+    let mut env = mock_env();
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        oracle_contract: "oracle".to_string(),
+        market_contract: "market".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        collector_contract: "collector".to_string(),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+        dyn_rate_epoch: 86400u64,
+        deposit_rate_model: "deposit_rate_model".to_string(),
+        dyn_rate_min: Decimal256::zero(),
+        dyn_rate_max: Decimal256::one(),
+        bad_debt_handler: None,
+        max_write_off_per_epoch: Uint256::zero(),
+        close_factor: Decimal256::one(),
+        liquidation_interval: 0,
+        margin_call_grace_period: 0,
+        hard_liquidation_ltv: Decimal256::one(),
+    };
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    // store whitelist elem
+    let msg = ExecuteMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: "bluna".to_string(),
+        custody_contract: "custody_bluna".to_string(),
+        max_ltv: Decimal256::percent(60),
+        liquidation_ltv: Decimal256::percent(80),
+        max_collateral_amount: None,
+        max_borrow_value: None,
+    };
+
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+
+    let msg = ExecuteMsg::LockCollateral {
+        collaterals: vec![("bluna".to_string(), Uint256::from(1000u64))],
+    };
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg).unwrap();
+
+    // max_ltv moves from 60% to 40% over 100 seconds
+    let msg = ExecuteMsg::UpdateWhitelist {
+        collateral_token: "bluna".to_string(),
+        custody_contract: None,
+        max_ltv: Some(Decimal256::percent(40)),
+        liquidation_ltv: None,
+        max_collateral_amount: None,
+        max_borrow_value: None,
+        max_ltv_ramp_seconds: Some(100u64),
+    };
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::Whitelist {
+            collateral_token: Some("bluna".to_string()),
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let whitelist_res: WhitelistResponse = from_binary(&res).unwrap();
+    assert_eq!(whitelist_res.elems[0].max_ltv, Decimal256::percent(40));
+    assert_eq!(
+        whitelist_res.elems[0].max_ltv_ramp,
+        Some(MaxLtvRamp {
+            start_ltv: Decimal256::percent(60),
+            start_time: env.block.time.seconds(),
+            end_time: env.block.time.seconds() + 100u64,
+        })
+    );
+
+    let borrow_limit_msg = QueryMsg::BorrowLimit {
+        borrower: "addr0000".to_string(),
+        block_time: None,
+    };
+    let res = query(deps.as_ref(), env.clone(), borrow_limit_msg.clone()).unwrap();
+    let borrow_limit_res: BorrowLimitResponse = from_binary(&res).unwrap();
+    assert_eq!(borrow_limit_res.borrow_limit, Uint256::from(600000u64));
+
+    env.block.time = env.block.time.plus_seconds(50);
+    let res = query(deps.as_ref(), env.clone(), borrow_limit_msg.clone()).unwrap();
+    let borrow_limit_res: BorrowLimitResponse = from_binary(&res).unwrap();
+    assert_eq!(borrow_limit_res.borrow_limit, Uint256::from(500000u64));
+
+    // a new ramp starts from the interpolated value and
+    // must stay below the liquidation LTV
+    let msg = ExecuteMsg::UpdateWhitelist {
+        collateral_token: "bluna".to_string(),
+        custody_contract: None,
+        max_ltv: Some(Decimal256::percent(30)),
+        liquidation_ltv: Some(Decimal256::percent(45)),
+        max_collateral_amount: None,
+        max_borrow_value: None,
+        max_ltv_ramp_seconds: Some(100u64),
+    };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+    match res {
+        Err(ContractError::InvalidLiquidationLtv {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    env.block.time = env.block.time.plus_seconds(50);
+    let res = query(deps.as_ref(), env.clone(), borrow_limit_msg.clone()).unwrap();
+    let borrow_limit_res: BorrowLimitResponse = from_binary(&res).unwrap();
+    assert_eq!(borrow_limit_res.borrow_limit, Uint256::from(400000u64));
+
+    // without a ramp the change applies at once
+    let msg = ExecuteMsg::UpdateWhitelist {
+        collateral_token: "bluna".to_string(),
+        custody_contract: None,
+        max_ltv: Some(Decimal256::percent(50)),
+        liquidation_ltv: None,
+        max_collateral_amount: None,
+        max_borrow_value: None,
+        max_ltv_ramp_seconds: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let res = query(deps.as_ref(), env.clone(), borrow_limit_msg).unwrap();
+    let borrow_limit_res: BorrowLimitResponse = from_binary(&res).unwrap();
+    assert_eq!(borrow_limit_res.borrow_limit, Uint256::from(500000u64));
+
+    let res = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::Whitelist {
+            collateral_token: Some("bluna".to_string()),
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let whitelist_res: WhitelistResponse = from_binary(&res).unwrap();
+    assert_eq!(whitelist_res.elems[0].max_ltv_ramp, None);
+}
+
+#[test]
+fn liquidate_collaterals() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier
+        .with_liquidation_percent(&[(&"liquidation".to_string(), &Decimal256::percent(1))]);
+
+    let info = mock_info("owner", &[]);
+    let env = mock_env();
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        oracle_contract: "oracle".to_string(),
+        market_contract: "market".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        collector_contract: "collector".to_string(),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+        dyn_rate_epoch: 86400u64,
+        deposit_rate_model: "deposit_rate_model".to_string(),
+        dyn_rate_min: Decimal256::zero(),
+        dyn_rate_max: Decimal256::one(),
+        bad_debt_handler: None,
+        max_write_off_per_epoch: Uint256::zero(),
+        close_factor: Decimal256::one(),
+        liquidation_interval: 0,
+        margin_call_grace_period: 0,
+        hard_liquidation_ltv: Decimal256::one(),
+    };
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    // store whitelist elem
+    let msg = ExecuteMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: "bluna".to_string(),
+        custody_contract: "custody_bluna".to_string(),
+        max_ltv: Decimal256::percent(60),
+        liquidation_ltv: Decimal256::percent(80),
+        max_collateral_amount: None,
+        max_borrow_value: None,
+    };
+
+    let _res = execute(deps.as_mut(), env.clone(), info, msg);
+
+    // simulate lock collateral
+    for borrower in ["addr0000", "addr0001"].iter() {
+        _lock_collateral(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(borrower, &[]),
+            vec![("bluna".to_string(), Uint256::from(1000u64))],
+        )
+        .unwrap();
+    }
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+
+    // liquidation_limit = 1000 * 1000 * 0.8 = 800,000 uusd
+    deps.querier.with_loan_amount(&[
+        (&"addr0000".to_string(), &Uint256::from(800000u64)),
+        (&"addr0001".to_string(), &Uint256::from(800001u64)),
+    ]);
+
+    let msg = ExecuteMsg::LiquidateCollaterals {
+        borrowers: vec![
+            "addr0000".to_string(),
+            "addr0001".to_string(),
+            "addr0001".to_string(),
+        ],
+    };
+    let info = mock_info("addr0002", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+            funds: vec![],
+            msg: to_binary(&ExecuteMsg::ExecuteLiquidation {
+                liquidator: "addr0002".to_string(),
+                borrower: "addr0001".to_string(),
+                collaterals: vec![("bluna".to_string(), Uint256::from(10u64))],
+            })
+            .unwrap(),
+        }))]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "liquidate_collaterals"),
+            attr("borrower", "addr0000"),
+            attr("result", "skipped"),
+            attr("borrower", "addr0001"),
+            attr("result", "liquidated"),
+            attr("collaterals", "10bluna"),
+            attr("borrower", "addr0001"),
+            attr("result", "skipped"),
+        ]
+    );
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::Collaterals {
+            borrower: "addr0001".to_string(),
+        },
+    )
+    .unwrap();
+    let collaterals_res: CollateralsResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        collaterals_res.collaterals,
+        vec![("bluna".to_string(), Uint256::from(990u64))]
+    );
+
+    let msg = ExecuteMsg::ExecuteLiquidation {
+        liquidator: "addr0002".to_string(),
+        borrower: "addr0001".to_string(),
+        collaterals: vec![("bluna".to_string(), Uint256::from(10u64))],
+    };
+    let info = mock_info("addr0002", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, msg.clone());
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "custody_bluna".to_string(),
+                funds: vec![],
+                msg: to_binary(&CustodyExecuteMsg::LiquidateCollateral {
+                    liquidator: "addr0002".to_string(),
+                    borrower: "addr0001".to_string(),
+                    amount: Uint256::from(10u64),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "market".to_string(),
+                funds: vec![],
+                msg: to_binary(&MarketExecuteMsg::RepayStableFromLiquidation {
+                    borrower: "addr0001".to_string(),
+                    prev_balance: Uint256::zero(),
+                })
+                .unwrap(),
+            })),
+        ]
+    );
+}
+
+#[test]
+fn repay_stable_from_yield_reserve() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(10000000000u128),
+    }]);
+
+    let info = mock_info("owner", &[]);
+    let mut env = mock_env();
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        oracle_contract: "oracle".to_string(),
+        market_contract: "market".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        collector_contract: "collector".to_string(),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+        dyn_rate_epoch: 86400u64,
+        deposit_rate_model: "deposit_rate_model".to_string(),
+        dyn_rate_min: Decimal256::zero(),
+        dyn_rate_max: Decimal256::one(),
+        bad_debt_handler: Some("handler".to_string()),
+        max_write_off_per_epoch: Uint256::from(1000000u64),
+        close_factor: Decimal256::one(),
+        liquidation_interval: 0,
+        margin_call_grace_period: 0,
+        hard_liquidation_ltv: Decimal256::one(),
+    };
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    // store whitelist elem
+    let msg = ExecuteMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: "bluna".to_string(),
+        custody_contract: "custody_bluna".to_string(),
+        max_ltv: Decimal256::percent(60),
+        liquidation_ltv: Decimal256::percent(60),
+        max_collateral_amount: None,
+        max_borrow_value: None,
+    };
+
+    let _res = execute(deps.as_mut(), env.clone(), info, msg);
+
+    // simulate lock collateral
+    let info = mock_info("addr0000", &[]);
+    _lock_collateral(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        vec![("bluna".to_string(), Uint256::from(1000u64))],
+    )
+    .unwrap();
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+    deps.querier
+        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::from(1500000u64))]);
+
+    let msg = ExecuteMsg::RepayStableFromYieldReserve {
+        borrower: "addr0000".to_string(),
+    };
+
+    // only the owner or the bad debt handler can write off
+    let info = mock_info("addr0001", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, msg.clone());
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // collaterals value 1000000 still covers the loan
+    deps.querier
+        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
+    let info = mock_info("handler", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+    match res {
+        Err(ContractError::CannotWriteOffSolventLoan {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the loan is under water, write off up to the epoch cap
+    deps.querier
+        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::from(1500000u64))]);
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "market".to_string(),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(1000000u64),
+                }],
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "market".to_string(),
+                funds: vec![],
+                msg: to_binary(&MarketExecuteMsg::RepayStableFromLiquidation {
+                    borrower: "addr0000".to_string(),
+                    prev_balance: Uint256::zero(),
+                })
+                .unwrap(),
+            })),
+        ]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "repay_stable_from_yield_reserve"),
+            attr("borrower", "addr0000"),
+            attr("write_off_amount", "1000000"),
+        ]
+    );
+
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+    match res {
+        Err(ContractError::WriteOffCapReached(time)) => {
+            assert_eq!(time, env.block.time.seconds() + 86400u64)
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the cap is restored in the next epoch
+    env.block.time = env.block.time.plus_seconds(86400u64);
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "repay_stable_from_yield_reserve"),
+            attr("borrower", "addr0000"),
+            attr("write_off_amount", "1000000"),
+        ]
+    );
+
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::WriteOffState {}).unwrap();
+    let write_off_state: WriteOffState = from_binary(&res).unwrap();
+    assert_eq!(
+        write_off_state,
+        WriteOffState {
+            epoch_start_time: env.block.time.seconds(),
+            epoch_written_off: Uint256::from(1000000u64),
+            total_written_off: Uint256::from(2000000u64),
+        }
+    );
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::WriteOffs {
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let write_offs_res: WriteOffsResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        write_offs_res,
+        WriteOffsResponse {
+            write_offs: vec![WriteOffResponse {
+                borrower: "addr0000".to_string(),
+                amount: Uint256::from(2000000u64),
+                last_written_off_height: env.block.height,
+            }]
+        }
+    );
+}
+
+#[test]
+fn fund_and_withdraw_reserve() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(10000000000u128),
+    }]);
+
+    let info = mock_info("owner", &[]);
+    let env = mock_env();
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        oracle_contract: "oracle".to_string(),
+        market_contract: "market".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        collector_contract: "collector".to_string(),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+        dyn_rate_epoch: 86400u64,
+        deposit_rate_model: "deposit_rate_model".to_string(),
+        dyn_rate_min: Decimal256::zero(),
+        dyn_rate_max: Decimal256::one(),
+        bad_debt_handler: None,
+        max_write_off_per_epoch: Uint256::zero(),
+        close_factor: Decimal256::one(),
+        liquidation_interval: 0,
+        margin_call_grace_period: 0,
+        hard_liquidation_ltv: Decimal256::one(),
+    };
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // only the stable denom can fund the reserve
+    let info = mock_info(
+        "addr0000",
+        &[
+            Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(1000000u128),
+            },
+            Coin {
+                denom: "ukrw".to_string(),
+                amount: Uint128::from(1000000u128),
+            },
+        ],
+    );
+    let res = execute(deps.as_mut(), env.clone(), info, ExecuteMsg::FundReserve {});
+    match res {
+        Err(ContractError::InvalidReserveDenom(denom)) => assert_eq!(denom, "ukrw"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    let res = execute(deps.as_mut(), env.clone(), info, ExecuteMsg::FundReserve {}).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "fund_reserve"),
+            attr("funded_amount", "1000000"),
+        ]
+    );
+
+    let epoch_state: EpochState = read_epoch_state(deps.as_ref().storage).unwrap();
+    assert_eq!(epoch_state.prev_interest_buffer, Uint256::from(1000000u64));
+
+    let msg = ExecuteMsg::WithdrawReserve {
+        amount: Uint256::from(400000u64),
+        recipient: "addr0001".to_string(),
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, msg.clone());
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let info = mock_info("owner", &[]);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        ExecuteMsg::WithdrawReserve {
+            amount: Uint256::from(10000000001u64),
+            recipient: "addr0001".to_string(),
+        },
+    );
+    match res {
+        Err(ContractError::WithdrawExceedsReserve(balance)) => assert_eq!(balance, 10000000000),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    deps.querier.with_tax(
+        Decimal::percent(1),
+        &[(&"uusd".to_string(), &Uint128::from(1000000u128))],
+    );
+
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "addr0001".to_string(),
+            amount: vec![deduct_tax(
+                deps.as_ref(),
+                Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(400000u128),
+                }
+            )
+            .unwrap()],
+        }))]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "withdraw_reserve"),
+            attr("recipient", "addr0001"),
+            attr("amount", "400000"),
+        ]
+    );
+
+    // baselines follow the withdrawn amount
+    let epoch_state: EpochState = read_epoch_state(deps.as_ref().storage).unwrap();
+    assert_eq!(epoch_state.prev_interest_buffer, Uint256::from(600000u64));
+
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::DynrateState {}).unwrap();
+    let dynrate_state: DynrateState = from_binary(&res).unwrap();
+    assert_eq!(
+        dynrate_state.prev_yield_reserve,
+        Decimal256::from_uint256(600000u64)
+    );
+
+    let res = query(deps.as_ref(), env, QueryMsg::Reserve {}).unwrap();
+    let reserve_res: ReserveResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        reserve_res,
+        ReserveResponse {
+            balance: Uint256::from(10000000000u64),
+            total_funded: Uint256::from(1000000u64),
+            total_accrued: Uint256::zero(),
+            total_distributed: Uint256::zero(),
+            total_anc_purchased: Uint256::zero(),
+            total_written_off: Uint256::zero(),
+            total_withdrawn: Uint256::from(400000u64),
+        }
+    );
+}
+
+#[test]
+fn record_bad_debt() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(10000000000u128),
+    }]);
+    deps.querier
+        .with_liquidation_percent(&[(&"liquidation".to_string(), &Decimal256::one())]);
+
+    let info = mock_info("owner", &[]);
+    let env = mock_env();
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        oracle_contract: "oracle".to_string(),
+        market_contract: "market".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        collector_contract: "collector".to_string(),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+        dyn_rate_epoch: 86400u64,
+        deposit_rate_model: "deposit_rate_model".to_string(),
+        dyn_rate_min: Decimal256::zero(),
+        dyn_rate_max: Decimal256::one(),
+        bad_debt_handler: None,
+        max_write_off_per_epoch: Uint256::from(1000000u64),
+        close_factor: Decimal256::one(),
+        liquidation_interval: 0,
+        margin_call_grace_period: 0,
+        hard_liquidation_ltv: Decimal256::one(),
+    };
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    // store whitelist elem
+    let msg = ExecuteMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: "bluna".to_string(),
+        custody_contract: "custody_bluna".to_string(),
+        max_ltv: Decimal256::percent(60),
+        liquidation_ltv: Decimal256::percent(60),
+        max_collateral_amount: None,
+        max_borrow_value: None,
+    };
+
+    let _res = execute(deps.as_mut(), env.clone(), info, msg);
+
+    // simulate lock collateral
+    let info = mock_info("addr0000", &[]);
+    _lock_collateral(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        vec![("bluna".to_string(), Uint256::from(1000u64))],
+    )
+    .unwrap();
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+
+    // borrow_limit = 1000 * 1000 * 0.6 = 600,000 uusd
+    deps.querier
+        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::from(600001u64))]);
+
+    // the whole collateral is liquidated, so the remaining loan gets recorded
+    let msg = ExecuteMsg::LiquidateCollateral {
+        borrower: "addr0000".to_string(),
+    };
+    let info = mock_info("addr0001", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
     assert_eq!(
         res.messages,
         vec![
             SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: "custody_batom".to_string(),
+                contract_addr: "custody_bluna".to_string(),
                 funds: vec![],
                 msg: to_binary(&CustodyExecuteMsg::LiquidateCollateral {
                     liquidator: "addr0001".to_string(),
                     borrower: "addr0000".to_string(),
-                    amount: Uint256::from(100000u64),
+                    amount: Uint256::from(1000u64),
                 })
                 .unwrap(),
             })),
             SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: "custody_bluna".to_string(),
+                contract_addr: "market".to_string(),
                 funds: vec![],
-                msg: to_binary(&CustodyExecuteMsg::LiquidateCollateral {
-                    liquidator: "addr0001".to_string(),
+                msg: to_binary(&MarketExecuteMsg::RepayStableFromLiquidation {
                     borrower: "addr0000".to_string(),
-                    amount: Uint256::from(10000u64),
+                    prev_balance: Uint256::zero(),
                 })
                 .unwrap(),
             })),
             SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: "market".to_string(),
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
                 funds: vec![],
-                msg: to_binary(&MarketExecuteMsg::RepayStableFromLiquidation {
+                msg: to_binary(&ExecuteMsg::RecordBadDebt {
                     borrower: "addr0000".to_string(),
-                    prev_balance: Uint256::zero(),
                 })
                 .unwrap(),
-            }))
+            })),
+        ]
+    );
+
+    let msg = ExecuteMsg::RecordBadDebt {
+        borrower: "addr0000".to_string(),
+    };
+    let res = execute(deps.as_mut(), env.clone(), info, msg.clone());
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "record_bad_debt"),
+            attr("borrower", "addr0000"),
+            attr("bad_debt", "600001"),
         ]
     );
 
     let res = query(
         deps.as_ref(),
-        env,
-        QueryMsg::Collaterals {
+        env.clone(),
+        QueryMsg::BadDebt {
             borrower: "addr0000".to_string(),
         },
     )
     .unwrap();
-    let collaterals_res: CollateralsResponse = from_binary(&res).unwrap();
+    let bad_debt_res: BadDebtResponse = from_binary(&res).unwrap();
     assert_eq!(
-        collaterals_res,
-        CollateralsResponse {
+        bad_debt_res,
+        BadDebtResponse {
             borrower: "addr0000".to_string(),
-            collaterals: vec![
-                (batom_collat_token, Uint256::from(9900000u64)),
-                (bluna_collat_token, Uint256::from(990000u64)),
-            ]
+            amount: Uint256::from(600001u64),
+            recorded_height: env.block.height,
+        }
+    );
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::AllBadDebts {
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let bad_debts_res: BadDebtsResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        bad_debts_res,
+        BadDebtsResponse {
+            bad_debts: vec![bad_debt_res],
+        }
+    );
+
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::TotalBadDebt {}).unwrap();
+    let total_bad_debt_res: TotalBadDebtResponse = from_binary(&res).unwrap();
+    assert_eq!(total_bad_debt_res.total_bad_debt, Uint256::from(600001u64));
+
+    // later repayments bring the bad debt down to the remaining loan
+    deps.querier
+        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::from(500000u64))]);
+    let msg = ExecuteMsg::ClearMarginCall {
+        borrower: "addr0000".to_string(),
+    };
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("market", &[]), msg).unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::BadDebt {
+            borrower: "addr0000".to_string(),
+        },
+    )
+    .unwrap();
+    let bad_debt_res: BadDebtResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        bad_debt_res,
+        BadDebtResponse {
+            borrower: "addr0000".to_string(),
+            amount: Uint256::from(500000u64),
+            recorded_height: env.block.height,
         }
     );
+
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::TotalBadDebt {}).unwrap();
+    let total_bad_debt_res: TotalBadDebtResponse = from_binary(&res).unwrap();
+    assert_eq!(total_bad_debt_res.total_bad_debt, Uint256::from(500000u64));
+
+    // writing off the loan clears the bad debt
+    let msg = ExecuteMsg::RepayStableFromYieldReserve {
+        borrower: "addr0000".to_string(),
+    };
+    let info = mock_info("owner", &[]);
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::AllBadDebts {
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let bad_debts_res: BadDebtsResponse = from_binary(&res).unwrap();
+    assert_eq!(bad_debts_res, BadDebtsResponse { bad_debts: vec![] });
+
+    let res = query(deps.as_ref(), env, QueryMsg::TotalBadDebt {}).unwrap();
+    let total_bad_debt_res: TotalBadDebtResponse = from_binary(&res).unwrap();
+    assert_eq!(total_bad_debt_res.total_bad_debt, Uint256::zero());
 }
 
 #[test]
@@ -1316,15 +4063,25 @@ fn dynamic_rate_model() {
         anc_purchase_factor: Decimal256::percent(20),
         price_timeframe: 60u64,
         dyn_rate_epoch: 8600u64,
-        dyn_rate_maxchange: Decimal256::permille(5),
-        dyn_rate_yr_increase_expectation: Decimal256::permille(1),
+        deposit_rate_model: "deposit_rate_model".to_string(),
         dyn_rate_min: Decimal256::from_ratio(1000000000000u64, 1000000000000000000u64),
         dyn_rate_max: Decimal256::from_ratio(1200000000000u64, 1000000000000000000u64),
+        bad_debt_handler: None,
+        max_write_off_per_epoch: Uint256::zero(),
+        close_factor: Decimal256::one(),
+        liquidation_interval: 0,
+        margin_call_grace_period: 0,
+        hard_liquidation_ltv: Decimal256::one(),
     };
 
     // we can just call .unwrap() to assert this was a success
     let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
 
+    deps.querier.with_deposit_rate_model(&[(
+        &"deposit_rate_model".to_string(),
+        &(Decimal256::permille(5), Decimal256::permille(1)),
+    )]);
+
     // store whitelist elems
     let msg = ExecuteMsg::Whitelist {
         name: "bluna".to_string(),
@@ -1332,6 +4089,9 @@ fn dynamic_rate_model() {
         collateral_token: "bluna".to_string(),
         custody_contract: "custody_bluna".to_string(),
         max_ltv: Decimal256::percent(60),
+        liquidation_ltv: Decimal256::percent(60),
+        max_collateral_amount: None,
+        max_borrow_value: None,
     };
 
     let _res = execute(deps.as_mut(), mock_env(), info.clone(), msg);
@@ -1342,32 +4102,28 @@ fn dynamic_rate_model() {
         collateral_token: "batom".to_string(),
         custody_contract: "custody_batom".to_string(),
         max_ltv: Decimal256::percent(60),
+        liquidation_ltv: Decimal256::percent(60),
+        max_collateral_amount: None,
+        max_borrow_value: None,
     };
 
-    let _res = execute(deps.as_mut(), mock_env(), info.clone(), msg);
+    let _res = execute(deps.as_mut(), mock_env(), info, msg);
 
-    // only contract itself can execute update_epoch_state
-    let msg = ExecuteMsg::UpdateEpochState {
+    let pending = PendingEpochOperation {
         interest_buffer: Uint256::from(10000000000u128),
         distributed_interest: Uint256::from(1000000u128),
     };
-    let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
-    match res {
-        Err(ContractError::Unauthorized {}) => (),
-        _ => panic!("DO NOT ENTER HERE"),
-    }
 
     // Assume execute epoch operation is executed
     let mut env = mock_env();
-    let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
-    env.block.height += 86400u64;
+    env.block.time = env.block.time.plus_seconds(86400u64);
 
     deps.querier.with_epoch_state(&[(
         &"market".to_string(),
         &(Uint256::from(1000000u64), Decimal256::percent(120)),
     )]);
 
-    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+    let res = epoch_operations_reply(deps.as_mut(), env.clone(), &pending).unwrap();
     assert_eq!(
         res.messages,
         vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
@@ -1399,8 +4155,8 @@ fn dynamic_rate_model() {
         &(Uint256::from(1000000u64), Decimal256::percent(125)),
     )]);
 
-    env.block.height += 86400u64;
-    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+    env.block.time = env.block.time.plus_seconds(86400u64);
+    let res = epoch_operations_reply(deps.as_mut(), env.clone(), &pending).unwrap();
 
     assert_eq!(
         res.messages,
@@ -1409,8 +4165,8 @@ fn dynamic_rate_model() {
             funds: vec![],
             msg: to_binary(&MarketExecuteMsg::ExecuteEpochOperations {
                 deposit_rate: Decimal256::from_str("0.000000482253086419").unwrap(),
-                target_deposit_rate: Decimal256::from_str("0.000001001073696371").unwrap(),
-                threshold_deposit_rate: Decimal256::from_str("0.000001001073696371").unwrap(),
+                target_deposit_rate: Decimal256::from_str("0.000001000158548959").unwrap(),
+                threshold_deposit_rate: Decimal256::from_str("0.000001000158548959").unwrap(),
                 distributed_interest: Uint256::from(1000000u128),
             })
             .unwrap(),
@@ -1430,7 +4186,7 @@ fn dynamic_rate_model() {
     let epoch_state_response = query_epoch_state(
         deps.as_ref(),
         Addr::unchecked("market"),
-        env.block.height,
+        env.block.time.seconds(),
         None,
     )
     .unwrap();
@@ -1443,72 +4199,75 @@ fn dynamic_rate_model() {
             prev_aterra_supply: epoch_state_response.aterra_supply,
             prev_exchange_rate: epoch_state_response.exchange_rate,
             prev_interest_buffer: Uint256::from(10000000000u128),
-            last_executed_height: env.block.height,
+            last_executed_time: env.block.time.seconds(),
         }
     );
 
-    // Case 1: YR unchanged, expected drop in rate due to dyn_rate_yr_increase_expectation
-    // Rate drop: 1001073696371 - 1000858957096 = 214739275
-    // 214739275 * 4656810 (bpy) = 1e15 = dyn_rate_yr_increase_expectation
+    // Case 1: YR unchanged, expected drop in rate due to yr_increase_expectation
+    // Rate drop: 1000158548959 - 1000126839167 = 31709792
+    // 31709792 * 31536000 (spy) = 1e15 = yr_increase_expectation
     validate_deposit_rates(
         deps.as_mut(),
-        Decimal256::from_ratio(1000858957096u64, 1000000000000000000u64),
+        Decimal256::from_ratio(1000126839167u64, 1000000000000000000u64),
     );
     // Case 2: Stillk unchanged, repeating behavior
-    // Rate drop: 1000858957096 - 1000644217821 = 214739275 = dyn_rate_yr_increase_expectation
+    // Rate drop: 1000126839167 - 1000095129375 = 31709792 = yr_increase_expectation
     store_dynrate_state(
         deps.as_mut().storage,
         &DynrateState {
-            last_executed_height: env.block.height,
+            last_executed_time: env.block.time.seconds(),
             prev_yield_reserve: Decimal256::from_str("10000000000").unwrap(),
+            model_state: None,
         },
     )
     .unwrap();
-    env.block.height += 86400u64;
-    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+    env.block.time = env.block.time.plus_seconds(86400u64);
+    let _res = epoch_operations_reply(deps.as_mut(), env.clone(), &pending).unwrap();
     validate_deposit_rates(
         deps.as_mut(),
-        Decimal256::from_ratio(1000644217821u64, 1000000000000000000u64),
+        Decimal256::from_ratio(1000095129375u64, 1000000000000000000u64),
     );
 
     // ----- YR increasing dramarically, 10x
-    // Rate increase: (1001717914192 - 1000644217821) * 4656810 = 5e15 = dyn_rate_maxchange
+    // Rate increase: (1000253678334 - 1000095129375) * 31536000 = 5e15 = max_change
     store_dynrate_state(
         deps.as_mut().storage,
         &DynrateState {
-            last_executed_height: env.block.height,
+            last_executed_time: env.block.time.seconds(),
             prev_yield_reserve: Decimal256::from_str("1000000000").unwrap(),
+            model_state: None,
         },
     )
     .unwrap();
-    env.block.height += 86400u64;
-    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+    env.block.time = env.block.time.plus_seconds(86400u64);
+    let _res = epoch_operations_reply(deps.as_mut(), env.clone(), &pending).unwrap();
     validate_deposit_rates(
         deps.as_mut(),
-        Decimal256::from_ratio(1001717914192u64, 1000000000000000000u64),
+        Decimal256::from_ratio(1000253678334u64, 1000000000000000000u64),
     );
 
-    // ----- YR increasing just a little, rate will still drop to compensate for dyn_rate_yr_increase_expectation
-    // (1001717914192 - 1001503174896) * 4656810 = 1.0000001e15
+    // ----- YR increasing just a little, rate will still drop to compensate for yr_increase_expectation
+    // (1000253678334 - 1000221968538) * 31536000 = 1.0000001e15
     store_dynrate_state(
         deps.as_mut().storage,
         &DynrateState {
-            last_executed_height: env.block.height,
+            last_executed_time: env.block.time.seconds(),
             prev_yield_reserve: Decimal256::from_str("10000000001").unwrap(),
+            model_state: None,
         },
     )
     .unwrap();
-    env.block.height += 86400u64;
-    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+    env.block.time = env.block.time.plus_seconds(86400u64);
+    let _res = epoch_operations_reply(deps.as_mut(), env.clone(), &pending).unwrap();
     validate_deposit_rates(
         deps.as_mut(),
-        Decimal256::from_ratio(1001503174896u64, 1000000000000000000u64),
+        Decimal256::from_ratio(1000221968538u64, 1000000000000000000u64),
     );
 
     // lets hit lower threshold
     for _i in 1..200 {
-        env.block.height += 86400u64;
-        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+        env.block.time = env.block.time.plus_seconds(86400u64);
+        let _res = epoch_operations_reply(deps.as_mut(), env.clone(), &pending).unwrap();
     }
     validate_deposit_rates(
         deps.as_mut(),
@@ -1516,17 +4275,18 @@ fn dynamic_rate_model() {
     );
 
     // lets hit upper threshold
-    for _i in 1..200 {
+    for _i in 1..1300 {
         store_dynrate_state(
             deps.as_mut().storage,
             &DynrateState {
-                last_executed_height: env.block.height,
+                last_executed_time: env.block.time.seconds(),
                 prev_yield_reserve: Decimal256::from_str("1000000000").unwrap(),
+                model_state: None,
             },
         )
         .unwrap();
-        env.block.height += 86400u64;
-        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+        env.block.time = env.block.time.plus_seconds(86400u64);
+        let _res = epoch_operations_reply(deps.as_mut(), env.clone(), &pending).unwrap();
     }
     validate_deposit_rates(
         deps.as_mut(),
@@ -1553,10 +4313,345 @@ fn validate_deposit_rates(deps: DepsMut, rate: Decimal256) {
             anc_purchase_factor: Decimal256::percent(20),
             price_timeframe: 60u64,
             dyn_rate_epoch: 8600u64,
-            dyn_rate_maxchange: Decimal256::permille(5),
-            dyn_rate_yr_increase_expectation: Decimal256::permille(1),
+            deposit_rate_model: "deposit_rate_model".to_string(),
             dyn_rate_min: Decimal256::from_ratio(1000000000000u64, 1000000000000000000u64),
             dyn_rate_max: Decimal256::from_ratio(1200000000000u64, 1000000000000000000u64),
+            bad_debt_handler: None,
+            max_write_off_per_epoch: Uint256::zero(),
+            close_factor: Decimal256::one(),
+            liquidation_interval: 0,
+            margin_call_grace_period: 0,
+            hard_liquidation_ltv: Decimal256::one(),
+        }
+    );
+}
+
+/// Delivers the reply of the last rewards distribution,
+/// which applies the epoch state in ExecuteEpochOperations
+fn epoch_operations_reply(
+    deps: DepsMut,
+    env: Env,
+    pending: &PendingEpochOperation,
+) -> Result<Response, ContractError> {
+    store_pending_epoch_operation(deps.storage, pending).unwrap();
+    reply(
+        deps,
+        env,
+        Reply {
+            id: 1,
+            result: ContractResult::Ok(SubMsgExecutionResponse {
+                events: vec![],
+                data: None,
+            }),
+        },
+    )
+}
+
+#[test]
+fn pause_liquidations() {
+    let mut deps = mock_dependencies(&[]);
+
+    let info = mock_info("owner", &[]);
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        oracle_contract: "oracle".to_string(),
+        market_contract: "market".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        collector_contract: "collector".to_string(),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+        dyn_rate_epoch: 86400u64,
+        deposit_rate_model: "deposit_rate_model".to_string(),
+        dyn_rate_min: Decimal256::zero(),
+        dyn_rate_max: Decimal256::one(),
+        bad_debt_handler: None,
+        max_write_off_per_epoch: Uint256::zero(),
+        close_factor: Decimal256::one(),
+        liquidation_interval: 0,
+        margin_call_grace_period: 0,
+        hard_liquidation_ltv: Decimal256::one(),
+    };
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::UpdateGuardian {
+        guardian: Some("guardian".to_string()),
+    };
+    let info = mock_info("owner", &[]);
+    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::Pause {
+        actions: vec![PauseAction::Unlock, PauseAction::Liquidate],
+    };
+    let info = mock_info("guardian", &[]);
+    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::UnlockCollateral {
+        collaterals: vec![("bluna".to_string(), Uint256::from(1000000u64))],
+        borrower: None,
+    };
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    match res {
+        Err(ContractError::Std(StdError::GenericErr { msg, .. })) => {
+            assert_eq!(msg, "unlock is paused")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = ExecuteMsg::LiquidateCollateral {
+        borrower: "addr0000".to_string(),
+    };
+    let info = mock_info("liquidator", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg);
+    match res {
+        Err(ContractError::Std(StdError::GenericErr { msg, .. })) => {
+            assert_eq!(msg, "liquidate is paused")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = ExecuteMsg::LiquidateCollaterals {
+        borrowers: vec!["addr0000".to_string()],
+    };
+    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    match res {
+        Err(ContractError::Std(StdError::GenericErr { msg, .. })) => {
+            assert_eq!(msg, "liquidate is paused")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the guardian cannot lift the pause
+    let msg = ExecuteMsg::Unpause {
+        actions: vec![PauseAction::Liquidate],
+    };
+    let info = mock_info("guardian", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
+    match res {
+        Err(ContractError::Std(StdError::GenericErr { msg, .. })) => {
+            assert_eq!(msg, "unauthorized")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let info = mock_info("owner", &[]);
+    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::PauseInfo {}).unwrap();
+    let pause_info: PauseInfoResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        pause_info,
+        PauseInfoResponse {
+            guardian: Some("guardian".to_string()),
+            paused: vec![PauseAction::Unlock],
+        }
+    );
+}
+
+#[test]
+fn migrate_legacy_state() {
+    let mut deps = mock_dependencies(&[]);
+
+    let info = mock_info("owner", &[]);
+    let env = mock_env();
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        oracle_contract: "oracle".to_string(),
+        market_contract: "market".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        collector_contract: "collector".to_string(),
+        stable_denom: "uusd".to_string(),
+        epoch_period: 86400u64,
+        threshold_deposit_rate: Decimal256::permille(3),
+        target_deposit_rate: Decimal256::permille(5),
+        buffer_distribution_factor: Decimal256::percent(20),
+        anc_purchase_factor: Decimal256::percent(20),
+        price_timeframe: 60u64,
+        dyn_rate_epoch: 8600u64,
+        deposit_rate_model: "deposit_rate_model".to_string(),
+        dyn_rate_min: Decimal256::from_str("0.000001").unwrap(),
+        dyn_rate_max: Decimal256::from_str("0.0000012").unwrap(),
+        bad_debt_handler: None,
+        max_write_off_per_epoch: Uint256::zero(),
+        close_factor: Decimal256::one(),
+        liquidation_interval: 0,
+        margin_call_grace_period: 0,
+        hard_liquidation_ltv: Decimal256::one(),
+    };
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // rewrite the state as stored by the unversioned contract
+    let storage = deps.as_mut().storage;
+    storage.remove(b"contract_info");
+    Singleton::<WriteOffConfig>::new(storage, b"write_off_config").remove();
+    Singleton::<WriteOffState>::new(storage, b"write_off_state").remove();
+    Singleton::<LiquidationConfig>::new(storage, b"liquidation_config").remove();
+    Singleton::new(storage, b"dynrate_config")
+        .save(&LegacyDynrateConfig {
+            dyn_rate_epoch: 8600u64,
+            dyn_rate_maxchange: Decimal256::permille(5),
+            dyn_rate_yr_increase_expectation: Decimal256::permille(1),
+            dyn_rate_min: Decimal256::from_str("0.000001").unwrap(),
+            dyn_rate_max: Decimal256::from_str("0.0000012").unwrap(),
+        })
+        .unwrap();
+    Singleton::new(storage, b"epoch_state")
+        .save(&LegacyEpochState {
+            deposit_rate: Decimal256::from_str("0.000001").unwrap(),
+            prev_aterra_supply: Uint256::from(1000000u64),
+            prev_exchange_rate: Decimal256::percent(120),
+            prev_interest_buffer: Uint256::from(5000u64),
+            last_executed_height: env.block.height - 100,
+        })
+        .unwrap();
+    Singleton::new(storage, b"dynrate_state")
+        .save(&LegacyDynrateState {
+            last_executed_height: env.block.height - 100,
+            prev_yield_reserve: Decimal256::from_uint256(5000u64),
+        })
+        .unwrap();
+    let bluna_raw = deps.api.addr_canonicalize("bluna").unwrap();
+    Bucket::new(deps.as_mut().storage, b"whitelist")
+        .save(
+            bluna_raw.as_slice(),
+            &LegacyWhitelistElem {
+                name: "bluna".to_string(),
+                symbol: "bluna".to_string(),
+                max_ltv: Decimal256::percent(60),
+                liquidation_ltv: None,
+                custody_contract: deps.api.addr_canonicalize("custody_bluna").unwrap(),
+                max_collateral_amount: None,
+                max_borrow_value: None,
+                status: None,
+                max_ltv_ramp: None,
+            },
+        )
+        .unwrap();
+    let batom_raw = deps.api.addr_canonicalize("batom").unwrap();
+    Bucket::new(deps.as_mut().storage, b"whitelist")
+        .save(
+            batom_raw.as_slice(),
+            &LegacyWhitelistElem {
+                name: "batom".to_string(),
+                symbol: "batom".to_string(),
+                max_ltv: Decimal256::percent(40),
+                liquidation_ltv: Some(Decimal256::percent(70)),
+                custody_contract: deps.api.addr_canonicalize("custody_batom").unwrap(),
+                max_collateral_amount: None,
+                max_borrow_value: None,
+                status: Some(LegacyWhitelistStatus::Deprecated {
+                    start_height: env.block.height - 100,
+                    end_height: env.block.height + 200,
+                }),
+                max_ltv_ramp: Some(LegacyMaxLtvRamp {
+                    start_ltv: Decimal256::percent(60),
+                    start_height: env.block.height - 50,
+                    end_height: env.block.height + 50,
+                }),
+            },
+        )
+        .unwrap();
+
+    // upgrades require a versioned contract
+    let res = migrate(deps.as_mut(), env.clone(), MigrateMsg::Upgrade {});
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "Contract version is missing; migrate from the legacy layout"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = MigrateMsg::FromLegacy {
+        deposit_rate_model: "deposit_rate_model0001".to_string(),
+        bad_debt_handler: Some("bad_debt_handler".to_string()),
+        max_write_off_per_epoch: Uint256::from(1000000u64),
+        close_factor: Decimal256::percent(50),
+        liquidation_interval: 10u64,
+        margin_call_grace_period: 600u64,
+        hard_liquidation_ltv: Decimal256::percent(90),
+    };
+    let _res = migrate(deps.as_mut(), env.clone(), msg.clone()).unwrap();
+
+    let dynrate_config = read_dynrate_config(deps.as_ref().storage).unwrap();
+    assert_eq!(
+        dynrate_config.deposit_rate_model,
+        deps.api
+            .addr_canonicalize("deposit_rate_model0001")
+            .unwrap()
+    );
+    assert_eq!(
+        dynrate_config.dyn_rate_epoch,
+        legacy_blocks_to_seconds(8600u64)
+    );
+    assert_eq!(
+        dynrate_config.dyn_rate_max,
+        legacy_rate_to_rate_per_second(Decimal256::from_str("0.0000012").unwrap())
+    );
+    assert_eq!(
+        read_epoch_state(deps.as_ref().storage).unwrap(),
+        EpochState {
+            deposit_rate: legacy_rate_to_rate_per_second(Decimal256::from_str("0.000001").unwrap()),
+            prev_aterra_supply: Uint256::from(1000000u64),
+            prev_exchange_rate: Decimal256::percent(120),
+            prev_interest_buffer: Uint256::from(5000u64),
+            last_executed_time: env.block.time.seconds(),
+        }
+    );
+    let whitelist_elem = read_whitelist_elem(deps.as_ref().storage, &bluna_raw).unwrap();
+    assert_eq!(whitelist_elem.liquidation_ltv, Decimal256::percent(60));
+    assert_eq!(whitelist_elem.status, WhitelistStatus::Active);
+
+    // ramps scheduled in block heights move to block times
+    let whitelist_elem = read_whitelist_elem(deps.as_ref().storage, &batom_raw).unwrap();
+    assert_eq!(
+        whitelist_elem.status,
+        WhitelistStatus::Deprecated {
+            start_time: env.block.time.seconds() - legacy_blocks_to_seconds(100),
+            end_time: env.block.time.seconds() + legacy_blocks_to_seconds(200),
+        }
+    );
+    assert_eq!(
+        whitelist_elem.max_ltv_ramp,
+        Some(MaxLtvRamp {
+            start_ltv: Decimal256::percent(60),
+            start_time: env.block.time.seconds() - legacy_blocks_to_seconds(50),
+            end_time: env.block.time.seconds() + legacy_blocks_to_seconds(50),
+        })
+    );
+    assert_eq!(
+        read_write_off_config(deps.as_ref().storage).unwrap(),
+        WriteOffConfig {
+            bad_debt_handler: Some(deps.api.addr_canonicalize("bad_debt_handler").unwrap()),
+            max_write_off_per_epoch: Uint256::from(1000000u64),
+        }
+    );
+    assert_eq!(
+        read_liquidation_config(deps.as_ref().storage).unwrap(),
+        LiquidationConfig {
+            close_factor: Decimal256::percent(50),
+            liquidation_interval: 10u64,
+            margin_call_grace_period: 600u64,
+            hard_liquidation_ltv: Decimal256::percent(90),
         }
     );
+
+    // the legacy migration cannot be replayed once versioned
+    let res = migrate(deps.as_mut(), env.clone(), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "Contract is already at version 0.0.0; legacy migration not allowed"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let _res = migrate(deps.as_mut(), env, MigrateMsg::Upgrade {}).unwrap();
 }
@@ -0,0 +1,267 @@
+use crate::error::ContractError;
+use crate::state::{read_config, store_config, Config, PidState, SignedDecimal};
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::{
+    from_binary, to_binary, Binary, CanonicalAddr, Deps, DepsMut, Env, MessageInfo, Response,
+    StdResult,
+};
+use cw2::set_contract_version;
+use moneymarket::common::SECONDS_PER_YEAR;
+use moneymarket::deposit_rate_model::TargetDepositRateResponse;
+use moneymarket::ownership::{
+    claim_ownership, drop_ownership_proposal, propose_new_owner, query_ownership_proposal,
+};
+use moneymarket::pid_deposit_rate_model::{ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg};
+use std::cmp::{max, min};
+
+pub const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
+pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> StdResult<Response> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    store_config(
+        deps.storage,
+        &Config {
+            owner: deps.api.addr_canonicalize(&msg.owner)?,
+            target_growth: msg.target_growth,
+            p_gain: msg.p_gain,
+            i_gain: msg.i_gain,
+            d_gain: msg.d_gain,
+            max_change: msg.max_change,
+        },
+    )?;
+
+    Ok(Response::default())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::UpdateConfig {
+            target_growth,
+            p_gain,
+            i_gain,
+            d_gain,
+            max_change,
+        } => update_config(
+            deps,
+            info,
+            target_growth,
+            p_gain,
+            i_gain,
+            d_gain,
+            max_change,
+        ),
+        ExecuteMsg::ProposeNewOwner { owner, expires_in } => {
+            let config: Config = read_config(deps.storage)?;
+            Ok(propose_new_owner(
+                deps,
+                env,
+                info,
+                config.owner,
+                owner,
+                expires_in,
+            )?)
+        }
+        ExecuteMsg::DropOwnershipProposal {} => {
+            let config: Config = read_config(deps.storage)?;
+            Ok(drop_ownership_proposal(deps, info, config.owner)?)
+        }
+        ExecuteMsg::ClaimOwnership {} => Ok(claim_ownership(deps, env, info, update_owner)?),
+    }
+}
+
+pub fn update_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    target_growth: Option<Decimal256>,
+    p_gain: Option<Decimal256>,
+    i_gain: Option<Decimal256>,
+    d_gain: Option<Decimal256>,
+    max_change: Option<Decimal256>,
+) -> Result<Response, ContractError> {
+    let mut config: Config = read_config(deps.storage)?;
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if let Some(target_growth) = target_growth {
+        config.target_growth = target_growth;
+    }
+
+    if let Some(p_gain) = p_gain {
+        config.p_gain = p_gain;
+    }
+
+    if let Some(i_gain) = i_gain {
+        config.i_gain = i_gain;
+    }
+
+    if let Some(d_gain) = d_gain {
+        config.d_gain = d_gain;
+    }
+
+    if let Some(max_change) = max_change {
+        config.max_change = max_change;
+    }
+
+    store_config(deps.storage, &config)?;
+    Ok(Response::default())
+}
+
+pub fn update_owner(deps: DepsMut, owner: CanonicalAddr) -> StdResult<()> {
+    let mut config: Config = read_config(deps.storage)?;
+    config.owner = owner;
+    store_config(deps.storage, &config)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::OwnershipProposal {} => to_binary(&query_ownership_proposal(deps)?),
+        QueryMsg::TargetDepositRate {
+            yield_reserve,
+            prev_yield_reserve,
+            current_rate,
+            seconds,
+            model_state,
+        } => to_binary(&query_target_deposit_rate(
+            deps,
+            yield_reserve,
+            prev_yield_reserve,
+            current_rate,
+            seconds,
+            model_state,
+        )?),
+    }
+}
+
+fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let resp = ConfigResponse {
+        owner: deps.api.addr_humanize(&config.owner)?.to_string(),
+        target_growth: config.target_growth,
+        p_gain: config.p_gain,
+        i_gain: config.i_gain,
+        d_gain: config.d_gain,
+        max_change: config.max_change,
+    };
+
+    Ok(resp)
+}
+
+fn query_target_deposit_rate(
+    deps: Deps,
+    yield_reserve: Decimal256,
+    prev_yield_reserve: Decimal256,
+    current_rate: Decimal256,
+    seconds: u64,
+    model_state: Option<Binary>,
+) -> StdResult<TargetDepositRateResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let state: Option<PidState> = model_state
+        .map(|model_state| from_binary(&model_state))
+        .transpose()?;
+
+    let (rate, state) = compute_target_deposit_rate(
+        &config,
+        yield_reserve,
+        prev_yield_reserve,
+        current_rate,
+        seconds,
+        state,
+    );
+
+    Ok(TargetDepositRateResponse {
+        rate,
+        model_state: state.map(|state| to_binary(&state)).transpose()?,
+    })
+}
+
+/// Steers the yearly deposit rate with the P, I and D terms of the gap between
+/// the yearly yield reserve growth and the target growth; the rate moves
+/// towards the controller output by at most max_change per evaluation
+pub fn compute_target_deposit_rate(
+    config: &Config,
+    yield_reserve: Decimal256,
+    prev_yield_reserve: Decimal256,
+    current_rate: Decimal256,
+    seconds: u64,
+    state: Option<PidState>,
+) -> (Decimal256, Option<PidState>) {
+    // without a prev yield reserve there is no growth to measure
+    if seconds == 0 || prev_yield_reserve.is_zero() {
+        return (current_rate, state);
+    }
+
+    // convert per-second rate into yearly rate
+    let seconds_per_year = Decimal256::from_uint256(SECONDS_PER_YEAR);
+    let current_rate = current_rate * seconds_per_year;
+
+    // elapsed time in years
+    let period = Decimal256::from_ratio(seconds, SECONDS_PER_YEAR);
+
+    // error = yearly growth of the yield reserve over the period - target growth
+    let growth =
+        SignedDecimal::diff(yield_reserve, prev_yield_reserve) / prev_yield_reserve / period;
+    let error = growth - SignedDecimal::positive(config.target_growth);
+
+    // the first evaluation starts the integral term at the current rate
+    let state = state.unwrap_or_else(|| PidState {
+        integral: if config.i_gain.is_zero() {
+            SignedDecimal::positive(Decimal256::zero())
+        } else {
+            SignedDecimal::positive(current_rate / config.i_gain)
+        },
+        prev_error: error,
+    });
+
+    let integral = state.integral + error * period;
+    let derivative = (error - state.prev_error) / period;
+    let output = error * config.p_gain + integral * config.i_gain + derivative * config.d_gain;
+    let target_rate = if output.negative {
+        Decimal256::zero()
+    } else {
+        output.value
+    };
+
+    let new_rate = if target_rate > current_rate {
+        min(target_rate, current_rate + config.max_change)
+    } else if current_rate > config.max_change {
+        max(target_rate, current_rate - config.max_change)
+    } else {
+        target_rate
+    };
+
+    // the integral stops accumulating while the rate is held back by max_change
+    let integral = if new_rate == target_rate {
+        integral
+    } else {
+        state.integral
+    };
+
+    // convert from yearly rate to per-second rate
+    (
+        new_rate / seconds_per_year,
+        Some(PidState {
+            integral,
+            prev_error: error,
+        }),
+    )
+}
@@ -0,0 +1,103 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::{CanonicalAddr, StdResult, Storage};
+use cosmwasm_storage::{singleton, singleton_read};
+use std::ops::{Add, Div, Mul, Sub};
+
+static KEY_CONFIG: &[u8] = b"config";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Config {
+    pub owner: CanonicalAddr,
+    pub target_growth: Decimal256,
+    pub p_gain: Decimal256,
+    pub i_gain: Decimal256,
+    pub d_gain: Decimal256,
+    pub max_change: Decimal256,
+}
+
+pub fn store_config(storage: &mut dyn Storage, config: &Config) -> StdResult<()> {
+    singleton(storage, KEY_CONFIG).save(config)
+}
+
+pub fn read_config(storage: &dyn Storage) -> StdResult<Config> {
+    singleton_read(storage, KEY_CONFIG).load()
+}
+
+/// Controller state returned with every target rate;
+/// the overseer passes it back with the next query
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PidState {
+    /// Error accumulated over the evaluated periods
+    pub integral: SignedDecimal,
+    pub prev_error: SignedDecimal,
+}
+
+/// Sign and magnitude of a value which can go below zero
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+pub struct SignedDecimal {
+    pub negative: bool,
+    pub value: Decimal256,
+}
+
+impl SignedDecimal {
+    pub fn new(negative: bool, value: Decimal256) -> Self {
+        SignedDecimal {
+            negative: negative && !value.is_zero(),
+            value,
+        }
+    }
+
+    pub fn positive(value: Decimal256) -> Self {
+        SignedDecimal::new(false, value)
+    }
+
+    /// a - b
+    pub fn diff(a: Decimal256, b: Decimal256) -> Self {
+        if a >= b {
+            SignedDecimal::new(false, a - b)
+        } else {
+            SignedDecimal::new(true, b - a)
+        }
+    }
+}
+
+impl Add for SignedDecimal {
+    type Output = SignedDecimal;
+
+    fn add(self, other: SignedDecimal) -> SignedDecimal {
+        if self.negative == other.negative {
+            SignedDecimal::new(self.negative, self.value + other.value)
+        } else if self.value >= other.value {
+            SignedDecimal::new(self.negative, self.value - other.value)
+        } else {
+            SignedDecimal::new(other.negative, other.value - self.value)
+        }
+    }
+}
+
+impl Sub for SignedDecimal {
+    type Output = SignedDecimal;
+
+    fn sub(self, other: SignedDecimal) -> SignedDecimal {
+        self + SignedDecimal::new(!other.negative, other.value)
+    }
+}
+
+impl Mul<Decimal256> for SignedDecimal {
+    type Output = SignedDecimal;
+
+    fn mul(self, factor: Decimal256) -> SignedDecimal {
+        SignedDecimal::new(self.negative, self.value * factor)
+    }
+}
+
+impl Div<Decimal256> for SignedDecimal {
+    type Output = SignedDecimal;
+
+    fn div(self, divisor: Decimal256) -> SignedDecimal {
+        SignedDecimal::new(self.negative, self.value / divisor)
+    }
+}
@@ -0,0 +1,195 @@
+use crate::contract::{execute, instantiate, query};
+use crate::error::ContractError;
+use crate::state::{PidState, SignedDecimal};
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+use cosmwasm_std::{from_binary, Binary};
+use moneymarket::deposit_rate_model::TargetDepositRateResponse;
+use moneymarket::pid_deposit_rate_model::{ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg};
+use std::str::FromStr;
+
+fn instantiate_msg() -> InstantiateMsg {
+    InstantiateMsg {
+        owner: "owner0000".to_string(),
+        target_growth: Decimal256::from_str("0.365").unwrap(),
+        p_gain: Decimal256::percent(50),
+        i_gain: Decimal256::one(),
+        d_gain: Decimal256::permille(1),
+        max_change: Decimal256::from_str("0.03").unwrap(),
+    }
+}
+
+#[test]
+fn proper_initialization() {
+    let mut deps = mock_dependencies(&[]);
+
+    let info = mock_info("addr0000", &[]);
+
+    // we can just call .unwrap() to assert this was a success
+    let res = instantiate(deps.as_mut(), mock_env(), info, instantiate_msg()).unwrap();
+    assert_eq!(0, res.messages.len());
+
+    // it worked, let's query the state
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let value: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        value,
+        ConfigResponse {
+            owner: "owner0000".to_string(),
+            target_growth: Decimal256::from_str("0.365").unwrap(),
+            p_gain: Decimal256::percent(50),
+            i_gain: Decimal256::one(),
+            d_gain: Decimal256::permille(1),
+            max_change: Decimal256::from_str("0.03").unwrap(),
+        }
+    );
+}
+
+#[test]
+fn update_config() {
+    let mut deps = mock_dependencies(&[]);
+
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, instantiate_msg()).unwrap();
+
+    // Unauthorized err
+    let info = mock_info("addr0000", &[]);
+    let msg = ExecuteMsg::UpdateConfig {
+        target_growth: None,
+        p_gain: Some(Decimal256::one()),
+        i_gain: None,
+        d_gain: None,
+        max_change: None,
+    };
+
+    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    let info = mock_info("owner0000", &[]);
+    let msg = ExecuteMsg::UpdateConfig {
+        target_growth: Some(Decimal256::percent(10)),
+        p_gain: Some(Decimal256::one()),
+        i_gain: Some(Decimal256::zero()),
+        d_gain: Some(Decimal256::zero()),
+        max_change: Some(Decimal256::percent(1)),
+    };
+
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(0, res.messages.len());
+
+    // it worked, let's query the state
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let value: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        value,
+        ConfigResponse {
+            owner: "owner0000".to_string(),
+            target_growth: Decimal256::percent(10),
+            p_gain: Decimal256::one(),
+            i_gain: Decimal256::zero(),
+            d_gain: Decimal256::zero(),
+            max_change: Decimal256::percent(1),
+        }
+    );
+}
+
+#[test]
+fn target_deposit_rate() {
+    let mut deps = mock_dependencies(&[]);
+
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, instantiate_msg()).unwrap();
+
+    let query_rate = |yield_reserve: u64,
+                      prev_yield_reserve: u64,
+                      current_rate: Decimal256,
+                      seconds: u64,
+                      model_state: Option<Binary>| {
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::TargetDepositRate {
+                yield_reserve: Decimal256::from_uint256(yield_reserve),
+                prev_yield_reserve: Decimal256::from_uint256(prev_yield_reserve),
+                current_rate,
+                seconds,
+                model_state,
+            },
+        )
+        .unwrap();
+        from_binary::<TargetDepositRateResponse>(&res).unwrap()
+    };
+    let pid_state = |res: &TargetDepositRateResponse| -> PidState {
+        from_binary(res.model_state.as_ref().unwrap()).unwrap()
+    };
+
+    // yearly rate of 3.1536%
+    let current_rate = Decimal256::from_ratio(1u64, 1000000000u64);
+
+    // the reserve grew 0.11% over a day, 0.01% above the target growth;
+    // the integral starts at the current rate
+    // rate = 0.5 * 0.0365 + (0.031536 + 0.0365 * (1 / 365)) + 0
+    let res = query_rate(10011000000, 10000000000, current_rate, 86400, None);
+    assert_eq!(
+        res.rate,
+        Decimal256::from_ratio(1581874682u64, 1000000000000000000u64)
+    );
+    assert_eq!(
+        pid_state(&res),
+        PidState {
+            integral: SignedDecimal::positive(Decimal256::from_str("0.031636").unwrap()),
+            prev_error: SignedDecimal::positive(
+                Decimal256::from_str("0.03650000000000004").unwrap()
+            ),
+        }
+    );
+
+    // back on the target growth, the derivative term pulls the rate down;
+    // capped at max_change, which holds the integral
+    let res = query_rate(
+        10021011000,
+        10011000000,
+        res.rate,
+        86400,
+        res.model_state.clone(),
+    );
+    assert_eq!(
+        res.rate,
+        Decimal256::from_ratio(630580922u64, 1000000000000000000u64)
+    );
+    assert_eq!(
+        pid_state(&res).integral,
+        SignedDecimal::positive(Decimal256::from_str("0.031636").unwrap())
+    );
+
+    // unchanged reserve misses the target by 36.5% a year; capped at max_change
+    let res = query_rate(10000000000, 10000000000, current_rate, 86400, None);
+    assert_eq!(
+        res.rate,
+        Decimal256::from_ratio(48706240u64, 1000000000000000000u64)
+    );
+    assert_eq!(
+        pid_state(&res),
+        PidState {
+            integral: SignedDecimal::positive(Decimal256::from_str("0.031536").unwrap()),
+            prev_error: SignedDecimal::new(true, Decimal256::from_str("0.365").unwrap()),
+        }
+    );
+
+    // an empty previous reserve gives no growth to measure, rate and state stay
+    let res = query_rate(0, 0, current_rate, 86400, None);
+    assert_eq!(res.rate, current_rate);
+    assert_eq!(res.model_state, None);
+
+    let model_state = query_rate(10011000000, 10000000000, current_rate, 86400, None).model_state;
+    let res = query_rate(5000000000, 0, current_rate, 86400, model_state.clone());
+    assert_eq!(res.rate, current_rate);
+    assert_eq!(res.model_state, model_state);
+
+    // no time passed, rate stays
+    let res = query_rate(5000000000, 10000000000, current_rate, 0, None);
+    assert_eq!(res.rate, current_rate);
+}
@@ -1,5 +1,13 @@
+use cosmwasm_bignumber::Decimal256;
 use cosmwasm_std::{Addr, Api, StdResult};
 
+/// Rates and periods are measured in seconds
+pub const SECONDS_PER_YEAR: u64 = 31_536_000;
+
+/// Blocks per year assumed by the per-block rates stored before
+/// the switch to per-second rates; only used by migrations
+pub const LEGACY_BLOCKS_PER_YEAR: u64 = 4_656_810;
+
 pub fn optional_addr_validate(api: &dyn Api, addr: Option<String>) -> StdResult<Option<Addr>> {
     let addr = if let Some(addr) = addr {
         Some(api.addr_validate(&addr)?)
@@ -9,3 +17,14 @@ pub fn optional_addr_validate(api: &dyn Api, addr: Option<String>) -> StdResult<
 
     Ok(addr)
 }
+
+/// Converts a legacy per-block rate into a per-second rate
+pub fn legacy_rate_to_rate_per_second(rate: Decimal256) -> Decimal256 {
+    rate * Decimal256::from_uint256(LEGACY_BLOCKS_PER_YEAR)
+        / Decimal256::from_uint256(SECONDS_PER_YEAR)
+}
+
+/// Converts a legacy # of blocks into the equivalent # of seconds
+pub fn legacy_blocks_to_seconds(blocks: u64) -> u64 {
+    (blocks as u128 * SECONDS_PER_YEAR as u128 / LEGACY_BLOCKS_PER_YEAR as u128) as u64
+}
@@ -1,6 +1,7 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::pause::PauseAction;
 use cosmwasm_bignumber::Uint256;
 use cw20::Cw20ReceiveMsg;
 
@@ -37,9 +38,21 @@ pub enum ExecuteMsg {
 
     /// Update config
     UpdateConfig {
-        owner: Option<String>,
         liquidation_contract: Option<String>,
     },
+    /// Propose a new owner, who can claim the ownership
+    /// within expires_in seconds
+    ProposeNewOwner { owner: String, expires_in: u64 },
+    /// Remove the pending ownership proposal
+    DropOwnershipProposal {},
+    /// Executor: proposed owner
+    ClaimOwnership {},
+    /// Set or remove the guardian
+    UpdateGuardian { guardian: Option<String> },
+    /// Executor: owner or guardian
+    Pause { actions: Vec<PauseAction> },
+    /// Executor: owner
+    Unpause { actions: Vec<PauseAction> },
     /// Make specified amount of tokens unspendable
     LockCollateral { borrower: String, amount: Uint256 },
     /// Make specified amount of collateral tokens spendable
@@ -74,12 +87,20 @@ pub enum Cw20HookMsg {
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
-pub struct MigrateMsg {}
+pub enum MigrateMsg {
+    /// Records the contract version of a custody deployed before
+    /// contract versioning
+    FromLegacy {},
+    /// Upgrades a versioned custody contract
+    Upgrade {},
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     Config {},
+    OwnershipProposal {},
+    PauseInfo {},
     Borrower {
         address: String,
     },
@@ -0,0 +1,67 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::Binary;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InstantiateMsg {
+    pub owner: String,
+    /// Max change of the yearly deposit rate per evaluation
+    pub max_change: Decimal256,
+    /// Yield reserve growth expected per evaluation
+    pub yr_increase_expectation: Decimal256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    UpdateConfig {
+        max_change: Option<Decimal256>,
+        yr_increase_expectation: Option<Decimal256>,
+    },
+    /// Propose a new owner, who can claim the ownership
+    /// within expires_in seconds
+    ProposeNewOwner { owner: String, expires_in: u64 },
+    /// Remove the pending ownership proposal
+    DropOwnershipProposal {},
+    /// Executor: proposed owner
+    ClaimOwnership {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    Config {},
+    OwnershipProposal {},
+    /// Common interface of the deposit rate models,
+    /// queried by the overseer every dynrate epoch
+    TargetDepositRate {
+        yield_reserve: Decimal256,
+        prev_yield_reserve: Decimal256,
+        /// Deposit rate per second
+        current_rate: Decimal256,
+        /// Seconds passed since the last evaluation
+        seconds: u64,
+        /// State returned with the previous target rate
+        model_state: Option<Binary>,
+    },
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigResponse {
+    pub owner: String,
+    pub max_change: Decimal256,
+    pub yr_increase_expectation: Decimal256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TargetDepositRateResponse {
+    /// Deposit rate per second
+    pub rate: Decimal256,
+    /// State of models which keep one between evaluations,
+    /// to be passed back with the next query
+    pub model_state: Option<Binary>,
+}
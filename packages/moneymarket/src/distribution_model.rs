@@ -16,18 +16,25 @@ pub struct InstantiateMsg {
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
     UpdateConfig {
-        owner: Option<String>,
         emission_cap: Option<Decimal256>,
         emission_floor: Option<Decimal256>,
         increment_multiplier: Option<Decimal256>,
         decrement_multiplier: Option<Decimal256>,
     },
+    /// Propose a new owner, who can claim the ownership
+    /// within expires_in seconds
+    ProposeNewOwner { owner: String, expires_in: u64 },
+    /// Remove the pending ownership proposal
+    DropOwnershipProposal {},
+    /// Executor: proposed owner
+    ClaimOwnership {},
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     Config {},
+    OwnershipProposal {},
     AncEmissionRate {
         deposit_rate: Decimal256,
         target_deposit_rate: Decimal256,
@@ -6,7 +6,9 @@ use cosmwasm_bignumber::{Decimal256, Uint256};
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
     pub owner: String,
+    /// Borrow rate per second at zero utilization
     pub base_rate: Decimal256,
+    /// Borrow rate per second added per unit of utilization
     pub interest_multiplier: Decimal256,
 }
 
@@ -14,16 +16,23 @@ pub struct InstantiateMsg {
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
     UpdateConfig {
-        owner: Option<String>,
         base_rate: Option<Decimal256>,
         interest_multiplier: Option<Decimal256>,
     },
+    /// Propose a new owner, who can claim the ownership
+    /// within expires_in seconds
+    ProposeNewOwner { owner: String, expires_in: u64 },
+    /// Remove the pending ownership proposal
+    DropOwnershipProposal {},
+    /// Executor: proposed owner
+    ClaimOwnership {},
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     Config {},
+    OwnershipProposal {},
     BorrowRate {
         market_balance: Uint256,
         total_liabilities: Decimal256,
@@ -31,6 +40,16 @@ pub enum QueryMsg {
     },
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MigrateMsg {
+    /// Converts the per-block rates of a model deployed before
+    /// contract versioning into per-second rates
+    FromLegacy {},
+    /// Upgrades a versioned model; the config layout is unchanged
+    Upgrade {},
+}
+
 // We define a custom struct for each query response
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct ConfigResponse {
@@ -42,5 +61,6 @@ pub struct ConfigResponse {
 // We define a custom struct for each query response
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct BorrowRateResponse {
+    /// Borrow rate per second
     pub rate: Decimal256,
 }
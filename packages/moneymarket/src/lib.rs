@@ -1,12 +1,18 @@
 pub mod common;
 pub mod custody;
+pub mod deposit_rate_model;
 pub mod distribution_model;
 pub mod interest_model;
 pub mod liquidation;
 pub mod liquidation_queue;
 pub mod market;
+pub mod migration;
+pub mod operator;
 pub mod oracle;
 pub mod overseer;
+pub mod ownership;
+pub mod pause;
+pub mod pid_deposit_rate_model;
 pub mod querier;
 pub mod terraswap;
 pub mod tokens;
@@ -33,7 +33,6 @@ pub struct InstantiateMsg {
 pub enum ExecuteMsg {
     Receive(Cw20ReceiveMsg),
     UpdateConfig {
-        owner: Option<String>,
         oracle_contract: Option<String>,
         stable_denom: Option<String>,
         safe_ratio: Option<Decimal256>,
@@ -42,6 +41,16 @@ pub enum ExecuteMsg {
         liquidation_threshold: Option<Uint256>,
         price_timeframe: Option<u64>,
     },
+    /// Propose a new owner, who can claim the ownership
+    /// within expires_in seconds
+    ProposeNewOwner {
+        owner: String,
+        expires_in: u64,
+    },
+    /// Remove the pending ownership proposal
+    DropOwnershipProposal {},
+    /// Executor: proposed owner
+    ClaimOwnership {},
     SubmitBid {
         collateral_token: String,
         premium_rate: Decimal256,
@@ -66,6 +75,7 @@ pub enum Cw20HookMsg {
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     Config {},
+    OwnershipProposal {},
     LiquidationAmount {
         borrow_amount: Uint256,
         borrow_limit: Uint256,
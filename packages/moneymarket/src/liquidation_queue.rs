@@ -1,6 +1,7 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::pause::PauseAction;
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::Uint128;
 use cw20::Cw20ReceiveMsg;
@@ -37,7 +38,6 @@ pub struct InstantiateMsg {
 pub enum ExecuteMsg {
     Receive(Cw20ReceiveMsg),
     UpdateConfig {
-        owner: Option<String>,
         oracle_contract: Option<String>,
         safe_ratio: Option<Decimal256>,
         bid_fee: Option<Decimal256>,
@@ -47,6 +47,28 @@ pub enum ExecuteMsg {
         waiting_period: Option<u64>,
         overseer: Option<String>,
     },
+    /// Propose a new owner, who can claim the ownership
+    /// within expires_in seconds
+    ProposeNewOwner {
+        owner: String,
+        expires_in: u64,
+    },
+    /// Remove the pending ownership proposal
+    DropOwnershipProposal {},
+    /// Executor: proposed owner
+    ClaimOwnership {},
+    /// Set or remove the guardian
+    UpdateGuardian {
+        guardian: Option<String>,
+    },
+    /// Executor: owner or guardian
+    Pause {
+        actions: Vec<PauseAction>,
+    },
+    /// Executor: owner
+    Unpause {
+        actions: Vec<PauseAction>,
+    },
     /// Owner operation to whitelist a new collateral
     WhitelistCollateral {
         collateral_token: String,
@@ -96,6 +118,8 @@ pub enum Cw20HookMsg {
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     Config {},
+    OwnershipProposal {},
+    PauseInfo {},
     LiquidationAmount {
         borrow_amount: Uint256,
         borrow_limit: Uint256,
@@ -1,7 +1,10 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::operator::OperatorPermission;
+use crate::pause::PauseAction;
 use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::Binary;
 use cw20::Cw20ReceiveMsg;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -13,7 +16,7 @@ pub struct InstantiateMsg {
     pub stable_denom: String,
     /// Anchor token code ID used to instantiate
     pub aterra_code_id: u64,
-    /// Anchor token distribution speed
+    /// Anchor token distribution speed per second
     pub anc_emission_rate: Decimal256,
     /// Maximum allowed borrow rate over deposited stable balance
     pub max_borrow_factor: Decimal256,
@@ -44,10 +47,37 @@ pub enum ExecuteMsg {
 
     /// Update config values
     UpdateConfig {
-        owner_addr: Option<String>,
         max_borrow_factor: Option<Decimal256>,
         interest_model: Option<String>,
         distribution_model: Option<String>,
+        /// Fee charged on flash loans, relative to the amount;
+        /// flash loans are disabled until it is set
+        flash_loan_fee: Option<Decimal256>,
+        /// Premium per second added to the borrow rate of fixed-rate
+        /// loans; fixed-rate borrowing is disabled until it is set
+        fixed_rate_premium: Option<Decimal256>,
+    },
+    /// Propose a new owner, who can claim the ownership
+    /// within expires_in seconds
+    ProposeNewOwner {
+        owner: String,
+        expires_in: u64,
+    },
+    /// Remove the pending ownership proposal
+    DropOwnershipProposal {},
+    /// Executor: proposed owner
+    ClaimOwnership {},
+    /// Set or remove the guardian
+    UpdateGuardian {
+        guardian: Option<String>,
+    },
+    /// Executor: owner or guardian
+    Pause {
+        actions: Vec<PauseAction>,
+    },
+    /// Executor: owner
+    Unpause {
+        actions: Vec<PauseAction>,
     },
 
     ////////////////////
@@ -84,10 +114,60 @@ pub enum ExecuteMsg {
     /// Repay stable asset to decrease liability
     RepayStable {},
 
+    /// Repay stable asset to decrease the liability of another borrower;
+    /// any overpayment is refunded to the sender
+    RepayStableFor {
+        borrower: String,
+    },
+
+    /// Lend stable asset for the duration of a callback to the sender;
+    /// the amount and the fee must be back in the market afterwards
+    FlashLoan {
+        amount: Uint256,
+        msg: Binary,
+    },
+
+    /// Borrow stable asset at the current borrow rate plus the fixed rate
+    /// premium, locked until the loan is repaid or rolled over
+    BorrowStableFixed {
+        borrow_amount: Uint256,
+        /// Block time after which the loan can be rolled
+        /// over into the variable-rate loan
+        maturity: Option<u64>,
+        to: Option<String>,
+        borrower: Option<String>,
+    },
+
+    /// Repay stable asset to decrease a fixed-rate loan;
+    /// any overpayment is refunded to the sender
+    RepayStableFixed {
+        loan_id: u64,
+        borrower: Option<String>,
+    },
+
+    /// Move a matured fixed-rate loan into the variable-rate loan
+    /// of the borrower; anyone can execute it
+    RolloverFixedLoan {
+        borrower: String,
+        loan_id: u64,
+    },
+
     /// Claim distributed ANC rewards
     ClaimRewards {
         to: Option<String>,
     },
+
+    /// Approve an operator to act on the position of the sender
+    /// until expires_at; replaces any previous approval
+    ApproveOperator {
+        operator: String,
+        permissions: Vec<OperatorPermission>,
+        borrow_allowance: Option<Uint256>,
+        expires_at: Option<u64>,
+    },
+    RevokeOperator {
+        operator: String,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -102,21 +182,38 @@ pub enum Cw20HookMsg {
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     Config {},
+    OwnershipProposal {},
+    PauseInfo {},
     State {
-        block_height: Option<u64>,
+        block_time: Option<u64>,
     },
     EpochState {
-        block_height: Option<u64>,
+        block_time: Option<u64>,
         distributed_interest: Option<Uint256>,
     },
     BorrowerInfo {
         borrower: String,
-        block_height: Option<u64>,
+        block_time: Option<u64>,
     },
     BorrowerInfos {
         start_after: Option<String>,
         limit: Option<u32>,
     },
+    FixedLoans {
+        borrower: String,
+        block_time: Option<u64>,
+    },
+    OperatorApproval {
+        owner: String,
+        operator: String,
+    },
+    OperatorApprovals {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Compares the aterra supply ledger with the token supply
+    SupplyReconciliation {},
 }
 
 // We define a custom struct for each query response
@@ -131,6 +228,8 @@ pub struct ConfigResponse {
     pub distributor_contract: String,
     pub stable_denom: String,
     pub max_borrow_factor: Decimal256,
+    pub flash_loan_fee: Option<Decimal256>,
+    pub fixed_rate_premium: Option<Decimal256>,
 }
 
 // We define a custom struct for each query response
@@ -143,10 +242,19 @@ pub struct StateResponse {
     pub global_interest_index: Decimal256,
     pub global_reward_index: Decimal256,
     pub anc_emission_rate: Decimal256,
+    pub aterra_supply: Uint256,
     pub prev_aterra_supply: Uint256,
     pub prev_exchange_rate: Decimal256,
 }
 
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SupplyReconciliationResponse {
+    pub ledger_supply: Uint256,
+    pub token_supply: Uint256,
+    pub reconciled: bool,
+}
+
 // We define a custom struct for each query response
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct EpochStateResponse {
@@ -161,15 +269,53 @@ pub struct BorrowerInfoResponse {
     pub interest_index: Decimal256,
     pub reward_index: Decimal256,
     pub loan_amount: Uint256,
+    /// Principal and interest of the fixed-rate loans
+    pub fixed_loan_amount: Uint256,
     pub pending_rewards: Decimal256,
 }
 
+impl BorrowerInfoResponse {
+    /// Debt of the variable and fixed-rate loans together
+    pub fn total_loan_amount(&self) -> Uint256 {
+        self.loan_amount + self.fixed_loan_amount
+    }
+}
+
 // We define a custom struct for each query response
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct BorrowerInfosResponse {
     pub borrower_infos: Vec<BorrowerInfoResponse>,
 }
 
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FixedLoanResponse {
+    pub loan_id: u64,
+    pub principal: Uint256,
+    /// Principal and accrued interest
+    pub loan_amount: Uint256,
+    pub rate: Decimal256,
+    pub maturity: Option<u64>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FixedLoansResponse {
+    pub borrower: String,
+    pub loans: Vec<FixedLoanResponse>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
-pub struct MigrateMsg {}
+pub enum MigrateMsg {
+    /// Migrates a market deployed before contract versioning,
+    /// settling the block based state into the time based one
+    FromLegacy {
+        /// Per-block borrow rate of the legacy interest model
+        borrow_rate: Decimal256,
+        /// Per-block target deposit rate of the legacy overseer
+        target_deposit_rate: Decimal256,
+    },
+    /// Upgrades a versioned market; the state layout is unchanged
+    Upgrade {},
+}
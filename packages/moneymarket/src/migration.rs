@@ -0,0 +1,57 @@
+use cosmwasm_std::{StdError, StdResult, Storage};
+use cw2::{ContractVersion, CONTRACT};
+
+pub fn read_contract_version(storage: &dyn Storage) -> StdResult<Option<ContractVersion>> {
+    CONTRACT.may_load(storage)
+}
+
+/// Contracts deployed before cw2 versioning carry no contract version;
+/// only those can be migrated from the legacy layout
+pub fn assert_legacy(storage: &dyn Storage) -> StdResult<()> {
+    match read_contract_version(storage)? {
+        Some(stored) => Err(StdError::generic_err(format!(
+            "Contract is already at version {}; legacy migration not allowed",
+            stored.version
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Checks that the stored contract can be upgraded to the given
+/// name and version; downgrades are rejected
+pub fn assert_upgrade(storage: &dyn Storage, name: &str, version: &str) -> StdResult<()> {
+    let stored = match read_contract_version(storage)? {
+        Some(stored) => stored,
+        None => {
+            return Err(StdError::generic_err(
+                "Contract version is missing; migrate from the legacy layout",
+            ))
+        }
+    };
+
+    if stored.contract != name {
+        return Err(StdError::generic_err(format!(
+            "Cannot migrate {} to {}",
+            stored.contract, name
+        )));
+    }
+
+    if parse_version(&stored.version)? > parse_version(version)? {
+        return Err(StdError::generic_err(format!(
+            "Cannot migrate from version {} to older version {}",
+            stored.version, version
+        )));
+    }
+
+    Ok(())
+}
+
+fn parse_version(version: &str) -> StdResult<Vec<u64>> {
+    version
+        .split('.')
+        .map(|v| {
+            v.parse::<u64>()
+                .map_err(|_| StdError::generic_err(format!("Invalid version: {}", version)))
+        })
+        .collect()
+}
@@ -0,0 +1,293 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use cosmwasm_bignumber::Uint256;
+use cosmwasm_std::{
+    attr, CanonicalAddr, Deps, DepsMut, Env, MessageInfo, Order, Response, StdError, StdResult,
+    Storage,
+};
+use cosmwasm_storage::{Bucket, ReadonlyBucket};
+
+const PREFIX_OPERATOR_APPROVAL: &[u8] = b"operator_approval";
+
+/// Operations an operator can execute on the position of its owner
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OperatorPermission {
+    Repay,
+    /// Fixed-rate borrows, limited by the borrow allowance of the approval
+    Borrow,
+    /// Unlocked collaterals stay with the owner
+    Unlock,
+}
+
+impl fmt::Display for OperatorPermission {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OperatorPermission::Repay => write!(f, "repay"),
+            OperatorPermission::Borrow => write!(f, "borrow"),
+            OperatorPermission::Unlock => write!(f, "unlock"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OperatorApproval {
+    pub permissions: Vec<OperatorPermission>,
+    /// Amount the operator can still borrow for the owner
+    pub borrow_allowance: Uint256,
+    /// Block time from which the approval is no longer valid
+    pub expires_at: Option<u64>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OperatorApprovalResponse {
+    pub owner: String,
+    pub operator: String,
+    pub permissions: Vec<OperatorPermission>,
+    pub borrow_allowance: Uint256,
+    pub expires_at: Option<u64>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OperatorApprovalsResponse {
+    pub approvals: Vec<OperatorApprovalResponse>,
+}
+
+pub fn store_operator_approval(
+    storage: &mut dyn Storage,
+    owner: &CanonicalAddr,
+    operator: &CanonicalAddr,
+    data: &OperatorApproval,
+) -> StdResult<()> {
+    Bucket::multilevel(storage, &[PREFIX_OPERATOR_APPROVAL, owner.as_slice()])
+        .save(operator.as_slice(), data)
+}
+
+pub fn read_operator_approval(
+    storage: &dyn Storage,
+    owner: &CanonicalAddr,
+    operator: &CanonicalAddr,
+) -> StdResult<Option<OperatorApproval>> {
+    ReadonlyBucket::multilevel(storage, &[PREFIX_OPERATOR_APPROVAL, owner.as_slice()])
+        .may_load(operator.as_slice())
+}
+
+pub fn remove_operator_approval(
+    storage: &mut dyn Storage,
+    owner: &CanonicalAddr,
+    operator: &CanonicalAddr,
+) {
+    Bucket::<OperatorApproval>::multilevel(storage, &[PREFIX_OPERATOR_APPROVAL, owner.as_slice()])
+        .remove(operator.as_slice())
+}
+
+/// Checks that the operator holds an unexpired approval
+/// with the given permission from the owner
+pub fn assert_operator(
+    storage: &dyn Storage,
+    owner: &CanonicalAddr,
+    operator: &CanonicalAddr,
+    permission: OperatorPermission,
+    block_time: u64,
+) -> StdResult<OperatorApproval> {
+    let approval = match read_operator_approval(storage, owner, operator)? {
+        Some(approval) if approval.permissions.contains(&permission) => approval,
+        _ => return Err(StdError::generic_err("unauthorized")),
+    };
+
+    if let Some(expires_at) = approval.expires_at {
+        if block_time >= expires_at {
+            return Err(StdError::generic_err("Operator approval expired"));
+        }
+    }
+
+    Ok(approval)
+}
+
+/// Checks the borrow permission of the operator and
+/// deducts the amount from its allowance
+pub fn use_borrow_allowance(
+    storage: &mut dyn Storage,
+    owner: &CanonicalAddr,
+    operator: &CanonicalAddr,
+    amount: Uint256,
+    block_time: u64,
+) -> StdResult<()> {
+    let mut approval = assert_operator(
+        storage,
+        owner,
+        operator,
+        OperatorPermission::Borrow,
+        block_time,
+    )?;
+
+    if amount > approval.borrow_allowance {
+        return Err(StdError::generic_err(format!(
+            "Borrow amount exceeds the operator allowance: {}",
+            approval.borrow_allowance
+        )));
+    }
+
+    approval.borrow_allowance = approval.borrow_allowance - amount;
+    store_operator_approval(storage, owner, operator, &approval)
+}
+
+/// Approves the operator for the given permissions, replacing
+/// any previous approval; only the permissions the contract
+/// supports can be granted
+/// Executor: position owner
+#[allow(clippy::too_many_arguments)]
+pub fn approve_operator<T>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    supported_permissions: &[OperatorPermission],
+    operator: String,
+    permissions: Vec<OperatorPermission>,
+    borrow_allowance: Option<Uint256>,
+    expires_at: Option<u64>,
+) -> StdResult<Response<T>>
+where
+    T: Clone + fmt::Debug + PartialEq + JsonSchema,
+{
+    let operator = deps.api.addr_validate(&operator)?;
+    if operator == info.sender {
+        return Err(StdError::generic_err("Cannot approve oneself as operator"));
+    }
+
+    if let Some(permission) = permissions
+        .iter()
+        .find(|permission| !supported_permissions.contains(*permission))
+    {
+        return Err(StdError::generic_err(format!(
+            "Unsupported operator permission: {}",
+            permission
+        )));
+    }
+
+    if let Some(expires_at) = expires_at {
+        if expires_at <= env.block.time.seconds() {
+            return Err(StdError::generic_err(
+                "Operator approval must expire after the current block time",
+            ));
+        }
+    }
+
+    store_operator_approval(
+        deps.storage,
+        &deps.api.addr_canonicalize(info.sender.as_str())?,
+        &deps.api.addr_canonicalize(operator.as_str())?,
+        &OperatorApproval {
+            permissions: permissions.clone(),
+            borrow_allowance: borrow_allowance.unwrap_or_else(Uint256::zero),
+            expires_at,
+        },
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "approve_operator"),
+        attr("owner", info.sender),
+        attr("operator", operator),
+        attr("permissions", join_permissions(&permissions)),
+    ]))
+}
+
+/// Executor: position owner
+pub fn revoke_operator<T>(
+    deps: DepsMut,
+    info: MessageInfo,
+    operator: String,
+) -> StdResult<Response<T>>
+where
+    T: Clone + fmt::Debug + PartialEq + JsonSchema,
+{
+    let operator = deps.api.addr_validate(&operator)?;
+    remove_operator_approval(
+        deps.storage,
+        &deps.api.addr_canonicalize(info.sender.as_str())?,
+        &deps.api.addr_canonicalize(operator.as_str())?,
+    );
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "revoke_operator"),
+        attr("owner", info.sender),
+        attr("operator", operator),
+    ]))
+}
+
+pub fn query_operator_approval(
+    deps: Deps,
+    owner: String,
+    operator: String,
+) -> StdResult<OperatorApprovalResponse> {
+    let owner_raw = deps.api.addr_canonicalize(&owner)?;
+    let operator_raw = deps.api.addr_canonicalize(&operator)?;
+    match read_operator_approval(deps.storage, &owner_raw, &operator_raw)? {
+        Some(approval) => Ok(OperatorApprovalResponse {
+            owner,
+            operator,
+            permissions: approval.permissions,
+            borrow_allowance: approval.borrow_allowance,
+            expires_at: approval.expires_at,
+        }),
+        None => Err(StdError::generic_err("No operator approval")),
+    }
+}
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+pub fn query_operator_approvals(
+    deps: Deps,
+    owner: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<OperatorApprovalsResponse> {
+    let owner_raw = deps.api.addr_canonicalize(&owner)?;
+    let start = match start_after {
+        Some(start_after) => {
+            // set the first key after the provided key, by appending a 1 byte
+            let mut v = deps
+                .api
+                .addr_canonicalize(&start_after)?
+                .as_slice()
+                .to_vec();
+            v.push(1);
+            Some(v)
+        }
+        None => None,
+    };
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let approvals: Vec<OperatorApprovalResponse> = ReadonlyBucket::<OperatorApproval>::multilevel(
+        deps.storage,
+        &[PREFIX_OPERATOR_APPROVAL, owner_raw.as_slice()],
+    )
+    .range(start.as_deref(), None, Order::Ascending)
+    .take(limit)
+    .map(|elem| {
+        let (k, v) = elem?;
+        Ok(OperatorApprovalResponse {
+            owner: owner.clone(),
+            operator: deps.api.addr_humanize(&CanonicalAddr::from(k))?.to_string(),
+            permissions: v.permissions,
+            borrow_allowance: v.borrow_allowance,
+            expires_at: v.expires_at,
+        })
+    })
+    .collect::<StdResult<Vec<OperatorApprovalResponse>>>()?;
+
+    Ok(OperatorApprovalsResponse { approvals })
+}
+
+fn join_permissions(permissions: &[OperatorPermission]) -> String {
+    permissions
+        .iter()
+        .map(|permission| permission.to_string())
+        .collect::<Vec<String>>()
+        .join(",")
+}
@@ -1,6 +1,8 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::operator::OperatorPermission;
+use crate::pause::PauseAction;
 use crate::tokens::TokensHuman;
 use cosmwasm_bignumber::{Decimal256, Uint256};
 
@@ -20,12 +22,12 @@ pub struct InstantiateMsg {
     /// The base denomination used when fetching oracle price,
     /// reward distribution, and borrow
     pub stable_denom: String,
-    /// # of blocks per epoch period
+    /// # of seconds per epoch period
     pub epoch_period: u64,
     /// Distribute interest buffer to market contract,
-    /// when deposit_rate < threshold_deposit_rate
+    /// when deposit_rate < threshold_deposit_rate (per second)
     pub threshold_deposit_rate: Decimal256,
-    /// Target deposit rate.
+    /// Target deposit rate per second.
     /// When current deposit rate is bigger than this,
     /// Custody contracts send rewards to interest buffer
     pub target_deposit_rate: Decimal256,
@@ -35,20 +37,48 @@ pub struct InstantiateMsg {
     pub anc_purchase_factor: Decimal256,
     /// Valid oracle price timeframe
     pub price_timeframe: u64,
-    /// # of blocks per each dynamic rate change period
+    /// # of seconds per each dynamic rate change period
     pub dyn_rate_epoch: u64,
-    /// maximum rate change during update
-    pub dyn_rate_maxchange: Decimal256,
-    /// amount of slack in yr change to trigger rate update
-    pub dyn_rate_yr_increase_expectation: Decimal256,
-    /// clamps for dyn rate
+    /// Deposit rate model contract address to compute the
+    /// new deposit rate every dynamic rate change period
+    pub deposit_rate_model: String,
+    /// clamps for dyn rate (per second)
     pub dyn_rate_min: Decimal256,
     pub dyn_rate_max: Decimal256,
+    /// Address allowed to write off bad debt besides the owner
+    pub bad_debt_handler: Option<String>,
+    /// Maximum amount of bad debt which can be written off
+    /// from the interest buffer per epoch period
+    pub max_write_off_per_epoch: Uint256,
+    /// Maximum share of the loan a single liquidation can repay
+    pub close_factor: Decimal256,
+    /// # of blocks between two liquidations of the same borrower
+    pub liquidation_interval: u64,
+    /// Seconds a flagged borrower has to restore the loan before
+    /// it can be liquidated; zero disables margin calls
+    pub margin_call_grace_period: u64,
+    /// Loans above this LTV can be liquidated during the grace period
+    pub hard_liquidation_ltv: Decimal256,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
-pub struct MigrateMsg {}
+pub enum MigrateMsg {
+    /// Migrates an overseer deployed before contract versioning; the
+    /// parameters initialize the config entries introduced since then
+    FromLegacy {
+        /// Deposit rate model replacing the built-in dynamic rate computation
+        deposit_rate_model: String,
+        bad_debt_handler: Option<String>,
+        max_write_off_per_epoch: Uint256,
+        close_factor: Decimal256,
+        liquidation_interval: u64,
+        margin_call_grace_period: u64,
+        hard_liquidation_ltv: Decimal256,
+    },
+    /// Upgrades a versioned overseer; the state layout is unchanged
+    Upgrade {},
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
@@ -60,7 +90,6 @@ pub enum ExecuteMsg {
 
     /// Update Configs
     UpdateConfig {
-        owner_addr: Option<String>,
         oracle_contract: Option<String>,
         liquidation_contract: Option<String>,
         threshold_deposit_rate: Option<Decimal256>,
@@ -70,24 +99,82 @@ pub enum ExecuteMsg {
         epoch_period: Option<u64>,
         price_timeframe: Option<u64>,
         dyn_rate_epoch: Option<u64>,
-        dyn_rate_maxchange: Option<Decimal256>,
-        dyn_rate_yr_increase_expectation: Option<Decimal256>,
+        deposit_rate_model: Option<String>,
         dyn_rate_min: Option<Decimal256>,
         dyn_rate_max: Option<Decimal256>,
+        bad_debt_handler: Option<String>,
+        max_write_off_per_epoch: Option<Uint256>,
+        close_factor: Option<Decimal256>,
+        liquidation_interval: Option<u64>,
+        margin_call_grace_period: Option<u64>,
+        hard_liquidation_ltv: Option<Decimal256>,
+    },
+    /// Propose a new owner, who can claim the ownership
+    /// within expires_in seconds
+    ProposeNewOwner {
+        owner: String,
+        expires_in: u64,
+    },
+    /// Remove the pending ownership proposal
+    DropOwnershipProposal {},
+    /// Executor: proposed owner
+    ClaimOwnership {},
+    /// Set or remove the guardian
+    UpdateGuardian {
+        guardian: Option<String>,
+    },
+    /// Executor: owner or guardian
+    Pause {
+        actions: Vec<PauseAction>,
+    },
+    /// Executor: owner
+    Unpause {
+        actions: Vec<PauseAction>,
     },
     /// Create new custody contract for the given collateral token
     Whitelist {
-        name: String,             // bAsset name
-        symbol: String,           // bAsset symbol
-        collateral_token: String, // bAsset token contract
-        custody_contract: String, // bAsset custody contract
-        max_ltv: Decimal256,      // Loan To Value ratio
+        name: String,                           // bAsset name
+        symbol: String,                         // bAsset symbol
+        collateral_token: String,               // bAsset token contract
+        custody_contract: String,               // bAsset custody contract
+        max_ltv: Decimal256,                    // Loan To Value ratio
+        liquidation_ltv: Decimal256,            // Loan To Value ratio at which liquidation starts
+        max_collateral_amount: Option<Uint256>, // Cap on the total locked amount
+        max_borrow_value: Option<Uint256>,      // Cap on the total borrow limit provided
     },
-    /// Update registered whitelist info
+    /// Update registered whitelist info;
+    /// a zero cap removes the cap and with max_ltv_ramp_seconds
+    /// max_ltv moves linearly to its new value over that many seconds
     UpdateWhitelist {
-        collateral_token: String,         // bAsset token contract
-        custody_contract: Option<String>, // bAsset custody contract
-        max_ltv: Option<Decimal256>,      // Loan To Value ratio
+        collateral_token: String,               // bAsset token contract
+        custody_contract: Option<String>,       // bAsset custody contract
+        max_ltv: Option<Decimal256>,            // Loan To Value ratio
+        liquidation_ltv: Option<Decimal256>,    // Loan To Value ratio at which liquidation starts
+        max_collateral_amount: Option<Uint256>, // Cap on the total locked amount
+        max_borrow_value: Option<Uint256>,      // Cap on the total borrow limit provided
+        max_ltv_ramp_seconds: Option<u64>,
+    },
+    /// Stop new locks and borrows against a whitelisted collateral
+    PauseCollateral {
+        collateral_token: String,
+    },
+    /// Set a paused or deprecated collateral back to active
+    ActivateCollateral {
+        collateral_token: String,
+    },
+    /// Ramp the max_ltv of a collateral down to zero over ramp_seconds
+    DeprecateCollateral {
+        collateral_token: String,
+        ramp_seconds: u64,
+    },
+    /// Remove a collateral from the whitelist once no borrower holds it
+    Delist {
+        collateral_token: String,
+    },
+    /// Send stable tokens from the interest buffer to the recipient
+    WithdrawReserve {
+        amount: Uint256,
+        recipient: String,
     },
 
     /// Claims all staking rewards from the bAsset contracts
@@ -96,9 +183,22 @@ pub enum ExecuteMsg {
     /// 2. Invoke [Custody] DistributeRewards
     /// 3. Update epoch state
     ExecuteEpochOperations {},
-    UpdateEpochState {
-        interest_buffer: Uint256,
-        distributed_interest: Uint256,
+    /// Records the loan left over after a liquidation
+    /// emptied the borrower's collaterals
+    RecordBadDebt {
+        borrower: String,
+    },
+    /// Clears the margin call of a borrower whose loan is safe
+    /// again after a repayment and updates its recorded bad debt
+    ClearMarginCall {
+        borrower: String,
+    },
+    /// Sends the collaterals computed by LiquidateCollaterals
+    /// to the liquidation and repays the loan
+    ExecuteLiquidation {
+        liquidator: String,
+        borrower: String,
+        collaterals: TokensHuman,
     },
 
     ////////////////////
@@ -109,6 +209,19 @@ pub enum ExecuteMsg {
     },
     UnlockCollateral {
         collaterals: TokensHuman, // <(Collateral Token, Amount)>
+        /// Position to unlock from, for approved operators
+        borrower: Option<String>,
+    },
+    /// Approve an operator to unlock collaterals of the sender
+    /// until expires_at; replaces any previous approval
+    ApproveOperator {
+        operator: String,
+        permissions: Vec<OperatorPermission>,
+        borrow_allowance: Option<Uint256>,
+        expires_at: Option<u64>,
+    },
+    RevokeOperator {
+        operator: String,
     },
 
     /////////////////////////////
@@ -117,9 +230,24 @@ pub enum ExecuteMsg {
     LiquidateCollateral {
         borrower: String,
     },
+    /// Starts the grace period of a liquidatable borrower, or
+    /// clears the flag of a borrower whose loan is safe again
+    FlagBorrower {
+        borrower: String,
+    },
+    /// Liquidates every unsafe loan in the list; safe loans are skipped
+    LiquidateCollaterals {
+        borrowers: Vec<String>,
+    },
 
     FundReserve {},
 
+    ////////////////////
+    /// Owner or bad debt handler operations
+    ////////////////////
+
+    /// Write off the loan of an insolvent borrower
+    /// with the interest buffer
     RepayStableFromYieldReserve {
         borrower: String,
     },
@@ -129,6 +257,8 @@ pub enum ExecuteMsg {
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     Config {},
+    OwnershipProposal {},
+    PauseInfo {},
     EpochState {},
     DynrateState {},
     Whitelist {
@@ -136,6 +266,13 @@ pub enum QueryMsg {
         start_after: Option<String>,
         limit: Option<u32>,
     },
+    /// Returns the total locked amount and borrow value
+    /// of each whitelisted collateral against its caps
+    CollateralUsage {
+        collateral_token: Option<String>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
     Collaterals {
         borrower: String,
     },
@@ -147,6 +284,48 @@ pub enum QueryMsg {
         borrower: String,
         block_time: Option<u64>,
     },
+    BorrowerHealth {
+        borrower: String,
+        block_time: Option<u64>,
+    },
+    MarginCall {
+        borrower: String,
+    },
+    /// Scans up to `limit` borrowers with collaterals and returns
+    /// the ones whose loan exceeds their liquidation limit
+    LiquidatableBorrowers {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    WriteOffState {},
+    WriteOffs {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    BadDebt {
+        borrower: String,
+    },
+    AllBadDebts {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    TotalBadDebt {},
+    /// Returns the snapshots of the recent epochs, oldest first
+    EpochHistory {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Breaks the interest buffer down into its cumulative flows
+    Reserve {},
+    OperatorApproval {
+        owner: String,
+        operator: String,
+    },
+    OperatorApprovals {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
 }
 
 // We define a custom struct for each query response
@@ -165,10 +344,15 @@ pub struct ConfigResponse {
     pub epoch_period: u64,
     pub price_timeframe: u64,
     pub dyn_rate_epoch: u64,
-    pub dyn_rate_maxchange: Decimal256,
-    pub dyn_rate_yr_increase_expectation: Decimal256,
+    pub deposit_rate_model: String,
     pub dyn_rate_min: Decimal256,
     pub dyn_rate_max: Decimal256,
+    pub bad_debt_handler: Option<String>,
+    pub max_write_off_per_epoch: Uint256,
+    pub close_factor: Decimal256,
+    pub liquidation_interval: u64,
+    pub margin_call_grace_period: u64,
+    pub hard_liquidation_ltv: Decimal256,
 }
 
 // We define a custom struct for each query response
@@ -177,8 +361,34 @@ pub struct WhitelistResponseElem {
     pub name: String,
     pub symbol: String,
     pub max_ltv: Decimal256,
+    pub liquidation_ltv: Decimal256,
     pub custody_contract: String,
     pub collateral_token: String,
+    pub max_collateral_amount: Option<Uint256>,
+    pub max_borrow_value: Option<Uint256>,
+    pub status: WhitelistStatus,
+    pub max_ltv_ramp: Option<MaxLtvRamp>,
+}
+
+/// Schedule of a max_ltv change; max_ltv is reached at end_time
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MaxLtvRamp {
+    pub start_ltv: Decimal256,
+    pub start_time: u64,
+    pub end_time: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WhitelistStatus {
+    Active,
+    /// No new locks or borrows against the collateral
+    Paused,
+    /// max_ltv ramps down linearly from start_time and is zero from end_time
+    Deprecated {
+        start_time: u64,
+        end_time: u64,
+    },
 }
 
 // We define a custom struct for each query response
@@ -187,6 +397,23 @@ pub struct WhitelistResponse {
     pub elems: Vec<WhitelistResponseElem>,
 }
 
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollateralUsageResponseElem {
+    pub collateral_token: String,
+    pub total_amount: Uint256,
+    pub max_collateral_amount: Option<Uint256>,
+    /// Borrow limit provided by all locked collaterals
+    pub borrow_value: Uint256,
+    pub max_borrow_value: Option<Uint256>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollateralUsageResponse {
+    pub elems: Vec<CollateralUsageResponseElem>,
+}
+
 // We define a custom struct for each query response
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct CollateralsResponse {
@@ -205,3 +432,138 @@ pub struct BorrowLimitResponse {
     pub borrower: String,
     pub borrow_limit: Uint256,
 }
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BorrowerHealthResponse {
+    pub borrower: String,
+    pub collaterals_value: Uint256,
+    pub borrow_limit: Uint256,
+    pub liquidation_limit: Uint256,
+    pub loan_amount: Uint256,
+    /// loan_amount / collaterals_value; None without collaterals
+    pub ltv: Option<Decimal256>,
+    /// Fraction all collateral prices can drop by before
+    /// the loan becomes liquidatable
+    pub liquidation_price_drop: Decimal256,
+    pub collaterals: Vec<CollateralHealthResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollateralHealthResponse {
+    pub collateral_token: String,
+    pub amount: Uint256,
+    pub price: Decimal256,
+    pub value: Uint256,
+    /// Share of the total collaterals value
+    pub weight: Decimal256,
+    pub max_ltv: Decimal256,
+    pub liquidation_ltv: Decimal256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MarginCallResponse {
+    pub borrower: String,
+    /// Block time the borrower was flagged at
+    pub flagged_time: Option<u64>,
+    /// Block time from which the borrower can be liquidated
+    pub grace_period_end: Option<u64>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LiquidatableBorrowerResponse {
+    pub borrower: String,
+    pub loan_amount: Uint256,
+    pub liquidation_limit: Uint256,
+    pub shortfall: Uint256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LiquidatableBorrowersResponse {
+    pub borrowers: Vec<LiquidatableBorrowerResponse>,
+    /// Pass as `start_after` to continue the scan; None once the
+    /// end of the borrowers was reached
+    pub last_scanned_borrower: Option<String>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WriteOffResponse {
+    pub borrower: String,
+    pub amount: Uint256,
+    pub last_written_off_height: u64,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WriteOffsResponse {
+    pub write_offs: Vec<WriteOffResponse>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BadDebtResponse {
+    pub borrower: String,
+    pub amount: Uint256,
+    pub recorded_height: u64,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BadDebtsResponse {
+    pub bad_debts: Vec<BadDebtResponse>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TotalBadDebtResponse {
+    pub total_bad_debt: Uint256,
+}
+
+/// Outcome of a dynamic rate re-evaluation
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DynrateDecision {
+    pub yield_reserve: Decimal256,
+    pub prev_deposit_rate: Decimal256,
+    pub threshold_deposit_rate: Decimal256,
+    pub target_deposit_rate: Decimal256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EpochHistoryResponseElem {
+    pub epoch: u64,
+    pub height: u64,
+    pub time: u64,
+    pub deposit_rate: Decimal256,
+    pub exchange_rate: Decimal256,
+    pub aterra_supply: Uint256,
+    pub interest_buffer: Uint256,
+    pub distributed_interest: Uint256,
+    /// Set when the deposit rates were re-evaluated during the epoch
+    pub dynrate: Option<DynrateDecision>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EpochHistoryResponse {
+    pub epochs: Vec<EpochHistoryResponseElem>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReserveResponse {
+    /// Current stable balance of the overseer
+    pub balance: Uint256,
+    pub total_funded: Uint256,
+    pub total_accrued: Uint256,
+    /// Sent to the market to cover the threshold deposit rate
+    pub total_distributed: Uint256,
+    /// Sent to the collector for ANC purchases
+    pub total_anc_purchased: Uint256,
+    pub total_written_off: Uint256,
+    pub total_withdrawn: Uint256,
+}
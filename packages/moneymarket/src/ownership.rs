@@ -0,0 +1,147 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use cosmwasm_std::{
+    attr, CanonicalAddr, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult, Storage,
+};
+use cosmwasm_storage::{ReadonlySingleton, Singleton};
+
+const KEY_OWNERSHIP_PROPOSAL: &[u8] = b"ownership_proposal";
+
+/// Longest time a proposed owner is given to claim the ownership
+pub const MAX_OWNERSHIP_PROPOSAL_EXPIRY: u64 = 1_209_600;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OwnershipProposal {
+    pub owner: CanonicalAddr,
+    /// Block time from which the proposal can no longer be claimed
+    pub expires_at: u64,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OwnershipProposalResponse {
+    pub owner: String,
+    pub expires_at: u64,
+}
+
+pub fn store_ownership_proposal(
+    storage: &mut dyn Storage,
+    data: &OwnershipProposal,
+) -> StdResult<()> {
+    Singleton::new(storage, KEY_OWNERSHIP_PROPOSAL).save(data)
+}
+
+pub fn read_ownership_proposal(storage: &dyn Storage) -> StdResult<Option<OwnershipProposal>> {
+    ReadonlySingleton::new(storage, KEY_OWNERSHIP_PROPOSAL).may_load()
+}
+
+pub fn remove_ownership_proposal(storage: &mut dyn Storage) {
+    Singleton::<OwnershipProposal>::new(storage, KEY_OWNERSHIP_PROPOSAL).remove()
+}
+
+/// Proposes a new owner, replacing any pending proposal
+/// Executor: owner
+pub fn propose_new_owner<T>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    owner: CanonicalAddr,
+    new_owner: String,
+    expires_in: u64,
+) -> StdResult<Response<T>>
+where
+    T: Clone + fmt::Debug + PartialEq + JsonSchema,
+{
+    if deps.api.addr_canonicalize(info.sender.as_str())? != owner {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    if expires_in > MAX_OWNERSHIP_PROPOSAL_EXPIRY {
+        return Err(StdError::generic_err(format!(
+            "Ownership proposal cannot expire later than {} seconds",
+            MAX_OWNERSHIP_PROPOSAL_EXPIRY
+        )));
+    }
+
+    let new_owner = deps.api.addr_validate(&new_owner)?;
+    let expires_at = env.block.time.seconds() + expires_in;
+    store_ownership_proposal(
+        deps.storage,
+        &OwnershipProposal {
+            owner: deps.api.addr_canonicalize(new_owner.as_str())?,
+            expires_at,
+        },
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "propose_new_owner"),
+        attr("new_owner", new_owner),
+        attr("expires_at", expires_at.to_string()),
+    ]))
+}
+
+/// Removes the pending ownership proposal
+/// Executor: owner
+pub fn drop_ownership_proposal<T>(
+    deps: DepsMut,
+    info: MessageInfo,
+    owner: CanonicalAddr,
+) -> StdResult<Response<T>>
+where
+    T: Clone + fmt::Debug + PartialEq + JsonSchema,
+{
+    if deps.api.addr_canonicalize(info.sender.as_str())? != owner {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    remove_ownership_proposal(deps.storage);
+
+    Ok(Response::new().add_attributes(vec![attr("action", "drop_ownership_proposal")]))
+}
+
+/// Hands the ownership over to the proposed owner; update_owner
+/// stores the new owner in the config of the contract
+/// Executor: proposed owner
+pub fn claim_ownership<T, F>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    update_owner: F,
+) -> StdResult<Response<T>>
+where
+    T: Clone + fmt::Debug + PartialEq + JsonSchema,
+    F: FnOnce(DepsMut, CanonicalAddr) -> StdResult<()>,
+{
+    let proposal = match read_ownership_proposal(deps.storage)? {
+        Some(proposal) => proposal,
+        None => return Err(StdError::generic_err("No ownership proposal to claim")),
+    };
+
+    if deps.api.addr_canonicalize(info.sender.as_str())? != proposal.owner {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    if env.block.time.seconds() >= proposal.expires_at {
+        return Err(StdError::generic_err("Ownership proposal expired"));
+    }
+
+    remove_ownership_proposal(deps.storage);
+    update_owner(deps, proposal.owner)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "claim_ownership"),
+        attr("new_owner", info.sender),
+    ]))
+}
+
+pub fn query_ownership_proposal(deps: Deps) -> StdResult<OwnershipProposalResponse> {
+    match read_ownership_proposal(deps.storage)? {
+        Some(proposal) => Ok(OwnershipProposalResponse {
+            owner: deps.api.addr_humanize(&proposal.owner)?.to_string(),
+            expires_at: proposal.expires_at,
+        }),
+        None => Err(StdError::generic_err("No ownership proposal")),
+    }
+}
@@ -0,0 +1,169 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use cosmwasm_std::{
+    attr, CanonicalAddr, Deps, DepsMut, MessageInfo, Response, StdError, StdResult, Storage,
+};
+use cosmwasm_storage::{ReadonlySingleton, Singleton};
+
+const KEY_PAUSE_INFO: &[u8] = b"pause_info";
+
+/// Operations that can be halted in an emergency. Repayments are
+/// deliberately left out so that loans can always be closed.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PauseAction {
+    Deposit,
+    Redeem,
+    Borrow,
+    Unlock,
+    Liquidate,
+    Bid,
+}
+
+impl fmt::Display for PauseAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PauseAction::Deposit => write!(f, "deposit"),
+            PauseAction::Redeem => write!(f, "redeem"),
+            PauseAction::Borrow => write!(f, "borrow"),
+            PauseAction::Unlock => write!(f, "unlock"),
+            PauseAction::Liquidate => write!(f, "liquidate"),
+            PauseAction::Bid => write!(f, "bid"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PauseInfo {
+    /// Address allowed to pause actions next to the owner
+    pub guardian: Option<CanonicalAddr>,
+    pub paused: Vec<PauseAction>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PauseInfoResponse {
+    pub guardian: Option<String>,
+    pub paused: Vec<PauseAction>,
+}
+
+pub fn store_pause_info(storage: &mut dyn Storage, data: &PauseInfo) -> StdResult<()> {
+    Singleton::new(storage, KEY_PAUSE_INFO).save(data)
+}
+
+pub fn read_pause_info(storage: &dyn Storage) -> StdResult<PauseInfo> {
+    match ReadonlySingleton::new(storage, KEY_PAUSE_INFO).may_load()? {
+        Some(pause_info) => Ok(pause_info),
+        None => Ok(PauseInfo {
+            guardian: None,
+            paused: vec![],
+        }),
+    }
+}
+
+pub fn assert_not_paused(storage: &dyn Storage, action: PauseAction) -> StdResult<()> {
+    if read_pause_info(storage)?.paused.contains(&action) {
+        return Err(StdError::generic_err(format!("{} is paused", action)));
+    }
+
+    Ok(())
+}
+
+/// Sets the guardian, or removes it when None is given
+/// Executor: owner
+pub fn update_guardian<T>(
+    deps: DepsMut,
+    info: MessageInfo,
+    owner: CanonicalAddr,
+    guardian: Option<String>,
+) -> StdResult<Response<T>>
+where
+    T: Clone + fmt::Debug + PartialEq + JsonSchema,
+{
+    if deps.api.addr_canonicalize(info.sender.as_str())? != owner {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    let mut pause_info = read_pause_info(deps.storage)?;
+    pause_info.guardian = match guardian {
+        Some(guardian) => Some(deps.api.addr_canonicalize(&guardian)?),
+        None => None,
+    };
+    store_pause_info(deps.storage, &pause_info)?;
+
+    Ok(Response::new().add_attributes(vec![attr("action", "update_guardian")]))
+}
+
+/// Executor: owner or guardian
+pub fn pause<T>(
+    deps: DepsMut,
+    info: MessageInfo,
+    owner: CanonicalAddr,
+    actions: Vec<PauseAction>,
+) -> StdResult<Response<T>>
+where
+    T: Clone + fmt::Debug + PartialEq + JsonSchema,
+{
+    let mut pause_info = read_pause_info(deps.storage)?;
+    let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+    if sender_raw != owner && Some(sender_raw) != pause_info.guardian {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    for action in actions.iter() {
+        if !pause_info.paused.contains(action) {
+            pause_info.paused.push(*action);
+        }
+    }
+    store_pause_info(deps.storage, &pause_info)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "pause"),
+        attr("paused", join_actions(&actions)),
+    ]))
+}
+
+/// Executor: owner
+pub fn unpause<T>(
+    deps: DepsMut,
+    info: MessageInfo,
+    owner: CanonicalAddr,
+    actions: Vec<PauseAction>,
+) -> StdResult<Response<T>>
+where
+    T: Clone + fmt::Debug + PartialEq + JsonSchema,
+{
+    if deps.api.addr_canonicalize(info.sender.as_str())? != owner {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    let mut pause_info = read_pause_info(deps.storage)?;
+    pause_info.paused.retain(|action| !actions.contains(action));
+    store_pause_info(deps.storage, &pause_info)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "unpause"),
+        attr("unpaused", join_actions(&actions)),
+    ]))
+}
+
+pub fn query_pause_info(deps: Deps) -> StdResult<PauseInfoResponse> {
+    let pause_info = read_pause_info(deps.storage)?;
+    Ok(PauseInfoResponse {
+        guardian: match pause_info.guardian {
+            Some(guardian) => Some(deps.api.addr_humanize(&guardian)?.to_string()),
+            None => None,
+        },
+        paused: pause_info.paused,
+    })
+}
+
+fn join_actions(actions: &[PauseAction]) -> String {
+    actions
+        .iter()
+        .map(|action| action.to_string())
+        .collect::<Vec<String>>()
+        .join(",")
+}
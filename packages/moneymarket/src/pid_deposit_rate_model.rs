@@ -0,0 +1,63 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::Binary;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InstantiateMsg {
+    pub owner: String,
+    /// Yearly growth of the yield reserve the controller steers towards
+    pub target_growth: Decimal256,
+    pub p_gain: Decimal256,
+    pub i_gain: Decimal256,
+    pub d_gain: Decimal256,
+    /// Max change of the yearly deposit rate per evaluation
+    pub max_change: Decimal256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    UpdateConfig {
+        target_growth: Option<Decimal256>,
+        p_gain: Option<Decimal256>,
+        i_gain: Option<Decimal256>,
+        d_gain: Option<Decimal256>,
+        max_change: Option<Decimal256>,
+    },
+    /// Propose a new owner, who can claim the ownership
+    /// within expires_in seconds
+    ProposeNewOwner { owner: String, expires_in: u64 },
+    /// Remove the pending ownership proposal
+    DropOwnershipProposal {},
+    /// Executor: proposed owner
+    ClaimOwnership {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    Config {},
+    OwnershipProposal {},
+    /// Same as deposit_rate_model::QueryMsg::TargetDepositRate,
+    /// answered with a deposit_rate_model::TargetDepositRateResponse
+    TargetDepositRate {
+        yield_reserve: Decimal256,
+        prev_yield_reserve: Decimal256,
+        current_rate: Decimal256,
+        seconds: u64,
+        model_state: Option<Binary>,
+    },
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigResponse {
+    pub owner: String,
+    pub target_growth: Decimal256,
+    pub p_gain: Decimal256,
+    pub i_gain: Decimal256,
+    pub d_gain: Decimal256,
+    pub max_change: Decimal256,
+}
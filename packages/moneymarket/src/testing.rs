@@ -1,10 +1,30 @@
+use crate::common::{legacy_blocks_to_seconds, legacy_rate_to_rate_per_second};
+use crate::migration::{assert_legacy, assert_upgrade};
 use crate::mock_querier::mock_dependencies;
+use crate::operator::{
+    approve_operator, assert_operator, query_operator_approval, query_operator_approvals,
+    revoke_operator, use_borrow_allowance, OperatorApprovalResponse, OperatorApprovalsResponse,
+    OperatorPermission,
+};
 use crate::oracle::PriceResponse;
+use crate::ownership::{
+    claim_ownership, drop_ownership_proposal, propose_new_owner, query_ownership_proposal,
+    OwnershipProposalResponse,
+};
+use crate::pause::{
+    assert_not_paused, pause, query_pause_info, unpause, update_guardian, PauseAction,
+    PauseInfoResponse,
+};
 use crate::querier::{compute_tax, deduct_tax, query_price, query_tax_rate, TimeConstraints};
 use crate::tokens::{Tokens, TokensHuman, TokensMath, TokensToRaw};
 
 use cosmwasm_bignumber::{Decimal256, Uint256};
-use cosmwasm_std::{Addr, Api, CanonicalAddr, Coin, Decimal, StdError, Uint128};
+use cosmwasm_std::testing::{mock_env, mock_info};
+use cosmwasm_std::{
+    attr, Addr, Api, CanonicalAddr, Coin, Decimal, Response, StdError, StdResult, Uint128,
+};
+use cw2::set_contract_version;
+use std::str::FromStr;
 
 #[test]
 fn tax_rate_querier() {
@@ -300,3 +320,443 @@ fn token_math_invalid_token_2() {
 
     let _ = tokens_1_raw.sub(tokens_2_raw);
 }
+
+#[test]
+fn legacy_block_conversions() {
+    assert_eq!(legacy_blocks_to_seconds(4_656_810), 31_536_000);
+    assert_eq!(legacy_blocks_to_seconds(86_400), 585_102);
+    assert_eq!(
+        legacy_rate_to_rate_per_second(Decimal256::percent(1)),
+        Decimal256::from_str("0.001476664764079147").unwrap()
+    );
+}
+
+#[test]
+fn ownership_proposal() {
+    let mut deps = mock_dependencies(&[]);
+    let mut env = mock_env();
+
+    let owner = deps.api.addr_canonicalize("owner0000").unwrap();
+    let mut new_owner: Option<CanonicalAddr> = None;
+
+    // only the owner can propose
+    let res: StdResult<Response> = propose_new_owner(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[]),
+        owner.clone(),
+        "owner0001".to_string(),
+        86400u64,
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "unauthorized"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res: StdResult<Response> = propose_new_owner(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner0000", &[]),
+        owner.clone(),
+        "owner0001".to_string(),
+        1_209_601u64,
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "Ownership proposal cannot expire later than 1209600 seconds"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res: Response = propose_new_owner(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner0000", &[]),
+        owner.clone(),
+        "owner0001".to_string(),
+        86400u64,
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "propose_new_owner"),
+            attr("new_owner", "owner0001"),
+            attr(
+                "expires_at",
+                (env.block.time.seconds() + 86400u64).to_string()
+            ),
+        ]
+    );
+    assert_eq!(
+        query_ownership_proposal(deps.as_ref()).unwrap(),
+        OwnershipProposalResponse {
+            owner: "owner0001".to_string(),
+            expires_at: env.block.time.seconds() + 86400u64,
+        }
+    );
+
+    // only the proposed owner can claim
+    let res: StdResult<Response> = claim_ownership(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner0000", &[]),
+        |_, _| panic!("DO NOT ENTER HERE"),
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "unauthorized"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the proposal expires
+    let mut expired_env = env.clone();
+    expired_env.block.time = expired_env.block.time.plus_seconds(86400u64);
+    let res: StdResult<Response> = claim_ownership(
+        deps.as_mut(),
+        expired_env,
+        mock_info("owner0001", &[]),
+        |_, _| panic!("DO NOT ENTER HERE"),
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "Ownership proposal expired"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    env.block.time = env.block.time.plus_seconds(86399u64);
+    let res: Response = claim_ownership(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner0001", &[]),
+        |_, owner| {
+            new_owner = Some(owner);
+            Ok(())
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "claim_ownership"),
+            attr("new_owner", "owner0001"),
+        ]
+    );
+    assert_eq!(
+        new_owner,
+        Some(deps.api.addr_canonicalize("owner0001").unwrap())
+    );
+
+    // the proposal is consumed
+    let res: StdResult<Response> = claim_ownership(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner0001", &[]),
+        |_, _| Ok(()),
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "No ownership proposal to claim"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // a dropped proposal cannot be claimed
+    let _res: Response = propose_new_owner(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner0000", &[]),
+        owner.clone(),
+        "owner0002".to_string(),
+        86400u64,
+    )
+    .unwrap();
+    let res: Response =
+        drop_ownership_proposal(deps.as_mut(), mock_info("owner0000", &[]), owner).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![attr("action", "drop_ownership_proposal")]
+    );
+    assert!(query_ownership_proposal(deps.as_ref()).is_err());
+}
+
+#[test]
+fn pause_and_unpause() {
+    let mut deps = mock_dependencies(&[]);
+    let owner = deps.api.addr_canonicalize("owner0000").unwrap();
+
+    // nothing is paused by default
+    assert_not_paused(deps.as_ref().storage, PauseAction::Borrow).unwrap();
+    assert_eq!(
+        query_pause_info(deps.as_ref()).unwrap(),
+        PauseInfoResponse {
+            guardian: None,
+            paused: vec![],
+        }
+    );
+
+    // only the owner can set the guardian
+    let res: StdResult<Response> = update_guardian(
+        deps.as_mut(),
+        mock_info("guardian0000", &[]),
+        owner.clone(),
+        Some("guardian0000".to_string()),
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "unauthorized"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let _res: Response = update_guardian(
+        deps.as_mut(),
+        mock_info("owner0000", &[]),
+        owner.clone(),
+        Some("guardian0000".to_string()),
+    )
+    .unwrap();
+
+    // neither owner nor guardian
+    let res: StdResult<Response> = pause(
+        deps.as_mut(),
+        mock_info("addr0000", &[]),
+        owner.clone(),
+        vec![PauseAction::Borrow],
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "unauthorized"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res: Response = pause(
+        deps.as_mut(),
+        mock_info("guardian0000", &[]),
+        owner.clone(),
+        vec![PauseAction::Borrow, PauseAction::Liquidate],
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![attr("action", "pause"), attr("paused", "borrow,liquidate"),]
+    );
+
+    match assert_not_paused(deps.as_ref().storage, PauseAction::Borrow) {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "borrow is paused"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    assert_not_paused(deps.as_ref().storage, PauseAction::Deposit).unwrap();
+
+    // the guardian cannot unpause
+    let res: StdResult<Response> = unpause(
+        deps.as_mut(),
+        mock_info("guardian0000", &[]),
+        owner.clone(),
+        vec![PauseAction::Borrow],
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "unauthorized"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let _res: Response = unpause(
+        deps.as_mut(),
+        mock_info("owner0000", &[]),
+        owner,
+        vec![PauseAction::Borrow],
+    )
+    .unwrap();
+    assert_not_paused(deps.as_ref().storage, PauseAction::Borrow).unwrap();
+    assert_eq!(
+        query_pause_info(deps.as_ref()).unwrap(),
+        PauseInfoResponse {
+            guardian: Some("guardian0000".to_string()),
+            paused: vec![PauseAction::Liquidate],
+        }
+    );
+}
+
+#[test]
+fn contract_version_checks() {
+    let mut deps = mock_dependencies(&[]);
+
+    // unversioned contracts can only be migrated from the legacy layout
+    assert_legacy(deps.as_ref().storage).unwrap();
+    match assert_upgrade(deps.as_ref().storage, "moneymarket-market", "0.2.0") {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "Contract version is missing; migrate from the legacy layout"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    set_contract_version(deps.as_mut().storage, "moneymarket-market", "0.2.0").unwrap();
+    match assert_legacy(deps.as_ref().storage) {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "Contract is already at version 0.2.0; legacy migration not allowed"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    assert_upgrade(deps.as_ref().storage, "moneymarket-market", "0.2.0").unwrap();
+    assert_upgrade(deps.as_ref().storage, "moneymarket-market", "0.10.0").unwrap();
+
+    match assert_upgrade(deps.as_ref().storage, "moneymarket-overseer", "0.2.0") {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "Cannot migrate moneymarket-market to moneymarket-overseer"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    match assert_upgrade(deps.as_ref().storage, "moneymarket-market", "0.1.9") {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "Cannot migrate from version 0.2.0 to older version 0.1.9"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn operator_approvals() {
+    let mut deps = mock_dependencies(&[]);
+    let env = mock_env();
+    let owner = deps.api.addr_canonicalize("owner0000").unwrap();
+    let operator = deps.api.addr_canonicalize("operator0000").unwrap();
+    let supported_permissions = [OperatorPermission::Repay, OperatorPermission::Borrow];
+
+    let res: StdResult<Response> = approve_operator(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner0000", &[]),
+        &supported_permissions,
+        "operator0000".to_string(),
+        vec![OperatorPermission::Unlock],
+        None,
+        None,
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Unsupported operator permission: unlock")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res: StdResult<Response> = approve_operator(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner0000", &[]),
+        &supported_permissions,
+        "operator0000".to_string(),
+        vec![OperatorPermission::Borrow],
+        None,
+        Some(env.block.time.seconds()),
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "Operator approval must expire after the current block time"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let expires_at = env.block.time.seconds() + 100;
+    let res: Response = approve_operator(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner0000", &[]),
+        &supported_permissions,
+        "operator0000".to_string(),
+        vec![OperatorPermission::Repay, OperatorPermission::Borrow],
+        Some(Uint256::from(1000u64)),
+        Some(expires_at),
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "approve_operator"),
+            attr("owner", "owner0000"),
+            attr("operator", "operator0000"),
+            attr("permissions", "repay,borrow"),
+        ]
+    );
+
+    // permissions are scoped
+    assert_operator(
+        deps.as_ref().storage,
+        &owner,
+        &operator,
+        OperatorPermission::Repay,
+        env.block.time.seconds(),
+    )
+    .unwrap();
+    match assert_operator(
+        deps.as_ref().storage,
+        &owner,
+        &operator,
+        OperatorPermission::Unlock,
+        env.block.time.seconds(),
+    ) {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "unauthorized"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // borrows are taken from the allowance
+    use_borrow_allowance(
+        deps.as_mut().storage,
+        &owner,
+        &operator,
+        Uint256::from(600u64),
+        env.block.time.seconds(),
+    )
+    .unwrap();
+    match use_borrow_allowance(
+        deps.as_mut().storage,
+        &owner,
+        &operator,
+        Uint256::from(401u64),
+        env.block.time.seconds(),
+    ) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Borrow amount exceeds the operator allowance: 400")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    assert_eq!(
+        query_operator_approvals(deps.as_ref(), "owner0000".to_string(), None, None).unwrap(),
+        OperatorApprovalsResponse {
+            approvals: vec![OperatorApprovalResponse {
+                owner: "owner0000".to_string(),
+                operator: "operator0000".to_string(),
+                permissions: vec![OperatorPermission::Repay, OperatorPermission::Borrow],
+                borrow_allowance: Uint256::from(400u64),
+                expires_at: Some(expires_at),
+            }],
+        }
+    );
+
+    match assert_operator(
+        deps.as_ref().storage,
+        &owner,
+        &operator,
+        OperatorPermission::Repay,
+        expires_at,
+    ) {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "Operator approval expired"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let _res: Response = revoke_operator(
+        deps.as_mut(),
+        mock_info("owner0000", &[]),
+        "operator0000".to_string(),
+    )
+    .unwrap();
+    match query_operator_approval(
+        deps.as_ref(),
+        "owner0000".to_string(),
+        "operator0000".to_string(),
+    ) {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "No operator approval"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}